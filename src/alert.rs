@@ -0,0 +1,227 @@
+use crate::theme::{Theme, RED, YELLOW};
+use regex::Regex;
+use std::fmt;
+
+/// Severity of a preset's current output
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub enum AlertLevel {
+    #[default]
+    Ok,
+    Warning,
+    Critical,
+}
+
+impl fmt::Display for AlertLevel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            AlertLevel::Ok => "ok",
+            AlertLevel::Warning => "warning",
+            AlertLevel::Critical => "critical",
+        };
+        f.write_str(name)
+    }
+}
+
+impl AlertLevel {
+    /// Theme to render with at this level, derived from the base theme
+    pub fn theme(self, base: Theme) -> Theme {
+        match self {
+            AlertLevel::Ok => base,
+            AlertLevel::Warning => base.with_foreground(YELLOW),
+            AlertLevel::Critical => base.with_foreground(RED),
+        }
+    }
+}
+
+/// A single threshold checked against command output
+#[derive(Debug, Clone)]
+pub enum Threshold {
+    /// First number in the output is >= value
+    Above(f64),
+    /// First number in the output is <= value
+    Below(f64),
+    /// Output matches the regex
+    Matches(Regex),
+}
+
+impl Threshold {
+    /// Parse a threshold spec:
+    /// - `90` or `>90` - numeric, triggers at or above 90
+    /// - `<10` - numeric, triggers at or below 10
+    /// - `/FAIL|ERROR/` - regex match on the output
+    pub fn parse(spec: &str) -> Result<Threshold, String> {
+        let spec = spec.trim();
+
+        if let Some(pattern) = spec
+            .strip_prefix('/')
+            .and_then(|rest| rest.strip_suffix('/'))
+        {
+            return Regex::new(pattern)
+                .map(Threshold::Matches)
+                .map_err(|e| format!("invalid regex '{}': {}", pattern, e));
+        }
+
+        let (below, number) = match spec.strip_prefix('<') {
+            Some(rest) => (true, rest),
+            None => (false, spec.strip_prefix('>').unwrap_or(spec)),
+        };
+        let value: f64 = number
+            .trim_start_matches('=')
+            .trim()
+            .parse()
+            .map_err(|_| format!("'{}' is not a number, <number, >number or /regex/", spec))?;
+
+        Ok(if below {
+            Threshold::Below(value)
+        } else {
+            Threshold::Above(value)
+        })
+    }
+
+    pub fn is_triggered(&self, output: &str) -> bool {
+        match self {
+            Threshold::Above(limit) => first_number(output).is_some_and(|v| v >= *limit),
+            Threshold::Below(limit) => first_number(output).is_some_and(|v| v <= *limit),
+            Threshold::Matches(re) => re.is_match(output),
+        }
+    }
+}
+
+/// Warning/critical thresholds for a preset
+#[derive(Debug, Clone, Default)]
+pub struct AlertRule {
+    pub warning: Option<Threshold>,
+    pub critical: Option<Threshold>,
+}
+
+impl AlertRule {
+    pub fn new(warning: Option<Threshold>, critical: Option<Threshold>) -> Self {
+        AlertRule { warning, critical }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.warning.is_none() && self.critical.is_none()
+    }
+
+    /// Evaluate output against the rule (critical wins over warning)
+    pub fn evaluate(&self, output: &str) -> AlertLevel {
+        if self
+            .critical
+            .as_ref()
+            .is_some_and(|t| t.is_triggered(output))
+        {
+            AlertLevel::Critical
+        } else if self
+            .warning
+            .as_ref()
+            .is_some_and(|t| t.is_triggered(output))
+        {
+            AlertLevel::Warning
+        } else {
+            AlertLevel::Ok
+        }
+    }
+}
+
+/// Tracks the alert level across updates and reports transitions
+#[derive(Debug, Clone, Default)]
+pub struct AlertMonitor {
+    rule: AlertRule,
+    level: AlertLevel,
+}
+
+impl AlertMonitor {
+    pub fn new(rule: AlertRule) -> Self {
+        AlertMonitor {
+            rule,
+            level: AlertLevel::Ok,
+        }
+    }
+
+    pub fn level(&self) -> AlertLevel {
+        self.level
+    }
+
+    /// Evaluate new output. Returns `Some((from, to))` when the level changed.
+    pub fn update(&mut self, output: &str) -> Option<(AlertLevel, AlertLevel)> {
+        let previous = self.level;
+        self.level = self.rule.evaluate(output);
+        (previous != self.level).then_some((previous, self.level))
+    }
+}
+
+/// Extract the first decimal number from text, e.g. "CPU: 12.5%" -> 12.5
+fn first_number(text: &str) -> Option<f64> {
+    let start = text.find(|c: char| c.is_ascii_digit())?;
+    let negative = text[..start].ends_with('-');
+    let digits: String = text[start..]
+        .chars()
+        .take_while(|c| c.is_ascii_digit() || *c == '.')
+        .collect();
+    let value: f64 = digits.trim_end_matches('.').parse().ok()?;
+    Some(if negative { -value } else { value })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_numeric_thresholds() {
+        assert!(matches!(Threshold::parse("90"), Ok(Threshold::Above(v)) if v == 90.0));
+        assert!(matches!(Threshold::parse(">=75.5"), Ok(Threshold::Above(v)) if v == 75.5));
+        assert!(matches!(Threshold::parse("<10"), Ok(Threshold::Below(v)) if v == 10.0));
+    }
+
+    #[test]
+    fn test_parse_regex_threshold() {
+        let threshold = Threshold::parse("/FAIL|ERROR/").unwrap();
+        assert!(threshold.is_triggered("build: FAILED"));
+        assert!(!threshold.is_triggered("build: passed"));
+    }
+
+    #[test]
+    fn test_parse_invalid_threshold() {
+        assert!(Threshold::parse("high").is_err());
+        assert!(Threshold::parse("/(/").is_err());
+    }
+
+    #[test]
+    fn test_first_number() {
+        assert_eq!(first_number("CPU: 12.5%"), Some(12.5));
+        assert_eq!(first_number("Disk: 91%"), Some(91.0));
+        assert_eq!(first_number("temp -4 C"), Some(-4.0));
+        assert_eq!(first_number("no digits"), None);
+    }
+
+    #[test]
+    fn test_rule_critical_wins() {
+        let rule = AlertRule::new(Some(Threshold::Above(70.0)), Some(Threshold::Above(90.0)));
+        assert_eq!(rule.evaluate("CPU: 12%"), AlertLevel::Ok);
+        assert_eq!(rule.evaluate("CPU: 75%"), AlertLevel::Warning);
+        assert_eq!(rule.evaluate("CPU: 95%"), AlertLevel::Critical);
+    }
+
+    #[test]
+    fn test_monitor_reports_transitions_once() {
+        let mut monitor = AlertMonitor::new(AlertRule::new(None, Some(Threshold::Above(80.0))));
+        assert_eq!(monitor.update("Disk: 50%"), None);
+        assert_eq!(
+            monitor.update("Disk: 85%"),
+            Some((AlertLevel::Ok, AlertLevel::Critical))
+        );
+        assert_eq!(monitor.update("Disk: 86%"), None);
+        assert_eq!(
+            monitor.update("Disk: 20%"),
+            Some((AlertLevel::Critical, AlertLevel::Ok))
+        );
+    }
+
+    #[test]
+    fn test_level_theme_colors() {
+        let base = Theme::default();
+        assert_eq!(AlertLevel::Ok.theme(base), base);
+        assert_eq!(AlertLevel::Warning.theme(base).foreground, YELLOW);
+        assert_eq!(AlertLevel::Critical.theme(base).foreground, RED);
+    }
+}
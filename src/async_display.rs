@@ -0,0 +1,314 @@
+use crate::display::DisplayError;
+use crate::driver::{select_driver, Capabilities, DisplayDriver};
+use crate::framebuffer::Framebuffer;
+use crate::image::{image_to_panel_bytes, Orientation};
+use crate::panel::{PanelSpec, Rect};
+use crate::port::{PortError, PortInfo, SerialSettings};
+use crate::protocol::{ProtocolError, ReplyParser, Step};
+use crate::quantize::ColorOptions;
+use image::RgbImage;
+use std::collections::VecDeque;
+use std::io;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::time::{sleep_until, timeout_at, Instant};
+use tokio_serial::{SerialPortBuilderExt, SerialStream};
+
+/// A byte stream to a display for the async API
+pub trait AsyncConnection: AsyncRead + AsyncWrite + Unpin + Send {}
+
+impl<T: AsyncRead + AsyncWrite + Unpin + Send> AsyncConnection for T {}
+
+/// Open the serial port of `port` for async use. [`crate::BaudRate::Auto`]
+/// opens at the default rate; probing is only done by the blocking API.
+pub fn open_async_connection(
+    port: &PortInfo,
+    settings: &SerialSettings,
+) -> Result<SerialStream, PortError> {
+    let connection = tokio_serial::new(&port.name, settings.baud_rate.rate())
+        .flow_control(settings.flow_control.into())
+        .timeout(settings.timeout)
+        .open_native_async()?;
+    Ok(connection)
+}
+
+/// An open display driven from async code, without blocking the runtime.
+///
+/// Sends are cancellation-safe: the steps of an operation are queued before
+/// any is sent, and progress is kept across awaits. If a send is dropped
+/// part way (e.g. by a timeout), the next call first finishes it, so the
+/// device never sees a half-written command followed by a new one.
+pub struct AsyncDisplay<C> {
+    connection: C,
+    driver: &'static dyn DisplayDriver,
+    pending: VecDeque<Step>,
+    /// Bytes of the first pending write already sent
+    written: usize,
+    /// When the settle step being waited on ends
+    settle_until: Option<Instant>,
+    ack: Option<ReplyParser>,
+    /// What has been queued so far; queued steps are always sent, even if
+    /// the call that queued them is cancelled
+    framebuffer: Option<Framebuffer>,
+}
+
+impl AsyncDisplay<SerialStream> {
+    /// Open the display on `port`, with `driver` or else the one matching
+    /// the port's VID/PID
+    pub async fn open(
+        port: &PortInfo,
+        driver: Option<&'static dyn DisplayDriver>,
+        settings: &SerialSettings,
+    ) -> Result<Self, DisplayError> {
+        let driver = select_driver(port, driver);
+        let connection = open_async_connection(port, settings)?;
+        Ok(AsyncDisplay::new(connection, driver).await?)
+    }
+}
+
+impl<C: AsyncConnection> AsyncDisplay<C> {
+    /// Drive an already open connection
+    pub async fn new(
+        connection: C,
+        driver: &'static dyn DisplayDriver,
+    ) -> Result<Self, ProtocolError> {
+        let mut display = AsyncDisplay {
+            connection,
+            driver,
+            pending: VecDeque::new(),
+            written: 0,
+            settle_until: None,
+            ack: None,
+            framebuffer: None,
+        };
+        display.run(driver.init_steps()).await?;
+        Ok(display)
+    }
+
+    pub fn driver(&self) -> &'static dyn DisplayDriver {
+        self.driver
+    }
+
+    pub fn capabilities(&self) -> Capabilities {
+        self.driver.capabilities()
+    }
+
+    pub fn panel(&self) -> PanelSpec {
+        self.capabilities().panel
+    }
+
+    /// What the panel shows, as far as this connection has drawn it
+    pub fn framebuffer(&self) -> Option<&Framebuffer> {
+        self.framebuffer.as_ref()
+    }
+
+    /// Send a full frame already converted for this panel
+    pub async fn send_frame(
+        &mut self,
+        data: &[u8],
+        orientation: Orientation,
+    ) -> Result<(), ProtocolError> {
+        let steps = self.driver.frame_steps(data, orientation)?;
+        self.flush_pending().await?;
+        let panel = self.panel();
+        self.framebuffer
+            .get_or_insert_with(|| Framebuffer::new(panel))
+            .set_frame(data, orientation);
+        self.pending.extend(steps);
+        self.flush_pending().await
+    }
+
+    /// Convert and send an image drawn in `orientation`
+    pub async fn send_image(
+        &mut self,
+        img: &RgbImage,
+        orientation: Orientation,
+        colors: ColorOptions,
+    ) -> Result<(), ProtocolError> {
+        let data = image_to_panel_bytes(img, orientation, colors, self.panel());
+        self.send_frame(&data, orientation).await
+    }
+
+    /// Draw pixel data into a window of the panel
+    pub async fn blit(&mut self, rect: Rect, data: &[u8]) -> Result<(), ProtocolError> {
+        let steps = self.driver.blit_steps(rect, data)?;
+        self.flush_pending().await?;
+        let panel = self.panel();
+        self.framebuffer
+            .get_or_insert_with(|| Framebuffer::new(panel))
+            .blit(rect, data);
+        self.pending.extend(steps);
+        self.flush_pending().await
+    }
+
+    /// Set backlight brightness (0 = off, 255 = full)
+    pub async fn set_brightness(&mut self, level: u8) -> Result<(), ProtocolError> {
+        let steps = self.driver.brightness_steps(level);
+        self.run(steps).await
+    }
+
+    /// Finish sending whatever a cancelled call left unsent
+    pub async fn flush_pending(&mut self) -> Result<(), ProtocolError> {
+        let result = self.drive().await;
+        if result.is_err() {
+            // After an I/O error the device state is unknown; don't resend
+            // the rest of the operation on the next call
+            self.pending.clear();
+            self.written = 0;
+            self.settle_until = None;
+            self.ack = None;
+        }
+        result
+    }
+
+    async fn run(&mut self, steps: Vec<Step>) -> Result<(), ProtocolError> {
+        self.flush_pending().await?;
+        self.pending.extend(steps);
+        self.flush_pending().await
+    }
+
+    async fn drive(&mut self) -> Result<(), ProtocolError> {
+        while let Some(step) = self.pending.front() {
+            match step {
+                Step::Write(bytes) => {
+                    while self.written < bytes.len() {
+                        let count = self.connection.write(&bytes[self.written..]).await?;
+                        if count == 0 {
+                            return Err(io::Error::from(io::ErrorKind::WriteZero).into());
+                        }
+                        self.written += count;
+                    }
+                }
+                &Step::Settle { command, delay } => {
+                    self.connection.flush().await?;
+                    let deadline = *self
+                        .settle_until
+                        .get_or_insert_with(|| Instant::now() + delay);
+                    let parser = self.ack.get_or_insert_with(|| ReplyParser::new(command));
+                    let mut byte = [0u8; 1];
+                    loop {
+                        match timeout_at(deadline, self.connection.read(&mut byte)).await {
+                            Err(_) => break,
+                            Ok(Ok(0)) => {
+                                sleep_until(deadline).await;
+                                break;
+                            }
+                            Ok(Ok(_)) => {
+                                if parser.push(byte[0]).is_some() {
+                                    break;
+                                }
+                            }
+                            Ok(Err(e)) => return Err(e.into()),
+                        }
+                    }
+                    self.settle_until = None;
+                    self.ack = None;
+                }
+            }
+            self.pending.pop_front();
+            self.written = 0;
+        }
+        self.connection.flush().await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::driver::WeActFsV1;
+    use std::time::Duration;
+    use tokio::io::duplex;
+    use tokio::time::timeout;
+
+    fn frame_bytes(data: &[u8]) -> Vec<u8> {
+        WeActFsV1
+            .frame_steps(data, Orientation::Landscape)
+            .unwrap()
+            .into_iter()
+            .filter_map(|step| match step {
+                Step::Write(bytes) => Some(bytes),
+                Step::Settle { .. } => None,
+            })
+            .flatten()
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_send_frame_matches_blocking_encoding() {
+        let (client, mut device) = duplex(64 * 1024);
+        let mut display = AsyncDisplay::new(client, &WeActFsV1).await.unwrap();
+        let data = vec![0x5A; display.panel().frame_bytes()];
+        display
+            .send_frame(&data, Orientation::Landscape)
+            .await
+            .unwrap();
+        drop(display);
+
+        let mut received = Vec::new();
+        device.read_to_end(&mut received).await.unwrap();
+        assert_eq!(received, frame_bytes(&data));
+    }
+
+    #[tokio::test]
+    async fn test_cancelled_send_is_finished_first() {
+        // A small pipe that nobody reads yet, so the first send stalls
+        let (client, mut device) = duplex(256);
+        let mut display = AsyncDisplay::new(client, &WeActFsV1).await.unwrap();
+        let first = vec![0x11; display.panel().frame_bytes()];
+        let second = vec![0x22; display.panel().frame_bytes()];
+
+        let cancelled = timeout(
+            Duration::from_millis(20),
+            display.send_frame(&first, Orientation::Landscape),
+        )
+        .await;
+        assert!(cancelled.is_err());
+
+        let reader = tokio::spawn(async move {
+            let mut received = Vec::new();
+            device.read_to_end(&mut received).await.unwrap();
+            received
+        });
+        display
+            .send_frame(&second, Orientation::Landscape)
+            .await
+            .unwrap();
+        drop(display);
+
+        let received = reader.await.unwrap();
+        assert_eq!(
+            received,
+            [frame_bytes(&first), frame_bytes(&second)].concat()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_framebuffer_includes_cancelled_send() {
+        let (client, _device) = duplex(256);
+        let mut display = AsyncDisplay::new(client, &WeActFsV1).await.unwrap();
+        let frame = vec![0x33; display.panel().frame_bytes()];
+        let cancelled = timeout(
+            Duration::from_millis(20),
+            display.send_frame(&frame, Orientation::Landscape),
+        )
+        .await;
+        assert!(cancelled.is_err());
+        assert_eq!(display.framebuffer().unwrap().data, frame);
+    }
+
+    #[tokio::test]
+    async fn test_invalid_blit_sends_nothing() {
+        let (client, mut device) = duplex(1024);
+        let mut display = AsyncDisplay::new(client, &WeActFsV1).await.unwrap();
+        let result = display.blit(Rect::new(0, 0, 200, 1), &[0; 400]).await;
+        assert!(matches!(
+            result,
+            Err(ProtocolError::WindowOutOfBounds { .. })
+        ));
+        drop(display);
+
+        let mut received = Vec::new();
+        device.read_to_end(&mut received).await.unwrap();
+        assert!(received.is_empty());
+    }
+}
@@ -0,0 +1,1071 @@
+use crate::font::FontError;
+use std::collections::HashMap;
+
+/// Names accepted by [`BitmapFont::builtin`]
+pub const BUILTIN_BITMAP_FONTS: [&str; 3] = ["5x7", "3x5", "8x16"];
+
+/// Largest glyph width or height accepted from a font file
+const MAX_GLYPH_SIZE: u32 = 256;
+
+/// A single glyph of a bitmap font, one bool per pixel (row-major)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BitmapGlyph {
+    pub width: u32,
+    pub height: u32,
+    /// Offset of the bitmap's left edge from the pen position
+    pub x_offset: i32,
+    /// Distance from the baseline up to the bitmap's top row
+    pub top: i32,
+    /// Horizontal pen advance in pixels
+    pub advance: u32,
+    pixels: Vec<bool>,
+}
+
+impl BitmapGlyph {
+    pub fn is_set(&self, x: u32, y: u32) -> bool {
+        x < self.width && y < self.height && self.pixels[(y * self.width + x) as usize]
+    }
+
+    /// Tight box around the set pixels as (min_x, min_y, max_x, max_y),
+    /// exclusive of the max edges, or `None` for blank glyphs
+    pub fn ink_rect(&self) -> Option<(u32, u32, u32, u32)> {
+        let mut rect: Option<(u32, u32, u32, u32)> = None;
+        for y in 0..self.height {
+            for x in 0..self.width {
+                if self.is_set(x, y) {
+                    rect = Some(match rect {
+                        Some((x0, y0, x1, y1)) => {
+                            (x0.min(x), y0.min(y), x1.max(x + 1), y1.max(y + 1))
+                        }
+                        None => (x, y, x + 1, y + 1),
+                    });
+                }
+            }
+        }
+        rect
+    }
+}
+
+/// A fixed-size pixel font, drawn without antialiasing at integer scales
+#[derive(Debug, Clone)]
+pub struct BitmapFont {
+    ascent: u32,
+    descent: u32,
+    glyphs: HashMap<char, BitmapGlyph>,
+}
+
+impl BitmapFont {
+    /// Built-in fonts: `5x7` (6x8 cell), `3x5` (4x6 cell, uppercase only)
+    /// and `8x16` (8x16 cell)
+    pub fn builtin(name: &str) -> Option<BitmapFont> {
+        match name {
+            "5x7" => Some(font_5x7()),
+            "3x5" => Some(font_3x5()),
+            "8x16" => Some(font_8x16()),
+            _ => None,
+        }
+    }
+
+    /// Parse a BDF (Glyph Bitmap Distribution Format) font.
+    /// Encodings are treated as Unicode codepoints.
+    pub fn parse_bdf(source: &str) -> Result<BitmapFont, FontError> {
+        let invalid = |msg: &str| FontError::InvalidBitmapFont(format!("BDF: {}", msg));
+
+        let mut lines = source.lines().map(str::trim);
+        if !lines
+            .next()
+            .is_some_and(|first| first.starts_with("STARTFONT"))
+        {
+            return Err(invalid("missing STARTFONT"));
+        }
+
+        let mut ascent: Option<u32> = None;
+        let mut descent: Option<u32> = None;
+        let mut bounding_box: Option<(i32, i32, i32, i32)> = None;
+        let mut glyphs = HashMap::new();
+
+        while let Some(line) = lines.next() {
+            let mut fields = line.split_whitespace();
+            match fields.next() {
+                Some("FONT_ASCENT") => ascent = fields.next().and_then(|v| v.parse().ok()),
+                Some("FONT_DESCENT") => descent = fields.next().and_then(|v| v.parse().ok()),
+                Some("FONTBOUNDINGBOX") => bounding_box = parse_four(fields),
+                Some("STARTCHAR") => {
+                    let mut encoding: Option<u32> = None;
+                    let mut advance: Option<u32> = None;
+                    let mut bbx = bounding_box;
+                    let mut rows: Vec<Vec<u8>> = Vec::new();
+                    let mut in_bitmap = false;
+
+                    for line in lines.by_ref() {
+                        let mut fields = line.split_whitespace();
+                        match fields.next() {
+                            Some("ENDCHAR") => break,
+                            Some(hex) if in_bitmap => {
+                                rows.push(parse_hex_row(hex).ok_or(invalid("bad bitmap row"))?)
+                            }
+                            Some("ENCODING") => {
+                                encoding = fields.next().and_then(|v| v.parse().ok())
+                            }
+                            Some("DWIDTH") => advance = fields.next().and_then(|v| v.parse().ok()),
+                            Some("BBX") => bbx = parse_four(fields),
+                            Some("BITMAP") => in_bitmap = true,
+                            _ => {}
+                        }
+                    }
+
+                    let (width, height, x_offset, y_offset) =
+                        bbx.ok_or(invalid("glyph without BBX"))?;
+                    let Some(c) = encoding.and_then(char::from_u32) else {
+                        continue;
+                    };
+                    let (width, height) = (width.max(0) as u32, height.max(0) as u32);
+                    if width > MAX_GLYPH_SIZE || height > MAX_GLYPH_SIZE {
+                        return Err(invalid("glyph too large"));
+                    }
+                    let top = y_offset
+                        .checked_add(height as i32)
+                        .ok_or(invalid("bad BBX"))?;
+                    let pixels = (0..height)
+                        .flat_map(|y| {
+                            let row = rows.get(y as usize);
+                            (0..width).map(move |x| row.is_some_and(|r| bit_msb(r, x)))
+                        })
+                        .collect();
+
+                    glyphs.insert(
+                        c,
+                        BitmapGlyph {
+                            width,
+                            height,
+                            x_offset,
+                            top,
+                            advance: advance.unwrap_or(width),
+                            pixels,
+                        },
+                    );
+                }
+                _ => {}
+            }
+        }
+
+        // Fall back to the bounding box when the ascent/descent properties are missing
+        let (_, box_height, _, box_y) = bounding_box.unwrap_or((0, 0, 0, 0));
+        let ascent = ascent.unwrap_or(box_height.saturating_add(box_y).max(0) as u32);
+        let descent = descent.unwrap_or(box_y.saturating_neg().max(0) as u32);
+
+        if glyphs.is_empty() {
+            return Err(invalid("no glyphs"));
+        }
+        Ok(BitmapFont {
+            ascent,
+            descent,
+            glyphs,
+        })
+    }
+
+    /// Parse an (uncompressed) X11 PCF font
+    pub fn parse_pcf(data: &[u8]) -> Result<BitmapFont, FontError> {
+        pcf::parse(data)
+    }
+
+    pub fn ascent(&self) -> u32 {
+        self.ascent
+    }
+
+    pub fn descent(&self) -> u32 {
+        self.descent
+    }
+
+    /// Native line height in pixels (at scale 1)
+    pub fn line_height(&self) -> u32 {
+        self.ascent.saturating_add(self.descent)
+    }
+
+    pub fn glyph(&self, c: char) -> Option<&BitmapGlyph> {
+        self.glyphs.get(&c)
+    }
+
+    /// Largest integer scale whose line height fits `font_size` (at least 1)
+    pub fn scale_for(&self, font_size: f32) -> u32 {
+        let native = self.line_height().max(1) as f32;
+        (font_size / native).floor().max(1.0) as u32
+    }
+}
+
+fn parse_four<'a>(mut fields: impl Iterator<Item = &'a str>) -> Option<(i32, i32, i32, i32)> {
+    let mut next = || fields.next()?.parse::<i32>().ok();
+    Some((next()?, next()?, next()?, next()?))
+}
+
+fn parse_hex_row(hex: &str) -> Option<Vec<u8>> {
+    // Byte slicing below needs ASCII
+    if !hex.is_ascii() || !hex.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).ok())
+        .collect()
+}
+
+/// Bit `x` of a row whose leftmost pixel is the most significant bit of byte 0
+fn bit_msb(row: &[u8], x: u32) -> bool {
+    row.get((x / 8) as usize)
+        .is_some_and(|byte| byte & (0x80 >> (x % 8)) != 0)
+}
+
+mod pcf {
+    use super::{bit_msb, BitmapFont, BitmapGlyph, MAX_GLYPH_SIZE};
+    use crate::font::FontError;
+    use std::collections::HashMap;
+
+    const PCF_ACCELERATORS: u32 = 1 << 1;
+    const PCF_METRICS: u32 = 1 << 2;
+    const PCF_BITMAPS: u32 = 1 << 3;
+    const PCF_BDF_ENCODINGS: u32 = 1 << 5;
+    const PCF_BDF_ACCELERATORS: u32 = 1 << 8;
+
+    const PCF_GLYPH_PAD_MASK: u32 = 3;
+    const PCF_BYTE_MASK: u32 = 1 << 2;
+    const PCF_BIT_MASK: u32 = 1 << 3;
+    const PCF_SCAN_UNIT_MASK: u32 = 3 << 4;
+    const PCF_COMPRESSED_METRICS: u32 = 0x100;
+
+    fn invalid(msg: &str) -> FontError {
+        FontError::InvalidBitmapFont(format!("PCF: {}", msg))
+    }
+
+    /// Reads integers from a table in the table's byte order
+    struct Reader<'a> {
+        data: &'a [u8],
+        pos: usize,
+        big_endian: bool,
+    }
+
+    impl<'a> Reader<'a> {
+        fn bytes(&mut self, n: usize) -> Result<&'a [u8], FontError> {
+            let end = self
+                .pos
+                .checked_add(n)
+                .ok_or_else(|| invalid("truncated table"))?;
+            let bytes = self
+                .data
+                .get(self.pos..end)
+                .ok_or_else(|| invalid("truncated table"))?;
+            self.pos = end;
+            Ok(bytes)
+        }
+
+        fn u8(&mut self) -> Result<u8, FontError> {
+            Ok(self.bytes(1)?[0])
+        }
+
+        fn i16(&mut self) -> Result<i16, FontError> {
+            let b: [u8; 2] = self.bytes(2)?.try_into().unwrap();
+            Ok(if self.big_endian {
+                i16::from_be_bytes(b)
+            } else {
+                i16::from_le_bytes(b)
+            })
+        }
+
+        fn i32(&mut self) -> Result<i32, FontError> {
+            let b: [u8; 4] = self.bytes(4)?.try_into().unwrap();
+            Ok(if self.big_endian {
+                i32::from_be_bytes(b)
+            } else {
+                i32::from_le_bytes(b)
+            })
+        }
+    }
+
+    struct Metric {
+        left: i32,
+        right: i32,
+        advance: i32,
+        ascent: i32,
+        descent: i32,
+    }
+
+    /// Locate a table and return (format, reader positioned after the format word)
+    fn table<'a>(data: &'a [u8], kind: u32) -> Result<Option<(u32, Reader<'a>)>, FontError> {
+        let mut toc = Reader {
+            data,
+            pos: 4,
+            big_endian: false,
+        };
+        let count = toc.i32()?;
+        for _ in 0..count {
+            let entry_kind = toc.i32()? as u32;
+            let _format = toc.i32()?;
+            let _size = toc.i32()?;
+            let offset =
+                usize::try_from(toc.i32()?).map_err(|_| invalid("negative table offset"))?;
+            if entry_kind == kind {
+                let mut reader = Reader {
+                    data,
+                    pos: offset,
+                    big_endian: false,
+                };
+                let format = reader.i32()? as u32;
+                reader.big_endian = format & PCF_BYTE_MASK != 0;
+                return Ok(Some((format, reader)));
+            }
+        }
+        Ok(None)
+    }
+
+    fn metrics(data: &[u8]) -> Result<Vec<Metric>, FontError> {
+        let (format, mut r) = table(data, PCF_METRICS)?.ok_or_else(|| invalid("no metrics"))?;
+        let mut metrics = Vec::new();
+        if format & PCF_COMPRESSED_METRICS != 0 {
+            let count = r.i16()?;
+            for _ in 0..count {
+                let mut next = || -> Result<i32, FontError> { Ok(r.u8()? as i32 - 0x80) };
+                metrics.push(Metric {
+                    left: next()?,
+                    right: next()?,
+                    advance: next()?,
+                    ascent: next()?,
+                    descent: next()?,
+                });
+            }
+        } else {
+            let count = r.i32()?;
+            for _ in 0..count {
+                let mut next = || -> Result<i32, FontError> { Ok(r.i16()? as i32) };
+                metrics.push(Metric {
+                    left: next()?,
+                    right: next()?,
+                    advance: next()?,
+                    ascent: next()?,
+                    descent: next()?,
+                });
+                r.i16()?; // attributes
+            }
+        }
+        Ok(metrics)
+    }
+
+    /// Glyph bitmaps normalized to MSB-first bytes, plus the row padding in bytes
+    fn bitmaps(data: &[u8]) -> Result<(Vec<Vec<u8>>, usize), FontError> {
+        let (format, mut r) = table(data, PCF_BITMAPS)?.ok_or_else(|| invalid("no bitmaps"))?;
+        let count = r.i32()?.max(0) as usize;
+        let offsets = (0..count)
+            .map(|_| r.i32().map(|o| o.max(0) as usize))
+            .collect::<Result<Vec<_>, _>>()?;
+        let mut sizes = [0usize; 4];
+        for size in &mut sizes {
+            *size = r.i32()?.max(0) as usize;
+        }
+        let pad_index = (format & PCF_GLYPH_PAD_MASK) as usize;
+        let total = sizes[pad_index];
+        let blob = r.bytes(total)?;
+
+        let msb_bit_first = format & PCF_BIT_MASK != 0;
+        let msb_byte_first = format & PCF_BYTE_MASK != 0;
+        let scan_unit = 1usize << ((format & PCF_SCAN_UNIT_MASK) >> 4);
+
+        let glyphs = offsets
+            .iter()
+            .enumerate()
+            .map(|(i, &start)| {
+                let end = offsets.get(i + 1).copied().unwrap_or(total).min(total);
+                let mut bytes = blob.get(start..end).unwrap_or_default().to_vec();
+                if msb_bit_first != msb_byte_first && scan_unit > 1 {
+                    for unit in bytes.chunks_mut(scan_unit) {
+                        unit.reverse();
+                    }
+                }
+                if !msb_bit_first {
+                    for byte in &mut bytes {
+                        *byte = byte.reverse_bits();
+                    }
+                }
+                bytes
+            })
+            .collect();
+        Ok((glyphs, 1 << pad_index))
+    }
+
+    /// Map from character to glyph index
+    fn encodings(data: &[u8]) -> Result<HashMap<char, usize>, FontError> {
+        let (_, mut r) = table(data, PCF_BDF_ENCODINGS)?.ok_or_else(|| invalid("no encodings"))?;
+        let min_byte2 = r.i16()? as u32;
+        let max_byte2 = r.i16()? as u32;
+        let min_byte1 = r.i16()? as u32;
+        let max_byte1 = r.i16()? as u32;
+        let _default_char = r.i16()?;
+
+        let mut map = HashMap::new();
+        for byte1 in min_byte1..=max_byte1 {
+            for byte2 in min_byte2..=max_byte2 {
+                let index = r.i16()? as u16;
+                if index == 0xFFFF {
+                    continue;
+                }
+                if let Some(c) = char::from_u32((byte1 << 8) | byte2) {
+                    map.insert(c, index as usize);
+                }
+            }
+        }
+        Ok(map)
+    }
+
+    /// Font-wide ascent/descent from the accelerator table, if present
+    fn accelerators(data: &[u8]) -> Result<Option<(i32, i32)>, FontError> {
+        let table = match table(data, PCF_BDF_ACCELERATORS)? {
+            Some(t) => Some(t),
+            None => table(data, PCF_ACCELERATORS)?,
+        };
+        let Some((_, mut r)) = table else {
+            return Ok(None);
+        };
+        r.bytes(8)?; // flags and padding
+        Ok(Some((r.i32()?, r.i32()?)))
+    }
+
+    pub(super) fn parse(data: &[u8]) -> Result<BitmapFont, FontError> {
+        if !data.starts_with(b"\x01fcp") {
+            return Err(invalid("bad magic"));
+        }
+
+        let metrics = metrics(data)?;
+        let (bitmaps, pad) = bitmaps(data)?;
+        let encodings = encodings(data)?;
+
+        let mut glyphs = HashMap::new();
+        for (c, index) in encodings {
+            let (Some(m), Some(bits)) = (metrics.get(index), bitmaps.get(index)) else {
+                continue;
+            };
+            let width = m.right.checked_sub(m.left);
+            let height = m.ascent.checked_add(m.descent);
+            let (Some(width), Some(height)) = (width, height) else {
+                return Err(invalid("bad glyph metrics"));
+            };
+            let (width, height) = (width.max(0) as u32, height.max(0) as u32);
+            if width > MAX_GLYPH_SIZE || height > MAX_GLYPH_SIZE {
+                return Err(invalid("glyph too large"));
+            }
+            let row_bytes = (width as usize).div_ceil(8).div_ceil(pad) * pad;
+            let pixels = (0..height as usize)
+                .flat_map(|y| {
+                    let row = bits.get(y * row_bytes..(y + 1) * row_bytes);
+                    (0..width).map(move |x| row.is_some_and(|r| bit_msb(r, x)))
+                })
+                .collect();
+            glyphs.insert(
+                c,
+                BitmapGlyph {
+                    width,
+                    height,
+                    x_offset: m.left,
+                    top: m.ascent,
+                    advance: m.advance.max(0) as u32,
+                    pixels,
+                },
+            );
+        }
+
+        let (ascent, descent) = match accelerators(data)? {
+            Some(metrics) => metrics,
+            None => metrics
+                .iter()
+                .fold((0, 0), |(a, d), m| (a.max(m.ascent), d.max(m.descent))),
+        };
+
+        if glyphs.is_empty() {
+            return Err(invalid("no glyphs"));
+        }
+        Ok(BitmapFont {
+            ascent: ascent.max(0) as u32,
+            descent: descent.max(0) as u32,
+            glyphs,
+        })
+    }
+}
+
+/// Classic 5x7 LCD font, one byte per column (bit 0 = top row), ASCII 0x20-0x7E
+const FONT_5X7: [[u8; 5]; 95] = [
+    [0x00, 0x00, 0x00, 0x00, 0x00], // space
+    [0x00, 0x00, 0x5F, 0x00, 0x00], // !
+    [0x00, 0x07, 0x00, 0x07, 0x00], // "
+    [0x14, 0x7F, 0x14, 0x7F, 0x14], // #
+    [0x24, 0x2A, 0x7F, 0x2A, 0x12], // $
+    [0x23, 0x13, 0x08, 0x64, 0x62], // %
+    [0x36, 0x49, 0x55, 0x22, 0x50], // &
+    [0x00, 0x05, 0x03, 0x00, 0x00], // '
+    [0x00, 0x1C, 0x22, 0x41, 0x00], // (
+    [0x00, 0x41, 0x22, 0x1C, 0x00], // )
+    [0x14, 0x08, 0x3E, 0x08, 0x14], // *
+    [0x08, 0x08, 0x3E, 0x08, 0x08], // +
+    [0x00, 0x50, 0x30, 0x00, 0x00], // ,
+    [0x08, 0x08, 0x08, 0x08, 0x08], // -
+    [0x00, 0x60, 0x60, 0x00, 0x00], // .
+    [0x20, 0x10, 0x08, 0x04, 0x02], // /
+    [0x3E, 0x51, 0x49, 0x45, 0x3E], // 0
+    [0x00, 0x42, 0x7F, 0x40, 0x00], // 1
+    [0x42, 0x61, 0x51, 0x49, 0x46], // 2
+    [0x21, 0x41, 0x45, 0x4B, 0x31], // 3
+    [0x18, 0x14, 0x12, 0x7F, 0x10], // 4
+    [0x27, 0x45, 0x45, 0x45, 0x39], // 5
+    [0x3C, 0x4A, 0x49, 0x49, 0x30], // 6
+    [0x01, 0x71, 0x09, 0x05, 0x03], // 7
+    [0x36, 0x49, 0x49, 0x49, 0x36], // 8
+    [0x06, 0x49, 0x49, 0x29, 0x1E], // 9
+    [0x00, 0x36, 0x36, 0x00, 0x00], // :
+    [0x00, 0x56, 0x36, 0x00, 0x00], // ;
+    [0x08, 0x14, 0x22, 0x41, 0x00], // <
+    [0x14, 0x14, 0x14, 0x14, 0x14], // =
+    [0x00, 0x41, 0x22, 0x14, 0x08], // >
+    [0x02, 0x01, 0x51, 0x09, 0x06], // ?
+    [0x32, 0x49, 0x79, 0x41, 0x3E], // @
+    [0x7E, 0x11, 0x11, 0x11, 0x7E], // A
+    [0x7F, 0x49, 0x49, 0x49, 0x36], // B
+    [0x3E, 0x41, 0x41, 0x41, 0x22], // C
+    [0x7F, 0x41, 0x41, 0x22, 0x1C], // D
+    [0x7F, 0x49, 0x49, 0x49, 0x41], // E
+    [0x7F, 0x09, 0x09, 0x09, 0x01], // F
+    [0x3E, 0x41, 0x49, 0x49, 0x7A], // G
+    [0x7F, 0x08, 0x08, 0x08, 0x7F], // H
+    [0x00, 0x41, 0x7F, 0x41, 0x00], // I
+    [0x20, 0x40, 0x41, 0x3F, 0x01], // J
+    [0x7F, 0x08, 0x14, 0x22, 0x41], // K
+    [0x7F, 0x40, 0x40, 0x40, 0x40], // L
+    [0x7F, 0x02, 0x0C, 0x02, 0x7F], // M
+    [0x7F, 0x04, 0x08, 0x10, 0x7F], // N
+    [0x3E, 0x41, 0x41, 0x41, 0x3E], // O
+    [0x7F, 0x09, 0x09, 0x09, 0x06], // P
+    [0x3E, 0x41, 0x51, 0x21, 0x5E], // Q
+    [0x7F, 0x09, 0x19, 0x29, 0x46], // R
+    [0x46, 0x49, 0x49, 0x49, 0x31], // S
+    [0x01, 0x01, 0x7F, 0x01, 0x01], // T
+    [0x3F, 0x40, 0x40, 0x40, 0x3F], // U
+    [0x1F, 0x20, 0x40, 0x20, 0x1F], // V
+    [0x3F, 0x40, 0x38, 0x40, 0x3F], // W
+    [0x63, 0x14, 0x08, 0x14, 0x63], // X
+    [0x07, 0x08, 0x70, 0x08, 0x07], // Y
+    [0x61, 0x51, 0x49, 0x45, 0x43], // Z
+    [0x00, 0x7F, 0x41, 0x41, 0x00], // [
+    [0x02, 0x04, 0x08, 0x10, 0x20], // backslash
+    [0x00, 0x41, 0x41, 0x7F, 0x00], // ]
+    [0x04, 0x02, 0x01, 0x02, 0x04], // ^
+    [0x40, 0x40, 0x40, 0x40, 0x40], // _
+    [0x00, 0x01, 0x02, 0x04, 0x00], // `
+    [0x20, 0x54, 0x54, 0x54, 0x78], // a
+    [0x7F, 0x48, 0x44, 0x44, 0x38], // b
+    [0x38, 0x44, 0x44, 0x44, 0x20], // c
+    [0x38, 0x44, 0x44, 0x48, 0x7F], // d
+    [0x38, 0x54, 0x54, 0x54, 0x18], // e
+    [0x08, 0x7E, 0x09, 0x01, 0x02], // f
+    [0x0C, 0x52, 0x52, 0x52, 0x3E], // g
+    [0x7F, 0x08, 0x04, 0x04, 0x78], // h
+    [0x00, 0x44, 0x7D, 0x40, 0x00], // i
+    [0x20, 0x40, 0x44, 0x3D, 0x00], // j
+    [0x7F, 0x10, 0x28, 0x44, 0x00], // k
+    [0x00, 0x41, 0x7F, 0x40, 0x00], // l
+    [0x7C, 0x04, 0x18, 0x04, 0x78], // m
+    [0x7C, 0x08, 0x04, 0x04, 0x78], // n
+    [0x38, 0x44, 0x44, 0x44, 0x38], // o
+    [0x7C, 0x14, 0x14, 0x14, 0x08], // p
+    [0x08, 0x14, 0x14, 0x18, 0x7C], // q
+    [0x7C, 0x08, 0x04, 0x04, 0x08], // r
+    [0x48, 0x54, 0x54, 0x54, 0x20], // s
+    [0x04, 0x3F, 0x44, 0x40, 0x20], // t
+    [0x3C, 0x40, 0x40, 0x20, 0x7C], // u
+    [0x1C, 0x20, 0x40, 0x20, 0x1C], // v
+    [0x3C, 0x40, 0x30, 0x40, 0x3C], // w
+    [0x44, 0x28, 0x10, 0x28, 0x44], // x
+    [0x0C, 0x50, 0x50, 0x50, 0x3C], // y
+    [0x44, 0x64, 0x54, 0x4C, 0x44], // z
+    [0x00, 0x08, 0x36, 0x41, 0x00], // {
+    [0x00, 0x00, 0x7F, 0x00, 0x00], // |
+    [0x00, 0x41, 0x36, 0x08, 0x00], // }
+    [0x10, 0x08, 0x08, 0x10, 0x08], // ~
+];
+
+/// Tiny 3x5 font, one 3-bit row per entry (MSB = left), ASCII 0x20-0x60.
+/// Lowercase letters reuse the uppercase glyphs.
+const FONT_3X5: [[u8; 5]; 65] = [
+    [0b000, 0b000, 0b000, 0b000, 0b000], // space
+    [0b010, 0b010, 0b010, 0b000, 0b010], // !
+    [0b101, 0b101, 0b000, 0b000, 0b000], // "
+    [0b101, 0b111, 0b101, 0b111, 0b101], // #
+    [0b011, 0b110, 0b010, 0b011, 0b110], // $
+    [0b101, 0b001, 0b010, 0b100, 0b101], // %
+    [0b010, 0b101, 0b010, 0b101, 0b011], // &
+    [0b010, 0b010, 0b000, 0b000, 0b000], // '
+    [0b001, 0b010, 0b010, 0b010, 0b001], // (
+    [0b100, 0b010, 0b010, 0b010, 0b100], // )
+    [0b000, 0b101, 0b010, 0b101, 0b000], // *
+    [0b000, 0b010, 0b111, 0b010, 0b000], // +
+    [0b000, 0b000, 0b000, 0b010, 0b100], // ,
+    [0b000, 0b000, 0b111, 0b000, 0b000], // -
+    [0b000, 0b000, 0b000, 0b000, 0b010], // .
+    [0b001, 0b001, 0b010, 0b100, 0b100], // /
+    [0b111, 0b101, 0b101, 0b101, 0b111], // 0
+    [0b010, 0b110, 0b010, 0b010, 0b111], // 1
+    [0b110, 0b001, 0b010, 0b100, 0b111], // 2
+    [0b110, 0b001, 0b010, 0b001, 0b110], // 3
+    [0b101, 0b101, 0b111, 0b001, 0b001], // 4
+    [0b111, 0b100, 0b110, 0b001, 0b110], // 5
+    [0b011, 0b100, 0b111, 0b101, 0b111], // 6
+    [0b111, 0b001, 0b010, 0b010, 0b010], // 7
+    [0b111, 0b101, 0b111, 0b101, 0b111], // 8
+    [0b111, 0b101, 0b111, 0b001, 0b110], // 9
+    [0b000, 0b010, 0b000, 0b010, 0b000], // :
+    [0b000, 0b010, 0b000, 0b010, 0b100], // ;
+    [0b001, 0b010, 0b100, 0b010, 0b001], // <
+    [0b000, 0b111, 0b000, 0b111, 0b000], // =
+    [0b100, 0b010, 0b001, 0b010, 0b100], // >
+    [0b110, 0b001, 0b010, 0b000, 0b010], // ?
+    [0b010, 0b101, 0b111, 0b100, 0b011], // @
+    [0b010, 0b101, 0b111, 0b101, 0b101], // A
+    [0b110, 0b101, 0b110, 0b101, 0b110], // B
+    [0b011, 0b100, 0b100, 0b100, 0b011], // C
+    [0b110, 0b101, 0b101, 0b101, 0b110], // D
+    [0b111, 0b100, 0b110, 0b100, 0b111], // E
+    [0b111, 0b100, 0b110, 0b100, 0b100], // F
+    [0b011, 0b100, 0b101, 0b101, 0b011], // G
+    [0b101, 0b101, 0b111, 0b101, 0b101], // H
+    [0b111, 0b010, 0b010, 0b010, 0b111], // I
+    [0b001, 0b001, 0b001, 0b101, 0b010], // J
+    [0b101, 0b101, 0b110, 0b101, 0b101], // K
+    [0b100, 0b100, 0b100, 0b100, 0b111], // L
+    [0b101, 0b111, 0b111, 0b101, 0b101], // M
+    [0b110, 0b101, 0b101, 0b101, 0b101], // N
+    [0b010, 0b101, 0b101, 0b101, 0b010], // O
+    [0b110, 0b101, 0b110, 0b100, 0b100], // P
+    [0b010, 0b101, 0b101, 0b111, 0b011], // Q
+    [0b110, 0b101, 0b110, 0b101, 0b101], // R
+    [0b011, 0b100, 0b010, 0b001, 0b110], // S
+    [0b111, 0b010, 0b010, 0b010, 0b010], // T
+    [0b101, 0b101, 0b101, 0b101, 0b111], // U
+    [0b101, 0b101, 0b101, 0b101, 0b010], // V
+    [0b101, 0b101, 0b111, 0b111, 0b101], // W
+    [0b101, 0b101, 0b010, 0b101, 0b101], // X
+    [0b101, 0b101, 0b010, 0b010, 0b010], // Y
+    [0b111, 0b001, 0b010, 0b100, 0b111], // Z
+    [0b110, 0b100, 0b100, 0b100, 0b110], // [
+    [0b100, 0b100, 0b010, 0b001, 0b001], // backslash
+    [0b011, 0b001, 0b001, 0b001, 0b011], // ]
+    [0b010, 0b101, 0b000, 0b000, 0b000], // ^
+    [0b000, 0b000, 0b000, 0b000, 0b111], // _
+    [0b100, 0b010, 0b000, 0b000, 0b000], // `
+];
+
+/// Tail of the 3x5 font: ASCII 0x7B-0x7E
+const FONT_3X5_TAIL: [[u8; 5]; 4] = [
+    [0b011, 0b010, 0b100, 0b010, 0b011], // {
+    [0b010, 0b010, 0b010, 0b010, 0b010], // |
+    [0b110, 0b010, 0b001, 0b010, 0b110], // }
+    [0b000, 0b011, 0b110, 0b000, 0b000], // ~
+];
+
+/// 8x16 VGA-style font, one byte per row (MSB = left), ASCII 0x20-0x7E.
+/// Rows 0-11 sit above the baseline, 12-15 hold descenders.
+const FONT_8X16: [[u8; 16]; 95] = [
+    *b"\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00", // space
+    *b"\x00\x00\x18\x3C\x3C\x3C\x18\x18\x18\x00\x18\x18\x00\x00\x00\x00", // !
+    *b"\x00\x66\x66\x66\x24\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00", // "
+    *b"\x00\x00\x00\x6C\x6C\xFE\x6C\x6C\x6C\xFE\x6C\x6C\x00\x00\x00\x00", // #
+    *b"\x18\x18\x7C\xC6\xC2\xC0\x7C\x06\x06\x86\xC6\x7C\x18\x18\x00\x00", // $
+    *b"\x00\x00\x00\x00\xC2\xC6\x0C\x18\x30\x60\xC6\x86\x00\x00\x00\x00", // %
+    *b"\x00\x00\x38\x6C\x6C\x38\x76\xDC\xCC\xCC\xCC\x76\x00\x00\x00\x00", // &
+    *b"\x00\x30\x30\x30\x60\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00", // '
+    *b"\x00\x00\x0C\x18\x30\x30\x30\x30\x30\x30\x18\x0C\x00\x00\x00\x00", // (
+    *b"\x00\x00\x30\x18\x0C\x0C\x0C\x0C\x0C\x0C\x18\x30\x00\x00\x00\x00", // )
+    *b"\x00\x00\x00\x00\x00\x66\x3C\xFF\x3C\x66\x00\x00\x00\x00\x00\x00", // *
+    *b"\x00\x00\x00\x00\x00\x18\x18\x7E\x18\x18\x00\x00\x00\x00\x00\x00", // +
+    *b"\x00\x00\x00\x00\x00\x00\x00\x00\x00\x18\x18\x18\x30\x00\x00\x00", // ,
+    *b"\x00\x00\x00\x00\x00\x00\x00\xFE\x00\x00\x00\x00\x00\x00\x00\x00", // -
+    *b"\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x18\x18\x00\x00\x00\x00", // .
+    *b"\x00\x00\x00\x00\x02\x06\x0C\x18\x30\x60\xC0\x80\x00\x00\x00\x00", // /
+    *b"\x00\x00\x38\x6C\xC6\xC6\xD6\xD6\xC6\xC6\x6C\x38\x00\x00\x00\x00", // 0
+    *b"\x00\x00\x18\x38\x78\x18\x18\x18\x18\x18\x18\x7E\x00\x00\x00\x00", // 1
+    *b"\x00\x00\x7C\xC6\x06\x0C\x18\x30\x60\xC0\xC6\xFE\x00\x00\x00\x00", // 2
+    *b"\x00\x00\x7C\xC6\x06\x06\x3C\x06\x06\x06\xC6\x7C\x00\x00\x00\x00", // 3
+    *b"\x00\x00\x0C\x1C\x3C\x6C\xCC\xFE\x0C\x0C\x0C\x1E\x00\x00\x00\x00", // 4
+    *b"\x00\x00\xFE\xC0\xC0\xC0\xFC\x06\x06\x06\xC6\x7C\x00\x00\x00\x00", // 5
+    *b"\x00\x00\x38\x60\xC0\xC0\xFC\xC6\xC6\xC6\xC6\x7C\x00\x00\x00\x00", // 6
+    *b"\x00\x00\xFE\xC6\x06\x06\x0C\x18\x30\x30\x30\x30\x00\x00\x00\x00", // 7
+    *b"\x00\x00\x7C\xC6\xC6\xC6\x7C\xC6\xC6\xC6\xC6\x7C\x00\x00\x00\x00", // 8
+    *b"\x00\x00\x7C\xC6\xC6\xC6\x7E\x06\x06\x06\x0C\x78\x00\x00\x00\x00", // 9
+    *b"\x00\x00\x00\x00\x18\x18\x00\x00\x00\x18\x18\x00\x00\x00\x00\x00", // :
+    *b"\x00\x00\x00\x00\x18\x18\x00\x00\x00\x18\x18\x30\x00\x00\x00\x00", // ;
+    *b"\x00\x00\x00\x06\x0C\x18\x30\x60\x30\x18\x0C\x06\x00\x00\x00\x00", // <
+    *b"\x00\x00\x00\x00\x00\x7E\x00\x00\x7E\x00\x00\x00\x00\x00\x00\x00", // =
+    *b"\x00\x00\x00\x60\x30\x18\x0C\x06\x0C\x18\x30\x60\x00\x00\x00\x00", // >
+    *b"\x00\x00\x7C\xC6\xC6\x0C\x18\x18\x18\x00\x18\x18\x00\x00\x00\x00", // ?
+    *b"\x00\x00\x00\x7C\xC6\xC6\xDE\xDE\xDE\xDC\xC0\x7C\x00\x00\x00\x00", // @
+    *b"\x00\x00\x10\x38\x6C\xC6\xC6\xFE\xC6\xC6\xC6\xC6\x00\x00\x00\x00", // A
+    *b"\x00\x00\xFC\x66\x66\x66\x7C\x66\x66\x66\x66\xFC\x00\x00\x00\x00", // B
+    *b"\x00\x00\x3C\x66\xC2\xC0\xC0\xC0\xC0\xC2\x66\x3C\x00\x00\x00\x00", // C
+    *b"\x00\x00\xF8\x6C\x66\x66\x66\x66\x66\x66\x6C\xF8\x00\x00\x00\x00", // D
+    *b"\x00\x00\xFE\x66\x62\x68\x78\x68\x60\x62\x66\xFE\x00\x00\x00\x00", // E
+    *b"\x00\x00\xFE\x66\x62\x68\x78\x68\x60\x60\x60\xF0\x00\x00\x00\x00", // F
+    *b"\x00\x00\x3C\x66\xC2\xC0\xC0\xDE\xC6\xC6\x66\x3A\x00\x00\x00\x00", // G
+    *b"\x00\x00\xC6\xC6\xC6\xC6\xFE\xC6\xC6\xC6\xC6\xC6\x00\x00\x00\x00", // H
+    *b"\x00\x00\x3C\x18\x18\x18\x18\x18\x18\x18\x18\x3C\x00\x00\x00\x00", // I
+    *b"\x00\x00\x1E\x0C\x0C\x0C\x0C\x0C\xCC\xCC\xCC\x78\x00\x00\x00\x00", // J
+    *b"\x00\x00\xE6\x66\x66\x6C\x78\x78\x6C\x66\x66\xE6\x00\x00\x00\x00", // K
+    *b"\x00\x00\xF0\x60\x60\x60\x60\x60\x60\x62\x66\xFE\x00\x00\x00\x00", // L
+    *b"\x00\x00\xC6\xEE\xFE\xFE\xD6\xC6\xC6\xC6\xC6\xC6\x00\x00\x00\x00", // M
+    *b"\x00\x00\xC6\xE6\xF6\xFE\xDE\xCE\xC6\xC6\xC6\xC6\x00\x00\x00\x00", // N
+    *b"\x00\x00\x7C\xC6\xC6\xC6\xC6\xC6\xC6\xC6\xC6\x7C\x00\x00\x00\x00", // O
+    *b"\x00\x00\xFC\x66\x66\x66\x7C\x60\x60\x60\x60\xF0\x00\x00\x00\x00", // P
+    *b"\x00\x00\x7C\xC6\xC6\xC6\xC6\xC6\xC6\xD6\xDE\x7C\x0C\x0E\x00\x00", // Q
+    *b"\x00\x00\xFC\x66\x66\x66\x7C\x6C\x66\x66\x66\xE6\x00\x00\x00\x00", // R
+    *b"\x00\x00\x7C\xC6\xC6\x60\x38\x0C\x06\xC6\xC6\x7C\x00\x00\x00\x00", // S
+    *b"\x00\x00\x7E\x7E\x5A\x18\x18\x18\x18\x18\x18\x3C\x00\x00\x00\x00", // T
+    *b"\x00\x00\xC6\xC6\xC6\xC6\xC6\xC6\xC6\xC6\xC6\x7C\x00\x00\x00\x00", // U
+    *b"\x00\x00\xC6\xC6\xC6\xC6\xC6\xC6\xC6\x6C\x38\x10\x00\x00\x00\x00", // V
+    *b"\x00\x00\xC6\xC6\xC6\xC6\xD6\xD6\xD6\xFE\xEE\x6C\x00\x00\x00\x00", // W
+    *b"\x00\x00\xC6\xC6\x6C\x7C\x38\x38\x7C\x6C\xC6\xC6\x00\x00\x00\x00", // X
+    *b"\x00\x00\x66\x66\x66\x66\x3C\x18\x18\x18\x18\x3C\x00\x00\x00\x00", // Y
+    *b"\x00\x00\xFE\xC6\x86\x0C\x18\x30\x60\xC2\xC6\xFE\x00\x00\x00\x00", // Z
+    *b"\x00\x00\x3C\x30\x30\x30\x30\x30\x30\x30\x30\x3C\x00\x00\x00\x00", // [
+    *b"\x00\x00\x00\x80\xC0\xE0\x70\x38\x1C\x0E\x06\x02\x00\x00\x00\x00", // backslash
+    *b"\x00\x00\x3C\x0C\x0C\x0C\x0C\x0C\x0C\x0C\x0C\x3C\x00\x00\x00\x00", // ]
+    *b"\x10\x38\x6C\xC6\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00", // ^
+    *b"\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\xFF\x00\x00", // _
+    *b"\x30\x30\x18\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00", // `
+    *b"\x00\x00\x00\x00\x00\x78\x0C\x7C\xCC\xCC\xCC\x76\x00\x00\x00\x00", // a
+    *b"\x00\x00\xE0\x60\x60\x78\x6C\x66\x66\x66\x66\x7C\x00\x00\x00\x00", // b
+    *b"\x00\x00\x00\x00\x00\x7C\xC6\xC0\xC0\xC0\xC6\x7C\x00\x00\x00\x00", // c
+    *b"\x00\x00\x1C\x0C\x0C\x3C\x6C\xCC\xCC\xCC\xCC\x76\x00\x00\x00\x00", // d
+    *b"\x00\x00\x00\x00\x00\x7C\xC6\xFE\xC0\xC0\xC6\x7C\x00\x00\x00\x00", // e
+    *b"\x00\x00\x1C\x36\x32\x30\x78\x30\x30\x30\x30\x78\x00\x00\x00\x00", // f
+    *b"\x00\x00\x00\x00\x00\x76\xCC\xCC\xCC\xCC\xCC\x7C\x0C\xCC\x78\x00", // g
+    *b"\x00\x00\xE0\x60\x60\x6C\x76\x66\x66\x66\x66\xE6\x00\x00\x00\x00", // h
+    *b"\x00\x00\x18\x18\x00\x38\x18\x18\x18\x18\x18\x3C\x00\x00\x00\x00", // i
+    *b"\x00\x00\x06\x06\x00\x0E\x06\x06\x06\x06\x06\x06\x66\x66\x3C\x00", // j
+    *b"\x00\x00\xE0\x60\x60\x66\x6C\x78\x78\x6C\x66\xE6\x00\x00\x00\x00", // k
+    *b"\x00\x00\x38\x18\x18\x18\x18\x18\x18\x18\x18\x3C\x00\x00\x00\x00", // l
+    *b"\x00\x00\x00\x00\x00\xEC\xFE\xD6\xD6\xD6\xD6\xC6\x00\x00\x00\x00", // m
+    *b"\x00\x00\x00\x00\x00\xDC\x66\x66\x66\x66\x66\x66\x00\x00\x00\x00", // n
+    *b"\x00\x00\x00\x00\x00\x7C\xC6\xC6\xC6\xC6\xC6\x7C\x00\x00\x00\x00", // o
+    *b"\x00\x00\x00\x00\x00\xDC\x66\x66\x66\x66\x66\x7C\x60\x60\xF0\x00", // p
+    *b"\x00\x00\x00\x00\x00\x76\xCC\xCC\xCC\xCC\xCC\x7C\x0C\x0C\x1E\x00", // q
+    *b"\x00\x00\x00\x00\x00\xDC\x76\x66\x60\x60\x60\xF0\x00\x00\x00\x00", // r
+    *b"\x00\x00\x00\x00\x00\x7C\xC6\x60\x38\x0C\xC6\x7C\x00\x00\x00\x00", // s
+    *b"\x00\x00\x10\x30\x30\xFC\x30\x30\x30\x30\x36\x1C\x00\x00\x00\x00", // t
+    *b"\x00\x00\x00\x00\x00\xCC\xCC\xCC\xCC\xCC\xCC\x76\x00\x00\x00\x00", // u
+    *b"\x00\x00\x00\x00\x00\x66\x66\x66\x66\x66\x3C\x18\x00\x00\x00\x00", // v
+    *b"\x00\x00\x00\x00\x00\xC6\xC6\xD6\xD6\xD6\xFE\x6C\x00\x00\x00\x00", // w
+    *b"\x00\x00\x00\x00\x00\xC6\x6C\x38\x38\x38\x6C\xC6\x00\x00\x00\x00", // x
+    *b"\x00\x00\x00\x00\x00\xC6\xC6\xC6\xC6\xC6\xC6\x7E\x06\x0C\xF8\x00", // y
+    *b"\x00\x00\x00\x00\x00\xFE\xCC\x18\x30\x60\xC6\xFE\x00\x00\x00\x00", // z
+    *b"\x00\x00\x0E\x18\x18\x18\x70\x18\x18\x18\x18\x0E\x00\x00\x00\x00", // {
+    *b"\x00\x00\x18\x18\x18\x18\x18\x18\x18\x18\x18\x18\x00\x00\x00\x00", // |
+    *b"\x00\x00\x70\x18\x18\x18\x0E\x18\x18\x18\x18\x70\x00\x00\x00\x00", // }
+    *b"\x00\x00\x76\xDC\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00", // ~
+];
+
+fn font_5x7() -> BitmapFont {
+    let glyphs = FONT_5X7
+        .iter()
+        .enumerate()
+        .map(|(i, columns)| {
+            let c = char::from(0x20 + i as u8);
+            let pixels = (0..7)
+                .flat_map(|y| (0..5).map(move |x| columns[x] & (1 << y) != 0))
+                .collect();
+            let glyph = BitmapGlyph {
+                width: 5,
+                height: 7,
+                x_offset: 0,
+                top: 7,
+                advance: 6,
+                pixels,
+            };
+            (c, glyph)
+        })
+        .collect();
+    BitmapFont {
+        ascent: 7,
+        descent: 1,
+        glyphs,
+    }
+}
+
+fn font_3x5() -> BitmapFont {
+    let glyph = |rows: &[u8; 5]| BitmapGlyph {
+        width: 3,
+        height: 5,
+        x_offset: 0,
+        top: 5,
+        advance: 4,
+        pixels: (0..5)
+            .flat_map(|y| (0..3).map(move |x| rows[y] & (0b100 >> x) != 0))
+            .collect(),
+    };
+
+    let mut glyphs: HashMap<char, BitmapGlyph> = FONT_3X5
+        .iter()
+        .enumerate()
+        .map(|(i, rows)| (char::from(0x20 + i as u8), glyph(rows)))
+        .collect();
+    for (i, rows) in FONT_3X5_TAIL.iter().enumerate() {
+        glyphs.insert(char::from(0x7B + i as u8), glyph(rows));
+    }
+    for c in 'a'..='z' {
+        let upper = glyphs[&c.to_ascii_uppercase()].clone();
+        glyphs.insert(c, upper);
+    }
+
+    BitmapFont {
+        ascent: 5,
+        descent: 1,
+        glyphs,
+    }
+}
+
+fn font_8x16() -> BitmapFont {
+    let glyphs = FONT_8X16
+        .iter()
+        .enumerate()
+        .map(|(i, rows)| {
+            let c = char::from(0x20 + i as u8);
+            let pixels = (0..16)
+                .flat_map(|y| (0..8).map(move |x| rows[y] & (0x80 >> x) != 0))
+                .collect();
+            let glyph = BitmapGlyph {
+                width: 8,
+                height: 16,
+                x_offset: 0,
+                top: 12,
+                advance: 8,
+                pixels,
+            };
+            (c, glyph)
+        })
+        .collect();
+    BitmapFont {
+        ascent: 12,
+        descent: 4,
+        glyphs,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE_BDF: &str = "STARTFONT 2.1
+FONT -test-fixed-medium-r-normal--8-80-75-75-c-40-iso10646-1
+SIZE 8 75 75
+FONTBOUNDINGBOX 4 8 0 -2
+STARTPROPERTIES 2
+FONT_ASCENT 6
+FONT_DESCENT 2
+ENDPROPERTIES
+CHARS 2
+STARTCHAR A
+ENCODING 65
+SWIDTH 500 0
+DWIDTH 4 0
+BBX 3 3 0 0
+BITMAP
+40
+A0
+E0
+ENDCHAR
+STARTCHAR uni00E9
+ENCODING 233
+DWIDTH 5 0
+BBX 2 2 1 -1
+BITMAP
+C0
+40
+ENDCHAR
+ENDFONT
+";
+
+    #[test]
+    fn test_builtin_fonts_exist() {
+        for name in BUILTIN_BITMAP_FONTS {
+            assert!(BitmapFont::builtin(name).is_some(), "missing {}", name);
+        }
+        assert!(BitmapFont::builtin("7x13").is_none());
+    }
+
+    #[test]
+    fn test_5x7_covers_printable_ascii() {
+        let font = BitmapFont::builtin("5x7").unwrap();
+        for c in ' '..='~' {
+            assert!(font.glyph(c).is_some(), "missing {:?}", c);
+        }
+        assert_eq!(font.line_height(), 8);
+    }
+
+    #[test]
+    fn test_5x7_glyph_pixels() {
+        let font = BitmapFont::builtin("5x7").unwrap();
+        let bang = font.glyph('!').unwrap();
+        // Column 2 is lit for rows 0-4 and 6, row 5 is the gap
+        assert!(bang.is_set(2, 0));
+        assert!(!bang.is_set(2, 5));
+        assert!(bang.is_set(2, 6));
+        assert!(!bang.is_set(0, 0));
+    }
+
+    #[test]
+    fn test_8x16_glyphs() {
+        let font = BitmapFont::builtin("8x16").unwrap();
+        for c in ' '..='~' {
+            assert!(font.glyph(c).is_some(), "missing {:?}", c);
+        }
+        assert_eq!(font.line_height(), 16);
+        assert_eq!(font.scale_for(16.0), 1);
+
+        // The bar of 'H' is row 6, and 'g' descends below the baseline
+        let h = font.glyph('H').unwrap();
+        assert!((0..7).all(|x| h.is_set(x, 6)));
+        assert!(!h.is_set(3, 5));
+        let g = font.glyph('g').unwrap();
+        assert_eq!(g.ink_rect(), Some((0, 5, 7, 15)));
+    }
+
+    #[test]
+    fn test_3x5_lowercase_uses_uppercase() {
+        let font = BitmapFont::builtin("3x5").unwrap();
+        assert_eq!(font.glyph('a'), font.glyph('A'));
+        for c in ' '..='~' {
+            assert!(font.glyph(c).is_some(), "missing {:?}", c);
+        }
+    }
+
+    #[test]
+    fn test_scale_for_is_integer() {
+        let font = BitmapFont::builtin("5x7").unwrap();
+        assert_eq!(font.scale_for(4.0), 1);
+        assert_eq!(font.scale_for(8.0), 1);
+        assert_eq!(font.scale_for(15.9), 1);
+        assert_eq!(font.scale_for(16.0), 2);
+        assert_eq!(font.scale_for(40.0), 5);
+    }
+
+    #[test]
+    fn test_parse_bdf() {
+        let font = BitmapFont::parse_bdf(SAMPLE_BDF).unwrap();
+        assert_eq!(font.ascent(), 6);
+        assert_eq!(font.descent(), 2);
+
+        let a = font.glyph('A').unwrap();
+        assert_eq!((a.width, a.height, a.advance, a.top), (3, 3, 4, 3));
+        assert!(a.is_set(1, 0));
+        assert!(a.is_set(0, 1) && !a.is_set(1, 1) && a.is_set(2, 1));
+        assert!(a.is_set(0, 2) && a.is_set(1, 2) && a.is_set(2, 2));
+
+        let e = font.glyph('é').unwrap();
+        assert_eq!((e.x_offset, e.top, e.advance), (1, 1, 5));
+    }
+
+    #[test]
+    fn test_parse_bdf_rejects_non_bdf() {
+        assert!(BitmapFont::parse_bdf("hello").is_err());
+    }
+
+    #[test]
+    fn test_parse_bdf_rejects_non_ascii_row() {
+        let source = SAMPLE_BDF.replace("A0\n", "aé1\n");
+        assert!(matches!(
+            BitmapFont::parse_bdf(&source),
+            Err(FontError::InvalidBitmapFont(_))
+        ));
+    }
+
+    #[test]
+    fn test_parse_bdf_huge_bounding_box() {
+        let source = SAMPLE_BDF
+            .replace(
+                "FONTBOUNDINGBOX 4 8 0 -2",
+                "FONTBOUNDINGBOX 4 2147483647 0 -2147483648",
+            )
+            .replace("FONT_ASCENT 6\n", "")
+            .replace("FONT_DESCENT 2\n", "");
+        let font = BitmapFont::parse_bdf(&source).unwrap();
+        assert_eq!(font.descent(), i32::MAX as u32);
+    }
+
+    #[test]
+    fn test_parse_bdf_rejects_huge_glyph() {
+        let source = SAMPLE_BDF.replace("BBX 3 3 0 0", "BBX 100000 100000 0 0");
+        assert!(BitmapFont::parse_bdf(&source).is_err());
+    }
+
+    /// Build a minimal little-endian PCF with one 3x2 glyph for 'A'
+    fn sample_pcf() -> Vec<u8> {
+        fn le32(v: i32) -> [u8; 4] {
+            v.to_le_bytes()
+        }
+        fn le16(v: i16) -> [u8; 2] {
+            v.to_le_bytes()
+        }
+
+        // Metrics (uncompressed): left 0, right 3, width 4, ascent 2, descent 0
+        let mut metrics = le32(0).to_vec();
+        metrics.extend(le32(1));
+        for v in [0i16, 3, 4, 2, 0, 0] {
+            metrics.extend(le16(v));
+        }
+
+        // Bitmaps: pad to 1 byte, MSBit first (format 0x08)
+        let mut bitmaps = le32(0x08).to_vec();
+        bitmaps.extend(le32(1)); // glyph count
+        bitmaps.extend(le32(0)); // offset
+        for size in [2, 4, 8, 16] {
+            bitmaps.extend(le32(size));
+        }
+        bitmaps.extend([0b1010_0000, 0b0100_0000]);
+
+        // Encodings: single row, byte2 65..=65
+        let mut encodings = le32(0).to_vec();
+        for v in [65i16, 65, 0, 0, 0, 0] {
+            encodings.extend(le16(v));
+        }
+
+        let tables = [(1 << 2, metrics), (1 << 3, bitmaps), (1 << 5, encodings)];
+        let mut data = b"\x01fcp".to_vec();
+        data.extend(le32(tables.len() as i32));
+        let mut offset = 8 + 16 * tables.len();
+        let mut body: Vec<u8> = Vec::new();
+        for (kind, table) in &tables {
+            data.extend(le32(*kind));
+            data.extend(le32(0));
+            data.extend(le32(table.len() as i32));
+            data.extend(le32(offset as i32));
+            offset += table.len();
+            body.extend(table);
+        }
+        data.extend(body);
+        data
+    }
+
+    #[test]
+    fn test_parse_pcf() {
+        let font = BitmapFont::parse_pcf(&sample_pcf()).unwrap();
+        let a = font.glyph('A').unwrap();
+        assert_eq!((a.width, a.height, a.advance), (3, 2, 4));
+        assert!(a.is_set(0, 0) && !a.is_set(1, 0) && a.is_set(2, 0));
+        assert!(!a.is_set(0, 1) && a.is_set(1, 1) && !a.is_set(2, 1));
+        assert_eq!(font.ascent(), 2);
+    }
+
+    #[test]
+    fn test_parse_pcf_rejects_bad_magic() {
+        assert!(BitmapFont::parse_pcf(b"STARTFONT").is_err());
+    }
+
+    #[test]
+    fn test_parse_pcf_rejects_negative_offset() {
+        let mut data = b"\x01fcp".to_vec();
+        for v in [1i32, 1 << 2, 0, 0, -1] {
+            data.extend(v.to_le_bytes());
+        }
+        assert!(BitmapFont::parse_pcf(&data).is_err());
+    }
+}
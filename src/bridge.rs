@@ -0,0 +1,137 @@
+use std::io::{self, ErrorKind, Read, Write};
+use std::net::TcpStream;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::thread;
+
+/// Forwards raw protocol bytes between TCP clients and a local device, so a
+/// remote machine can open `tcp://host:port` as if the display were local.
+///
+/// One client is served at a time. Replies from the device go to the
+/// connected client and are dropped while none is.
+pub struct Bridge<W: Write> {
+    client: Arc<Mutex<Option<TcpStream>>>,
+    device: W,
+}
+
+impl<W: Write> Bridge<W> {
+    /// Bridge a device given as a reading and a writing half (e.g. a serial
+    /// port and its `try_clone`)
+    pub fn new(mut device_in: impl Read + Send + 'static, device: W) -> Self {
+        let client: Arc<Mutex<Option<TcpStream>>> = Arc::default();
+        {
+            let client = Arc::clone(&client);
+            thread::spawn(move || {
+                let mut buf = [0u8; 256];
+                loop {
+                    let count = match device_in.read(&mut buf) {
+                        Ok(0) => return,
+                        Ok(count) => count,
+                        Err(e)
+                            if matches!(
+                                e.kind(),
+                                ErrorKind::TimedOut
+                                    | ErrorKind::WouldBlock
+                                    | ErrorKind::Interrupted
+                            ) =>
+                        {
+                            continue
+                        }
+                        Err(_) => return,
+                    };
+                    let mut client = client.lock().unwrap_or_else(|e| e.into_inner());
+                    if let Some(stream) = client.as_mut() {
+                        // A client that went away is noticed by serve_client
+                        let _ = stream.write_all(&buf[..count]);
+                    }
+                }
+            });
+        }
+        Bridge { client, device }
+    }
+
+    /// Forward everything `stream` sends to the device until it
+    /// disconnects. Returns the number of bytes forwarded.
+    pub fn serve_client(&mut self, mut stream: TcpStream) -> io::Result<u64> {
+        stream.set_nodelay(true)?;
+        *self.lock_client() = Some(stream.try_clone()?);
+        let result = forward(&mut stream, &mut self.device);
+        *self.lock_client() = None;
+        result
+    }
+
+    fn lock_client(&self) -> MutexGuard<'_, Option<TcpStream>> {
+        self.client.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// Copy `from` into `to`, flushing after every read so commands reach the
+/// device as they arrive
+fn forward(from: &mut impl Read, to: &mut impl Write) -> io::Result<u64> {
+    let mut buf = [0u8; 4096];
+    let mut total = 0;
+    loop {
+        let count = match from.read(&mut buf) {
+            Ok(0) => return Ok(total),
+            Ok(count) => count,
+            Err(e) if e.kind() == ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        };
+        to.write_all(&buf[..count])?;
+        to.flush()?;
+        total += count as u64;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::display::Display;
+    use crate::image::Orientation;
+    use crate::panel::PanelSpec;
+    use crate::port::{PortInfo, SerialSettings};
+    use std::net::TcpListener;
+
+    fn tcp_pair() -> (TcpStream, TcpStream) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let near = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+        let (far, _) = listener.accept().unwrap();
+        (near, far)
+    }
+
+    #[test]
+    fn test_display_over_bridge() {
+        // A fake device on one end of a loopback pair, the bridge on the other
+        let (mut device, device_end) = tcp_pair();
+        let mut bridge = Bridge::new(device_end.try_clone().unwrap(), device_end);
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = format!("tcp://{}", listener.local_addr().unwrap());
+        let server = thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            bridge.serve_client(stream).unwrap()
+        });
+
+        let frame = vec![0x42; PanelSpec::WEACT_FS_096.frame_bytes()];
+        let expected = 2 + 3 + 10 + frame.len();
+        let fake = thread::spawn(move || {
+            let mut query = [0u8; 2];
+            device.read_exact(&mut query).unwrap();
+            device.write_all(b"\xC2v3\n").unwrap();
+            let mut rest = vec![0u8; expected - 2];
+            device.read_exact(&mut rest).unwrap();
+            ([query.to_vec(), rest].concat(), device)
+        });
+
+        let port = PortInfo::from_address(&address);
+        let mut display = Display::open_with(&port, None, &SerialSettings::default()).unwrap();
+        let info = display.query_info().unwrap();
+        assert_eq!(info.firmware.as_deref(), Some("v3"));
+        display.send_frame(&frame, Orientation::Landscape).unwrap();
+        drop(display);
+
+        let (received, _device) = fake.join().unwrap();
+        assert_eq!(&received[..2], &[0xC2, 0x0A]);
+        assert_eq!(&received[2..5], &[0x02, 0x01, 0x0A]);
+        assert_eq!(&received[15..], &frame[..]);
+        assert_eq!(server.join().unwrap(), expected as u64);
+    }
+}
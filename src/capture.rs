@@ -0,0 +1,247 @@
+use crate::port::Connection;
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::Path;
+use std::thread::sleep;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use thiserror::Error;
+
+/// First bytes of a capture file
+const MAGIC: &[u8; 8] = b"DFSCAP01";
+
+#[derive(Error, Debug)]
+pub enum CaptureError {
+    #[error("Capture file error: {0}")]
+    Io(#[from] io::Error),
+    #[error("Not a display-fs capture file")]
+    BadMagic,
+}
+
+/// A recorded session: when it started and every write, in order
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Capture {
+    pub started: SystemTime,
+    pub records: Vec<CaptureRecord>,
+}
+
+/// Bytes written at one point of a session
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CaptureRecord {
+    /// Time since the session started
+    pub offset: Duration,
+    pub data: Vec<u8>,
+}
+
+/// Writes a capture file: a header with the session's start time (ms
+/// since the Unix epoch), then one record per write: offset in µs (u64),
+/// length (u32) and the bytes, little-endian
+pub struct CaptureWriter<W: Write> {
+    out: W,
+    start: Instant,
+}
+
+impl CaptureWriter<BufWriter<File>> {
+    pub fn create(path: &Path) -> io::Result<Self> {
+        CaptureWriter::new(BufWriter::new(File::create(path)?))
+    }
+}
+
+impl<W: Write> CaptureWriter<W> {
+    pub fn new(mut out: W) -> io::Result<Self> {
+        let started = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default();
+        out.write_all(MAGIC)?;
+        out.write_all(&(started.as_millis() as u64).to_le_bytes())?;
+        Ok(CaptureWriter {
+            out,
+            start: Instant::now(),
+        })
+    }
+
+    pub fn record(&mut self, data: &[u8]) -> io::Result<()> {
+        let offset = self.start.elapsed().as_micros() as u64;
+        self.out.write_all(&offset.to_le_bytes())?;
+        self.out.write_all(&(data.len() as u32).to_le_bytes())?;
+        self.out.write_all(data)
+    }
+
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.out.flush()
+    }
+}
+
+/// A connection that also records everything written to it
+pub struct Tee<W: Write> {
+    inner: Box<dyn Connection>,
+    capture: CaptureWriter<W>,
+}
+
+impl Tee<BufWriter<File>> {
+    /// Record writes to `inner` into a new capture file at `path`
+    pub fn create(inner: Box<dyn Connection>, path: &Path) -> io::Result<Self> {
+        Ok(Tee::new(inner, CaptureWriter::create(path)?))
+    }
+}
+
+impl<W: Write> Tee<W> {
+    pub fn new(inner: Box<dyn Connection>, capture: CaptureWriter<W>) -> Self {
+        Tee { inner, capture }
+    }
+}
+
+impl<W: Write> Read for Tee<W> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.inner.read(buf)
+    }
+}
+
+impl<W: Write> Write for Tee<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let written = self.inner.write(buf)?;
+        self.capture.record(&buf[..written])?;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()?;
+        self.capture.flush()
+    }
+}
+
+impl<W: Write + Send> Connection for Tee<W> {
+    fn clear(&mut self) -> io::Result<()> {
+        self.inner.clear()
+    }
+
+    fn read_timeout(&mut self, buf: &mut [u8], timeout: Duration) -> io::Result<usize> {
+        self.inner.read_timeout(buf, timeout)
+    }
+}
+
+/// Read a whole capture file
+pub fn read_capture(path: &Path) -> Result<Capture, CaptureError> {
+    parse_capture(BufReader::new(File::open(path)?))
+}
+
+/// Parse a capture from `reader`. A record cut short (e.g. by a crash
+/// mid-session) ends the capture.
+pub fn parse_capture(mut reader: impl Read) -> Result<Capture, CaptureError> {
+    let mut magic = [0u8; 8];
+    reader.read_exact(&mut magic)?;
+    if &magic != MAGIC {
+        return Err(CaptureError::BadMagic);
+    }
+    let mut millis = [0u8; 8];
+    reader.read_exact(&mut millis)?;
+    let started = UNIX_EPOCH + Duration::from_millis(u64::from_le_bytes(millis));
+
+    let mut records = Vec::new();
+    let mut header = [0u8; 12];
+    loop {
+        match reader.read_exact(&mut header) {
+            Ok(()) => {}
+            Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => break,
+            Err(e) => return Err(e.into()),
+        }
+        let offset = u64::from_le_bytes(header[..8].try_into().unwrap_or_default());
+        let len = u32::from_le_bytes(header[8..].try_into().unwrap_or_default());
+        // Read only what is there rather than trusting the length up front
+        let mut data = Vec::new();
+        reader.by_ref().take(len as u64).read_to_end(&mut data)?;
+        if data.len() < len as usize {
+            break;
+        }
+        records.push(CaptureRecord {
+            offset: Duration::from_micros(offset),
+            data,
+        });
+    }
+
+    Ok(Capture { started, records })
+}
+
+/// Resend a capture to `port`, keeping the original gaps between writes
+pub fn replay(capture: &Capture, port: &mut dyn Connection) -> io::Result<()> {
+    let start = Instant::now();
+    for record in &capture.records {
+        sleep((start + record.offset).saturating_duration_since(Instant::now()));
+        port.write_all(&record.data)?;
+    }
+    port.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::driver::tests::Recorder;
+
+    #[test]
+    fn test_tee_records_writes() {
+        let port = Recorder::default();
+        let mut file = Vec::new();
+        {
+            let capture = CaptureWriter::new(&mut file).unwrap();
+            let mut tee = Tee::new(Box::new(port.clone()), capture);
+            tee.write_all(&[0x02, 0x01, 0x0A]).unwrap();
+            tee.write_all(&[0x03, 0xFF, 0, 0, 0x0A]).unwrap();
+            tee.flush().unwrap();
+        }
+
+        let capture = parse_capture(&file[..]).unwrap();
+        let data: Vec<_> = capture.records.iter().map(|r| r.data.clone()).collect();
+        assert_eq!(
+            data,
+            vec![vec![0x02, 0x01, 0x0A], vec![0x03, 0xFF, 0, 0, 0x0A]]
+        );
+        assert!(capture.records[0].offset <= capture.records[1].offset);
+        assert_eq!(port.written(), data.concat());
+    }
+
+    #[test]
+    fn test_replay_resends_bytes() {
+        let capture = Capture {
+            started: UNIX_EPOCH,
+            records: vec![
+                CaptureRecord {
+                    offset: Duration::ZERO,
+                    data: vec![1, 2],
+                },
+                CaptureRecord {
+                    offset: Duration::from_millis(20),
+                    data: vec![3],
+                },
+            ],
+        };
+        let mut port = Recorder::default();
+        let start = Instant::now();
+        replay(&capture, &mut port).unwrap();
+        assert!(start.elapsed() >= Duration::from_millis(20));
+        assert_eq!(port.written(), vec![1, 2, 3]);
+    }
+
+    #[test]
+    fn test_parse_rejects_other_files() {
+        assert!(matches!(
+            parse_capture(&b"PNG\x00\x00\x00\x00\x00"[..]),
+            Err(CaptureError::BadMagic)
+        ));
+    }
+
+    #[test]
+    fn test_truncated_record_ends_capture() {
+        let mut file = Vec::new();
+        {
+            let mut capture = CaptureWriter::new(&mut file).unwrap();
+            capture.record(&[1, 2, 3]).unwrap();
+            capture.record(&[4, 5, 6]).unwrap();
+        }
+        file.truncate(file.len() - 1);
+        assert_eq!(parse_capture(&file[..]).unwrap().records.len(), 1);
+
+        // A corrupt length claiming 4 GiB ends it the same way
+        let second_len = 16 + 12 + 3 + 8;
+        file[second_len..second_len + 4].copy_from_slice(&u32::MAX.to_le_bytes());
+        assert_eq!(parse_capture(&file[..]).unwrap().records.len(), 1);
+    }
+}
@@ -0,0 +1,171 @@
+use crate::port::{BaudRate, FlowControl, SerialSettings};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use thiserror::Error;
+
+const CONFIG_DIR: &str = "display-fs";
+const CONFIG_FILE: &str = "config.toml";
+
+#[derive(Error, Debug)]
+pub enum ConfigError {
+    #[error("Failed to read config file: {0}")]
+    ReadFailed(#[from] std::io::Error),
+    #[error("Invalid config file: {0}")]
+    ParseFailed(#[from] toml::de::Error),
+}
+
+/// Settings loaded from `config.toml`. Command-line options take precedence.
+///
+/// ```toml
+/// font = "/usr/share/fonts/truetype/noto/NotoSans-Regular.ttf"
+/// fallback_fonts = ["/usr/share/fonts/opentype/noto/NotoSansCJK-Regular.ttc"]
+///
+/// [widgets.clock]
+/// font = "5x7"
+///
+/// [serial]
+/// baud = "auto"
+/// ```
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    /// TrueType/OpenType font used instead of the embedded one
+    pub font: Option<PathBuf>,
+    /// Fonts tried in order for characters the main font lacks
+    pub fallback_fonts: Vec<PathBuf>,
+    /// Per-widget overrides, keyed by preset name, `show` or `spotify`
+    pub widgets: HashMap<String, WidgetConfig>,
+    /// Serial link settings
+    pub serial: SerialConfig,
+}
+
+/// Settings for the serial link to the display
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SerialConfig {
+    /// Baud rate, or "auto" to probe the rates the firmware may run at
+    pub baud: Option<BaudRate>,
+    /// `none`, `software` (XON/XOFF) or `hardware` (RTS/CTS)
+    pub flow_control: FlowControl,
+    /// Read/write timeout in milliseconds
+    pub timeout_ms: Option<u64>,
+}
+
+impl SerialConfig {
+    /// Serial settings, with `baud` (from `--baud`) taking precedence
+    pub fn settings(&self, baud: Option<BaudRate>) -> SerialSettings {
+        let defaults = SerialSettings::default();
+        SerialSettings {
+            baud_rate: baud.or(self.baud).unwrap_or(defaults.baud_rate),
+            flow_control: self.flow_control,
+            timeout: self
+                .timeout_ms
+                .map_or(defaults.timeout, Duration::from_millis),
+        }
+    }
+}
+
+/// Settings for a single widget
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct WidgetConfig {
+    /// Font spec (file path or built-in bitmap font name) for this widget
+    pub font: Option<PathBuf>,
+}
+
+impl Config {
+    /// Default config location: `$XDG_CONFIG_HOME/display-fs/config.toml`,
+    /// `~/.config/display-fs/config.toml`, or `%APPDATA%\display-fs\config.toml`
+    pub fn default_path() -> Option<PathBuf> {
+        let base = std::env::var_os("XDG_CONFIG_HOME")
+            .map(PathBuf::from)
+            .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))
+            .or_else(|| std::env::var_os("APPDATA").map(PathBuf::from))?;
+        Some(base.join(CONFIG_DIR).join(CONFIG_FILE))
+    }
+
+    pub fn load(path: &Path) -> Result<Config, ConfigError> {
+        let contents = std::fs::read_to_string(path)?;
+        Config::parse(&contents)
+    }
+
+    /// Load from the default location, or return defaults if there is no config file
+    pub fn load_default() -> Result<Config, ConfigError> {
+        match Config::default_path() {
+            Some(path) if path.exists() => Config::load(&path),
+            _ => Ok(Config::default()),
+        }
+    }
+
+    pub fn parse(contents: &str) -> Result<Config, ConfigError> {
+        Ok(toml::from_str(contents)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_empty_config_is_default() {
+        let config = Config::parse("").unwrap();
+        assert!(config.font.is_none());
+        assert!(config.fallback_fonts.is_empty());
+    }
+
+    #[test]
+    fn test_parse_fallback_fonts() {
+        let config = Config::parse("fallback_fonts = [\"/a.ttf\", \"/b.otf\"]").unwrap();
+        assert_eq!(
+            config.fallback_fonts,
+            vec![PathBuf::from("/a.ttf"), PathBuf::from("/b.otf")]
+        );
+    }
+
+    #[test]
+    fn test_parse_font_path() {
+        let config = Config::parse("font = \"/tmp/custom.ttf\"").unwrap();
+        assert_eq!(config.font, Some(PathBuf::from("/tmp/custom.ttf")));
+    }
+
+    #[test]
+    fn test_parse_widget_font() {
+        let config = Config::parse("[widgets.clock]\nfont = \"5x7\"").unwrap();
+        assert_eq!(config.widgets["clock"].font, Some(PathBuf::from("5x7")));
+    }
+
+    #[test]
+    fn test_parse_serial() {
+        let config =
+            Config::parse("[serial]\nbaud = 921600\nflow_control = \"hardware\"\ntimeout_ms = 250")
+                .unwrap();
+        let settings = config.serial.settings(None);
+        assert_eq!(settings.baud_rate, BaudRate::Fixed(921600));
+        assert_eq!(settings.flow_control, FlowControl::Hardware);
+        assert_eq!(settings.timeout, Duration::from_millis(250));
+
+        // `--baud` wins over the config
+        let settings = config.serial.settings(Some(BaudRate::Auto));
+        assert_eq!(settings.baud_rate, BaudRate::Auto);
+
+        let config = Config::parse("[serial]\nbaud = \"auto\"").unwrap();
+        assert_eq!(config.serial.baud, Some(BaudRate::Auto));
+        assert!(Config::parse("[serial]\nbaud = \"fast\"").is_err());
+    }
+
+    #[test]
+    fn test_unknown_key_is_error() {
+        assert!(matches!(
+            Config::parse("colour = \"red\""),
+            Err(ConfigError::ParseFailed(_))
+        ));
+    }
+
+    #[test]
+    fn test_load_missing_file() {
+        let result = Config::load(Path::new("/nonexistent/display-fs/config.toml"));
+        assert!(matches!(result, Err(ConfigError::ReadFailed(_))));
+    }
+}
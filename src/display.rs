@@ -0,0 +1,207 @@
+use crate::capture::{CaptureError, Tee};
+use crate::driver::{select_driver, Capabilities, DeviceInfo, DisplayDriver};
+use crate::framebuffer::{Framebuffer, StateWriter};
+use crate::image::{image_to_panel_bytes, Orientation};
+use crate::panel::{PanelSpec, Rect};
+use crate::port::{
+    open_device, resolve_baud_rate, BaudRate, Connection, PortError, PortInfo, SerialSettings,
+};
+use crate::protocol::ProtocolError;
+use crate::quantize::ColorOptions;
+use image::RgbImage;
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum DisplayError {
+    #[error(transparent)]
+    Port(#[from] PortError),
+    #[error(transparent)]
+    Protocol(#[from] ProtocolError),
+    #[error(transparent)]
+    Capture(#[from] CaptureError),
+}
+
+/// An open display: a connection and the driver that speaks its protocol
+pub struct Display {
+    connection: Box<dyn Connection>,
+    driver: &'static dyn DisplayDriver,
+    /// What has been sent so far
+    framebuffer: Option<Framebuffer>,
+    /// Saves the framebuffer to a state file after updates
+    state: Option<StateWriter>,
+}
+
+impl Display {
+    /// Open the display on `port`, with `driver` or else the one matching
+    /// the port's VID/PID
+    pub fn open(
+        port: &PortInfo,
+        driver: Option<&'static dyn DisplayDriver>,
+    ) -> Result<Self, DisplayError> {
+        Display::open_with(port, driver, &SerialSettings::default())
+    }
+
+    /// Open the display on `port` (a serial port or `tcp://` bridge) with
+    /// `settings`, probing the baud rate first if it is [`BaudRate::Auto`]
+    pub fn open_with(
+        port: &PortInfo,
+        driver: Option<&'static dyn DisplayDriver>,
+        settings: &SerialSettings,
+    ) -> Result<Self, DisplayError> {
+        let driver = select_driver(port, driver);
+        let settings = SerialSettings {
+            baud_rate: BaudRate::Fixed(resolve_baud_rate(port, settings, driver)?),
+            ..*settings
+        };
+        let connection = open_device(port, &settings)?;
+        Ok(Display::new(connection, driver)?)
+    }
+
+    /// Drive an already open connection
+    pub fn new(
+        mut connection: Box<dyn Connection>,
+        driver: &'static dyn DisplayDriver,
+    ) -> Result<Self, ProtocolError> {
+        driver.init(connection.as_mut())?;
+        Ok(Display {
+            connection,
+            driver,
+            framebuffer: None,
+            state: None,
+        })
+    }
+
+    /// Record every byte sent from now on into a capture file at `path`
+    /// (see [`crate::capture`])
+    pub fn capture_to(self, path: &Path) -> Result<Self, CaptureError> {
+        let connection = Tee::create(self.connection, path)?;
+        Ok(Display {
+            connection: Box::new(connection),
+            ..self
+        })
+    }
+
+    /// Keep the framebuffer saved to `path` for `display-fs screenshot`
+    /// (see [`Framebuffer::default_path`]). Saving happens in the
+    /// background, at most twice a second, and once more when the display
+    /// is dropped.
+    pub fn with_state_file(self, path: PathBuf) -> Self {
+        Display {
+            state: Some(StateWriter::spawn(path)),
+            ..self
+        }
+    }
+
+    /// What the panel shows, as far as this connection has drawn it
+    pub fn framebuffer(&self) -> Option<&Framebuffer> {
+        self.framebuffer.as_ref()
+    }
+
+    pub fn driver(&self) -> &'static dyn DisplayDriver {
+        self.driver
+    }
+
+    pub fn capabilities(&self) -> Capabilities {
+        self.driver.capabilities()
+    }
+
+    pub fn panel(&self) -> PanelSpec {
+        self.capabilities().panel
+    }
+
+    /// Ask the device about itself (firmware version, where supported)
+    pub fn query_info(&mut self) -> Result<DeviceInfo, ProtocolError> {
+        self.driver.query_info(self.connection.as_mut())
+    }
+
+    /// Send a full frame already converted for this panel
+    pub fn send_frame(
+        &mut self,
+        data: &[u8],
+        orientation: Orientation,
+    ) -> Result<(), ProtocolError> {
+        self.driver
+            .send_frame(self.connection.as_mut(), data, orientation)?;
+        self.framebuffer
+            .get_or_insert_with(|| Framebuffer::new(self.driver.capabilities().panel))
+            .set_frame(data, orientation);
+        self.save_state();
+        Ok(())
+    }
+
+    /// Convert and send an image drawn in `orientation`
+    pub fn send_image(
+        &mut self,
+        img: &RgbImage,
+        orientation: Orientation,
+        colors: ColorOptions,
+    ) -> Result<(), ProtocolError> {
+        let data = image_to_panel_bytes(img, orientation, colors, self.panel());
+        self.send_frame(&data, orientation)
+    }
+
+    /// Draw pixel data into a window of the panel
+    pub fn blit(&mut self, rect: Rect, data: &[u8]) -> Result<(), ProtocolError> {
+        self.driver.blit(self.connection.as_mut(), rect, data)?;
+        self.framebuffer
+            .get_or_insert_with(|| Framebuffer::new(self.driver.capabilities().panel))
+            .blit(rect, data);
+        self.save_state();
+        Ok(())
+    }
+
+    /// Set backlight brightness (0 = off, 255 = full)
+    pub fn set_brightness(&mut self, level: u8) -> Result<(), ProtocolError> {
+        self.driver.set_brightness(self.connection.as_mut(), level)
+    }
+
+    fn save_state(&self) {
+        if let (Some(state), Some(framebuffer)) = (&self.state, &self.framebuffer) {
+            state.update(framebuffer);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::driver::tests::Recorder;
+    use crate::driver::WeActFsV1;
+
+    #[test]
+    fn test_send_image_converts_for_panel() {
+        let port = Recorder::default();
+        let mut display = Display::new(Box::new(port.clone()), &WeActFsV1).unwrap();
+        assert_eq!(display.panel(), PanelSpec::WEACT_FS_096);
+        let img = RgbImage::new(160, 80);
+        display
+            .send_image(&img, Orientation::Landscape, ColorOptions::default())
+            .unwrap();
+        // Orientation command, bitmap header, one full RGB565 frame
+        assert_eq!(port.written().len(), 3 + 10 + 80 * 160 * 2);
+
+        display.set_brightness(0).unwrap();
+        assert!(port.written().ends_with(&[0x03, 0, 0, 0, 0x0A]));
+    }
+
+    #[test]
+    fn test_framebuffer_tracks_updates() {
+        let mut display = Display::new(Box::new(Recorder::default()), &WeActFsV1).unwrap();
+        assert!(display.framebuffer().is_none());
+
+        let mut img = RgbImage::new(160, 80);
+        img.put_pixel(150, 4, image::Rgb([255, 0, 0]));
+        display
+            .send_image(&img, Orientation::Landscape, ColorOptions::default())
+            .unwrap();
+        display
+            .blit(Rect::new(0, 0, 1, 1), &0xFFFFu16.to_le_bytes())
+            .unwrap();
+
+        let shown = display.framebuffer().unwrap().to_image();
+        assert_eq!(shown.get_pixel(150, 4), &image::Rgb([255, 0, 0]));
+        // Panel pixel (0, 0) is the bottom-left corner in landscape
+        assert_eq!(shown.get_pixel(0, 79), &image::Rgb([255, 255, 255]));
+    }
+}
@@ -0,0 +1,352 @@
+use crate::image::Orientation;
+use crate::panel::{PanelSpec, Rect};
+use crate::port::{Connection, PortInfo};
+use crate::protocol::{self, ProtocolError, Step};
+
+/// What a display supports
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Capabilities {
+    pub panel: PanelSpec,
+    /// Backlight brightness can be set
+    pub brightness: bool,
+    /// Windows smaller than the panel can be drawn
+    pub partial_updates: bool,
+}
+
+/// What the device reports about itself. Fields are `None` when the
+/// firmware doesn't answer the query.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DeviceInfo {
+    pub firmware: Option<String>,
+}
+
+/// Speaks the protocol of one family of USB serial displays. Drivers
+/// describe each operation as [`Step`]s; the provided methods run them on a
+/// blocking connection, and the async API runs the same steps.
+pub trait DisplayDriver: Send + Sync {
+    /// Short name, as passed to `--driver`
+    fn name(&self) -> &'static str;
+
+    fn description(&self) -> &'static str;
+
+    /// USB VID/PID pairs the device enumerates as
+    fn usb_ids(&self) -> &'static [(u16, u16)];
+
+    fn capabilities(&self) -> Capabilities;
+
+    /// Steps to prepare a freshly opened connection
+    fn init_steps(&self) -> Vec<Step> {
+        Vec::new()
+    }
+
+    fn orientation_steps(&self, orientation: Orientation) -> Vec<Step>;
+
+    /// Steps to draw pixel data, already in the panel's format, into `rect`
+    fn blit_steps(&self, rect: Rect, data: &[u8]) -> Result<Vec<Step>, ProtocolError>;
+
+    /// Steps to set backlight brightness (0 = off, 255 = full)
+    fn brightness_steps(&self, level: u8) -> Vec<Step>;
+
+    /// Steps to draw a full frame in `orientation`
+    fn frame_steps(
+        &self,
+        data: &[u8],
+        orientation: Orientation,
+    ) -> Result<Vec<Step>, ProtocolError> {
+        let mut steps = self.orientation_steps(orientation);
+        steps.extend(self.blit_steps(Rect::full(self.capabilities().panel), data)?);
+        Ok(steps)
+    }
+
+    /// Ask the device about itself
+    fn query_info(&self, _port: &mut dyn Connection) -> Result<DeviceInfo, ProtocolError> {
+        Ok(DeviceInfo::default())
+    }
+
+    /// Whether this driver handles the USB device `vid`:`pid`
+    fn matches(&self, vid: u16, pid: u16) -> bool {
+        self.usb_ids().contains(&(vid, pid))
+    }
+
+    /// Prepare a freshly opened connection
+    fn init(&self, port: &mut dyn Connection) -> Result<(), ProtocolError> {
+        protocol::run_steps(port, &self.init_steps())
+    }
+
+    fn set_orientation(
+        &self,
+        port: &mut dyn Connection,
+        orientation: Orientation,
+    ) -> Result<(), ProtocolError> {
+        protocol::run_steps(port, &self.orientation_steps(orientation))
+    }
+
+    /// Draw pixel data, already in the panel's format, into `rect`
+    fn blit(
+        &self,
+        port: &mut dyn Connection,
+        rect: Rect,
+        data: &[u8],
+    ) -> Result<(), ProtocolError> {
+        protocol::run_steps(port, &self.blit_steps(rect, data)?)
+    }
+
+    /// Set backlight brightness (0 = off, 255 = full)
+    fn set_brightness(&self, port: &mut dyn Connection, level: u8) -> Result<(), ProtocolError> {
+        protocol::run_steps(port, &self.brightness_steps(level))
+    }
+
+    /// Draw a full frame in `orientation`
+    fn send_frame(
+        &self,
+        port: &mut dyn Connection,
+        data: &[u8],
+        orientation: Orientation,
+    ) -> Result<(), ProtocolError> {
+        let steps = self.frame_steps(data, orientation)?;
+        port.clear()?;
+        protocol::run_steps(port, &steps)
+    }
+}
+
+/// WeAct Studio Display FS V1 (0.96", 80x160)
+#[derive(Debug, Clone, Copy, Default)]
+pub struct WeActFsV1;
+
+const WEACT_FS_V1_IDS: &[(u16, u16)] = &[
+    (0x1A86, 0x7523), // CH340
+    (0x1A86, 0x5523), // CH341
+    (0x1A86, 0xFE0C), // WeAct Studio Display FS V1
+];
+
+impl DisplayDriver for WeActFsV1 {
+    fn name(&self) -> &'static str {
+        "weact-fs-v1"
+    }
+
+    fn description(&self) -> &'static str {
+        "WeAct Studio Display FS V1 (0.96\", 80x160)"
+    }
+
+    fn usb_ids(&self) -> &'static [(u16, u16)] {
+        WEACT_FS_V1_IDS
+    }
+
+    fn capabilities(&self) -> Capabilities {
+        Capabilities {
+            panel: PanelSpec::WEACT_FS_096,
+            brightness: true,
+            partial_updates: true,
+        }
+    }
+
+    fn orientation_steps(&self, orientation: Orientation) -> Vec<Step> {
+        protocol::orientation_steps(orientation)
+    }
+
+    fn blit_steps(&self, rect: Rect, data: &[u8]) -> Result<Vec<Step>, ProtocolError> {
+        protocol::bitmap_steps(self.capabilities().panel, rect, data)
+    }
+
+    fn brightness_steps(&self, level: u8) -> Vec<Step> {
+        protocol::brightness_steps(level)
+    }
+
+    fn query_info(&self, port: &mut dyn Connection) -> Result<DeviceInfo, ProtocolError> {
+        port.clear()?;
+        Ok(DeviceInfo {
+            firmware: protocol::query_version(port)?,
+        })
+    }
+}
+
+/// Known drivers, in detection order
+pub static DRIVERS: &[&dyn DisplayDriver] = &[&WeActFsV1];
+
+/// The driver for USB device `vid`:`pid`
+pub fn driver_for(vid: u16, pid: u16) -> Option<&'static dyn DisplayDriver> {
+    DRIVERS
+        .iter()
+        .copied()
+        .find(|driver| driver.matches(vid, pid))
+}
+
+/// `driver` if given, else the driver for the port's VID/PID, else the
+/// FS V1 driver
+pub fn select_driver(
+    port: &PortInfo,
+    driver: Option<&'static dyn DisplayDriver>,
+) -> &'static dyn DisplayDriver {
+    driver
+        .or_else(|| driver_for(port.vid, port.pid))
+        .unwrap_or(&WeActFsV1)
+}
+
+/// Look up a driver by name
+pub fn driver_named(name: &str) -> Option<&'static dyn DisplayDriver> {
+    DRIVERS
+        .iter()
+        .copied()
+        .find(|driver| driver.name().eq_ignore_ascii_case(name))
+}
+
+/// Parse a driver name (for CLI arguments)
+pub fn parse_driver(name: &str) -> Result<&'static dyn DisplayDriver, String> {
+    driver_named(name).ok_or_else(|| {
+        let names: Vec<_> = DRIVERS.iter().map(|driver| driver.name()).collect();
+        format!(
+            "unknown driver '{}' (available: {})",
+            name,
+            names.join(", ")
+        )
+    })
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use std::collections::VecDeque;
+    use std::io::{self, Read, Write};
+    use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+
+    #[derive(Default)]
+    struct Log {
+        written: Vec<u8>,
+        clears: usize,
+        replies: VecDeque<u8>,
+        /// Writes wait until this is cleared
+        held: bool,
+    }
+
+    /// Connection that records everything written to it and reads back
+    /// scripted replies; clones share the record
+    #[derive(Clone, Default)]
+    pub(crate) struct Recorder {
+        log: Arc<Mutex<Log>>,
+        released: Arc<Condvar>,
+    }
+
+    impl Recorder {
+        pub fn with_replies(replies: &[u8]) -> Self {
+            let recorder = Recorder::default();
+            recorder.log().replies.extend(replies);
+            recorder
+        }
+
+        pub fn written(&self) -> Vec<u8> {
+            self.log().written.clone()
+        }
+
+        pub fn clears(&self) -> usize {
+            self.log().clears
+        }
+
+        /// Make writes block until [`Recorder::release`]
+        pub fn hold(&self) {
+            self.log().held = true;
+        }
+
+        pub fn release(&self) {
+            self.log().held = false;
+            self.released.notify_all();
+        }
+
+        fn log(&self) -> MutexGuard<'_, Log> {
+            self.log.lock().unwrap()
+        }
+    }
+
+    impl Read for Recorder {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            let mut log = self.log();
+            let count = buf.len().min(log.replies.len());
+            for (slot, byte) in buf.iter_mut().zip(log.replies.drain(..count)) {
+                *slot = byte;
+            }
+            Ok(count)
+        }
+    }
+
+    impl Write for Recorder {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            let mut log = self.log();
+            while log.held {
+                log = self.released.wait(log).unwrap();
+            }
+            log.written.extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    impl Connection for Recorder {
+        fn clear(&mut self) -> io::Result<()> {
+            // Replies are scripted ahead of time, so keep them
+            self.log().clears += 1;
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_vid_pid_detection() {
+        // CH340, CH341 and WeAct VID/PIDs select the FS V1 driver
+        for (vid, pid) in [(0x1A86, 0x7523), (0x1A86, 0x5523), (0x1A86, 0xFE0C)] {
+            assert_eq!(driver_for(vid, pid).unwrap().name(), "weact-fs-v1");
+        }
+        assert!(driver_for(0x1234, 0x5678).is_none());
+    }
+
+    #[test]
+    fn test_parse_driver() {
+        assert_eq!(parse_driver("WeAct-FS-V1").unwrap().name(), "weact-fs-v1");
+        let error = parse_driver("nope").err().unwrap();
+        assert!(error.contains("weact-fs-v1"), "{}", error);
+    }
+
+    #[test]
+    fn test_fs_v1_send_frame() {
+        let mut port = Recorder::default();
+        let data = vec![0xAB; PanelSpec::WEACT_FS_096.frame_bytes()];
+        WeActFsV1
+            .send_frame(&mut port, &data, Orientation::Landscape)
+            .unwrap();
+
+        assert_eq!(port.clears(), 1);
+        // Orientation, then a full-panel bitmap header, then the pixels
+        let written = port.written();
+        assert_eq!(&written[..3], &[0x02, 0x01, 0x0A]);
+        assert_eq!(
+            &written[3..13],
+            &protocol::create_bitmap_header_for(PanelSpec::WEACT_FS_096).unwrap()
+        );
+        assert_eq!(&written[13..], &data[..]);
+    }
+
+    #[test]
+    fn test_fs_v1_query_info() {
+        let mut port = Recorder::with_replies(b"\xC2v1.2\n");
+        let info = WeActFsV1.query_info(&mut port).unwrap();
+        assert_eq!(info.firmware.as_deref(), Some("v1.2"));
+        assert_eq!(port.written(), vec![0xC2, 0x0A]);
+
+        // Firmware that doesn't answer
+        let info = WeActFsV1.query_info(&mut Recorder::default()).unwrap();
+        assert_eq!(info, DeviceInfo::default());
+    }
+
+    #[test]
+    fn test_fs_v1_blit_window() {
+        let mut port = Recorder::default();
+        let rect = Rect::new(8, 16, 4, 2);
+        WeActFsV1.blit(&mut port, rect, &[0; 16]).unwrap();
+        let written = port.written();
+        assert_eq!(
+            &written[..10],
+            &protocol::create_bitmap_header_window(rect).unwrap()
+        );
+        assert_eq!(written.len(), 10 + 16);
+    }
+}
@@ -0,0 +1,726 @@
+use crate::bitmap_font::BitmapFont;
+use crate::glyph_cache::{GlyphBitmap, GlyphCache, GlyphMetrics};
+use crate::icons::icon_for;
+use crate::layout::InkBounds;
+use ab_glyph::{
+    point, Font as _, FontArc, FontRef, FontVec, GlyphId, GlyphImageFormat, PxScale, ScaleFont,
+};
+use image::Rgb;
+use std::path::Path;
+use std::sync::{Arc, OnceLock};
+use thiserror::Error;
+
+const FONT_DATA: &[u8] = include_bytes!("../assets/fonts/DejaVuSans.ttf");
+
+#[cfg(feature = "japanese")]
+const JAPANESE_FONT_DATA: &[u8] = include_bytes!("../assets/fonts/NotoSansJP-Regular.otf");
+
+/// Well-known system fonts tried (in order) for glyphs no configured font has
+const SYSTEM_FALLBACK_PATHS: &[&str] = &[
+    // macOS
+    "/System/Library/Fonts/Hiragino Sans GB.ttc",
+    "/System/Library/Fonts/Apple Color Emoji.ttc",
+    "/System/Library/Fonts/AppleSDGothicNeo.ttc",
+    "/System/Library/Fonts/Supplemental/Arial Unicode.ttf",
+    "/Library/Fonts/Arial Unicode.ttf",
+    // Linux
+    "/usr/share/fonts/opentype/noto/NotoSansCJK-Regular.ttc",
+    "/usr/share/fonts/noto-cjk/NotoSansCJK-Regular.ttc",
+    "/usr/share/fonts/google-noto-cjk/NotoSansCJK-Regular.ttc",
+    "/usr/share/fonts/truetype/droid/DroidSansFallbackFull.ttf",
+    "/usr/share/fonts/truetype/noto/NotoColorEmoji.ttf",
+    "/usr/share/fonts/noto/NotoColorEmoji.ttf",
+    "/usr/share/fonts/truetype/noto/NotoEmoji-Regular.ttf",
+    "/usr/share/fonts/truetype/ancient-scripts/Symbola_hint.ttf",
+    // Windows
+    "C:\\Windows\\Fonts\\msgothic.ttc",
+    "C:\\Windows\\Fonts\\msyh.ttc",
+    "C:\\Windows\\Fonts\\malgun.ttf",
+    "C:\\Windows\\Fonts\\seguiemj.ttf",
+    "C:\\Windows\\Fonts\\seguisym.ttf",
+];
+
+/// A glyph to draw: a character looked up through the fallback chain, or a
+/// glyph a shaper picked from one face of the chain
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Glyph {
+    Char(char),
+    Shaped { face: usize, id: u16 },
+}
+
+/// A glyph produced by [`Font::shape`], positioned relative to the pen
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ShapedGlyph {
+    pub glyph: Glyph,
+    pub advance: f32,
+    pub x_offset: f32,
+    /// Downwards offset from the baseline (combining marks are placed above)
+    pub y_offset: f32,
+}
+
+#[derive(Error, Debug)]
+pub enum FontError {
+    #[error("Failed to read font file: {0}")]
+    ReadFailed(#[from] std::io::Error),
+    #[error("Not a valid TrueType/OpenType font")]
+    InvalidFont,
+    #[error("Invalid bitmap font: {0}")]
+    InvalidBitmapFont(String),
+}
+
+/// One font in a fallback chain
+#[derive(Clone, Debug)]
+pub(crate) enum Face {
+    /// Scalable TrueType/OpenType outlines, antialiased
+    Outline(FontArc),
+    /// Fixed pixel font, drawn at integer scales without antialiasing
+    Bitmap(Arc<BitmapFont>),
+    /// The built-in color icons (see [`crate::icons`])
+    Icons,
+}
+
+/// The icon face, searched after the configured faces
+static ICON_FACE: Face = Face::Icons;
+
+impl Face {
+    fn has_glyph(&self, c: char) -> bool {
+        match self {
+            Face::Outline(font) => font.glyph_id(c) != GlyphId(0),
+            Face::Bitmap(font) => font.glyph(c).is_some(),
+            Face::Icons => icon_for(c).is_some(),
+        }
+    }
+
+    fn line_height(&self, font_size: f32) -> f32 {
+        match self {
+            Face::Outline(font) => font.as_scaled(PxScale::from(font_size)).height(),
+            Face::Bitmap(font) => (font.line_height() * font.scale_for(font_size)) as f32,
+            Face::Icons => font_size,
+        }
+    }
+
+    fn ascent(&self, font_size: f32) -> f32 {
+        match self {
+            Face::Outline(font) => font.as_scaled(PxScale::from(font_size)).ascent(),
+            Face::Bitmap(font) => (font.ascent() * font.scale_for(font_size)) as f32,
+            Face::Icons => font_size * 0.8,
+        }
+    }
+
+    fn advance(&self, c: char, font_size: f32) -> f32 {
+        match self {
+            Face::Outline(font) => font
+                .as_scaled(PxScale::from(font_size))
+                .h_advance(font.glyph_id(c)),
+            Face::Bitmap(font) => font
+                .glyph(c)
+                .map_or(0.0, |g| (g.advance * font.scale_for(font_size)) as f32),
+            Face::Icons => icon_for(c).map_or(0.0, |icon| icon.advance(font_size)),
+        }
+    }
+
+    /// Kerning adjustment between two glyphs of this face
+    fn kern(&self, prev: char, c: char, font_size: f32) -> f32 {
+        match self {
+            Face::Outline(font) => font
+                .as_scaled(PxScale::from(font_size))
+                .kern(font.glyph_id(prev), font.glyph_id(c)),
+            Face::Bitmap(_) | Face::Icons => 0.0,
+        }
+    }
+
+    /// Outline glyph id for a character or shaped glyph of this face
+    fn outline_id(font: &FontArc, glyph: Glyph) -> GlyphId {
+        match glyph {
+            Glyph::Char(c) => font.glyph_id(c),
+            Glyph::Shaped { id, .. } => GlyphId(id),
+        }
+    }
+
+    /// Pixel bounds of the drawn glyph relative to its origin on the baseline
+    fn ink_bounds(&self, glyph: Glyph, font_size: f32) -> Option<InkBounds> {
+        match self {
+            Face::Outline(font) => {
+                let id = Face::outline_id(font, glyph);
+                let outline =
+                    font.outline_glyph(id.with_scale_and_position(font_size, point(0.0, 0.0)));
+                let Some(outline) = outline else {
+                    // Color emoji fonts have images instead of outlines
+                    return color_image(font, id, font_size, 0.0).map(|image| InkBounds {
+                        min_x: image.left as f32,
+                        min_y: image.top as f32,
+                        max_x: (image.left + image.width as i32) as f32,
+                        max_y: image.top as f32
+                            + (image.coverage.len() / image.width.max(1)) as f32,
+                    });
+                };
+                let bounds = outline.px_bounds();
+                Some(InkBounds {
+                    min_x: bounds.min.x,
+                    min_y: bounds.min.y,
+                    max_x: bounds.max.x,
+                    max_y: bounds.max.y,
+                })
+            }
+            Face::Bitmap(font) => {
+                let Glyph::Char(c) = glyph else {
+                    return None;
+                };
+                let glyph = font.glyph(c)?;
+                let (x0, y0, x1, y1) = glyph.ink_rect()?;
+                let scale = font.scale_for(font_size) as f32;
+                let left = glyph.x_offset as f32 * scale;
+                let top = -glyph.top as f32 * scale;
+                Some(InkBounds {
+                    min_x: left + x0 as f32 * scale,
+                    min_y: top + y0 as f32 * scale,
+                    max_x: left + x1 as f32 * scale,
+                    max_y: top + y1 as f32 * scale,
+                })
+            }
+            Face::Icons => match glyph {
+                Glyph::Char(c) => icon_for(c).map(|icon| icon.ink_bounds(font_size)),
+                Glyph::Shaped { .. } => None,
+            },
+        }
+    }
+
+    /// Rasterize an outline glyph, color image or icon with its origin at
+    /// (x, baseline), relative to that point rounded down to whole pixels.
+    /// `None` for bitmap faces, which are cheap enough to draw directly.
+    fn rasterize(
+        &self,
+        glyph: Glyph,
+        font_size: f32,
+        x: f32,
+        baseline: f32,
+    ) -> Option<GlyphBitmap> {
+        let font = match (self, glyph) {
+            (Face::Outline(font), _) => font,
+            (Face::Icons, Glyph::Char(c)) => {
+                return icon_for(c).map(|icon| icon.rasterize(font_size, x))
+            }
+            _ => return None,
+        };
+        let id = Face::outline_id(font, glyph);
+        let Some(outlined) =
+            font.outline_glyph(id.with_scale_and_position(font_size, point(x, baseline)))
+        else {
+            return Some(color_image(font, id, font_size, x).unwrap_or_default());
+        };
+        let bounds = outlined.px_bounds();
+        let width = bounds.width() as usize;
+        let mut coverage = vec![0.0; width * bounds.height() as usize];
+        outlined.draw(|gx, gy, value| coverage[gy as usize * width + gx as usize] = value);
+        Some(GlyphBitmap {
+            left: bounds.min.x.round() as i32 - x.floor() as i32,
+            top: bounds.min.y.round() as i32 - baseline.floor() as i32,
+            width,
+            coverage,
+            colors: None,
+        })
+    }
+
+    /// Rasterize `c` with its origin at (x, baseline), calling `plot(x, y, coverage)`
+    fn draw(
+        &self,
+        c: char,
+        font_size: f32,
+        x: f32,
+        baseline: f32,
+        mut plot: impl FnMut(i32, i32, f32),
+    ) {
+        match self {
+            Face::Outline(font) => {
+                let glyph = font
+                    .glyph_id(c)
+                    .with_scale_and_position(font_size, point(x, baseline));
+                if let Some(outlined) = font.outline_glyph(glyph) {
+                    let bounds = outlined.px_bounds();
+                    outlined.draw(|gx, gy, coverage| {
+                        plot(
+                            gx as i32 + bounds.min.x.round() as i32,
+                            gy as i32 + bounds.min.y.round() as i32,
+                            coverage,
+                        )
+                    });
+                }
+            }
+            Face::Bitmap(font) => {
+                let Some(glyph) = font.glyph(c) else {
+                    return;
+                };
+                let scale = font.scale_for(font_size) as i32;
+                let left = x.round() as i32 + glyph.x_offset * scale;
+                let top = baseline.round() as i32 - glyph.top * scale;
+                for gy in 0..glyph.height {
+                    for gx in 0..glyph.width {
+                        if !glyph.is_set(gx, gy) {
+                            continue;
+                        }
+                        for dy in 0..scale {
+                            for dx in 0..scale {
+                                plot(
+                                    left + gx as i32 * scale + dx,
+                                    top + gy as i32 * scale + dy,
+                                    1.0,
+                                );
+                            }
+                        }
+                    }
+                }
+            }
+            // Icons are always drawn from the glyph cache
+            Face::Icons => {}
+        }
+    }
+}
+
+/// A color emoji font's image for glyph `id`, scaled to `font_size` with
+/// its origin `x` pixels right of a whole pixel. PNG images (CBDT, sbix)
+/// are supported.
+fn color_image(font: &FontArc, id: GlyphId, font_size: f32, x: f32) -> Option<GlyphBitmap> {
+    let image = font.glyph_raster_image2(id, u16::MAX)?;
+    if !matches!(image.format, GlyphImageFormat::Png) {
+        return None;
+    }
+    let pixels = image::load_from_memory_with_format(image.data, image::ImageFormat::Png)
+        .ok()?
+        .to_rgba8();
+    let scaled = font.as_scaled(PxScale::from(font_size));
+    let pixels_per_em = scaled.h_scale_factor() * font.units_per_em()?;
+    let scale = pixels_per_em / image.pixels_per_em as f32;
+    // Image offsets are from the baseline, y up, at the image's own size
+    let left = x + image.origin.x * scale;
+    let height = pixels.height() as f32 * scale;
+    let top = -(image.origin.y * scale) - height;
+    let width = pixels.width() as f32 * scale;
+    Some(GlyphBitmap::from_rgba(&pixels, left, top, width, height))
+}
+
+/// A font fallback chain. Each character is drawn with the first face that
+/// has a glyph for it. Cheap to clone - parsed faces are shared.
+#[derive(Clone, Debug)]
+pub struct Font {
+    faces: Vec<Face>,
+    system_fallback: bool,
+    cache: Arc<GlyphCache>,
+}
+
+impl Font {
+    /// The fonts embedded in the binary: DejaVuSans, plus NotoSansJP with `japanese`
+    pub fn embedded() -> Font {
+        static EMBEDDED: OnceLock<Vec<Face>> = OnceLock::new();
+        let faces = EMBEDDED.get_or_init(|| {
+            vec![
+                embedded_face(FONT_DATA),
+                #[cfg(feature = "japanese")]
+                embedded_face(JAPANESE_FONT_DATA),
+            ]
+        });
+        Font {
+            faces: faces.clone(),
+            system_fallback: false,
+            cache: Arc::default(),
+        }
+    }
+
+    /// Load a font by spec: a built-in bitmap font name (`5x7`, `3x5`,
+    /// `8x16`) or a path to a TrueType/OpenType, BDF or PCF file
+    pub fn load(spec: &Path) -> Result<Font, FontError> {
+        let builtin = spec.to_str().and_then(BitmapFont::builtin);
+        match builtin {
+            Some(bitmap) if !spec.exists() => Ok(Font::from_bitmap(bitmap)),
+            _ => Font::from_file(spec),
+        }
+    }
+
+    /// Load a font file, detecting BDF and PCF bitmap fonts by their header.
+    /// For font collections the first font is used.
+    pub fn from_file(path: &Path) -> Result<Font, FontError> {
+        Font::from_bytes(std::fs::read(path)?)
+    }
+
+    pub fn from_bytes(data: Vec<u8>) -> Result<Font, FontError> {
+        if data.starts_with(b"STARTFONT") {
+            let source = String::from_utf8_lossy(&data);
+            return Ok(Font::from_bitmap(BitmapFont::parse_bdf(&source)?));
+        }
+        if data.starts_with(b"\x01fcp") {
+            return Ok(Font::from_bitmap(BitmapFont::parse_pcf(&data)?));
+        }
+
+        let face = FontVec::try_from_vec_and_index(data, 0).map_err(|_| FontError::InvalidFont)?;
+        Ok(Font {
+            faces: vec![Face::Outline(FontArc::new(face))],
+            system_fallback: false,
+            cache: Arc::default(),
+        })
+    }
+
+    pub fn from_bitmap(font: BitmapFont) -> Font {
+        Font {
+            faces: vec![Face::Bitmap(Arc::new(font))],
+            system_fallback: false,
+            cache: Arc::default(),
+        }
+    }
+
+    /// Append another font's faces to the end of this chain
+    pub fn with_fallback(mut self, fallback: Font) -> Font {
+        self.faces.extend(fallback.faces);
+        self.system_fallback |= fallback.system_fallback;
+        self.cache = Arc::default();
+        self
+    }
+
+    /// Also look up missing glyphs in well-known system fonts (CJK, emoji, symbols)
+    pub fn with_system_fallback(mut self) -> Font {
+        self.system_fallback = true;
+        self.cache = Arc::default();
+        self
+    }
+
+    /// Number of faces in the chain (not counting system fallbacks)
+    pub fn face_count(&self) -> usize {
+        self.faces.len()
+    }
+
+    /// The first face, whose metrics define line height
+    fn primary(&self) -> &Face {
+        &self.faces[0]
+    }
+
+    /// Faces searched for glyphs, in order: the chain, then system fonts.
+    /// System fonts are only loaded once a search gets past the others.
+    fn chain(&self) -> impl Iterator<Item = &Face> {
+        let system = std::iter::once(self.system_fallback)
+            .filter(|&enabled| enabled)
+            .flat_map(|_| system_faces());
+        self.faces
+            .iter()
+            .chain(std::iter::once(&ICON_FACE))
+            .chain(system)
+    }
+
+    /// Find the first face with a glyph for `c`. Falls back to the primary
+    /// face (which draws tofu, or nothing for bitmap fonts) if none has it.
+    pub(crate) fn face_for(&self, c: char) -> &Face {
+        let index = self.face_index(c);
+        self.chain().nth(index).unwrap_or(self.primary())
+    }
+
+    /// Position of `face_for(c)` in the chain, cached per character
+    pub(crate) fn face_index(&self, c: char) -> usize {
+        self.cache.faces.get_or_insert_with(c, || {
+            self.chain().position(|face| face.has_glyph(c)).unwrap_or(0)
+        })
+    }
+
+    /// The face that draws `glyph`
+    fn face_of(&self, glyph: Glyph) -> &Face {
+        match glyph {
+            Glyph::Char(c) => self.face_for(c),
+            Glyph::Shaped { face, .. } => self.chain().nth(face).unwrap_or(self.primary()),
+        }
+    }
+
+    /// Advance and ink bounds of `glyph`, cached per size. Shaped glyphs
+    /// take their advance from the shaper, so theirs is left at 0.
+    fn metrics(&self, glyph: Glyph, font_size: f32) -> GlyphMetrics {
+        self.cache
+            .metrics
+            .get_or_insert_with((glyph, font_size.to_bits()), || {
+                let face = self.face_of(glyph);
+                GlyphMetrics {
+                    advance: match glyph {
+                        Glyph::Char(c) => face.advance(c, font_size),
+                        Glyph::Shaped { .. } => 0.0,
+                    },
+                    ink: face.ink_bounds(glyph, font_size),
+                }
+            })
+    }
+
+    /// Shape a run of text with the face that provides its first character,
+    /// applying contextual forms, ligatures and mark positioning (e.g. for
+    /// Arabic and Hebrew). Glyphs come back in visual order, so an `rtl`
+    /// run is returned right-to-left reversed, ready to draw left to right.
+    /// `None` for bitmap faces, which have nothing to shape with.
+    pub fn shape(&self, text: &str, font_size: f32, rtl: bool) -> Option<Arc<[ShapedGlyph]>> {
+        let key = (text.to_string(), font_size.to_bits(), rtl);
+        self.cache.shaped.get_or_insert_with(key, || {
+            let index = self.face_index(text.chars().next()?);
+            let Face::Outline(font) = self.chain().nth(index)? else {
+                return None;
+            };
+            let face = rustybuzz::Face::from_slice(font.font_data(), 0)?;
+            let mut buffer = rustybuzz::UnicodeBuffer::new();
+            buffer.push_str(text);
+            buffer.set_direction(if rtl {
+                rustybuzz::Direction::RightToLeft
+            } else {
+                rustybuzz::Direction::LeftToRight
+            });
+            let output = rustybuzz::shape(&face, &[], buffer);
+
+            // Shaper positions are in font units
+            let scaled = font.as_scaled(PxScale::from(font_size));
+            let (h_scale, v_scale) = (scaled.h_scale_factor(), scaled.v_scale_factor());
+            let glyphs = output
+                .glyph_infos()
+                .iter()
+                .zip(output.glyph_positions())
+                .map(|(info, position)| ShapedGlyph {
+                    glyph: Glyph::Shaped {
+                        face: index,
+                        id: info.glyph_id as u16,
+                    },
+                    advance: position.x_advance as f32 * h_scale,
+                    x_offset: position.x_offset as f32 * h_scale,
+                    y_offset: -position.y_offset as f32 * v_scale,
+                })
+                .collect();
+            Some(glyphs)
+        })
+    }
+
+    pub fn has_glyph(&self, c: char) -> bool {
+        self.faces.iter().any(|face| face.has_glyph(c)) || ICON_FACE.has_glyph(c)
+    }
+
+    /// Line height at `font_size`, from the primary face
+    pub fn line_height(&self, font_size: f32) -> f32 {
+        self.primary().line_height(font_size)
+    }
+
+    /// Distance from the top of a line to its baseline, from the primary face
+    pub fn ascent(&self, font_size: f32) -> f32 {
+        self.primary().ascent(font_size)
+    }
+
+    /// Horizontal advance of `c` in the face that provides it
+    pub fn advance(&self, c: char, font_size: f32) -> f32 {
+        self.metrics(Glyph::Char(c), font_size).advance
+    }
+
+    /// Kerning adjustment between `prev` and `c`. Pairs drawn from different
+    /// faces are not kerned.
+    pub fn kern(&self, prev: char, c: char, font_size: f32) -> f32 {
+        if self.face_index(c) == self.face_index(prev) {
+            self.face_for(c).kern(prev, c, font_size)
+        } else {
+            0.0
+        }
+    }
+
+    /// Bounds of the pixels `c` draws, relative to its origin on the
+    /// baseline, or `None` if it draws nothing (e.g. space)
+    pub fn ink_bounds(&self, c: char, font_size: f32) -> Option<InkBounds> {
+        self.glyph_ink_bounds(Glyph::Char(c), font_size)
+    }
+
+    /// Like [`Font::ink_bounds`], for a character or shaped glyph
+    pub fn glyph_ink_bounds(&self, glyph: Glyph, font_size: f32) -> Option<InkBounds> {
+        self.metrics(glyph, font_size).ink
+    }
+
+    /// Rasterize `c` with its origin at (x, baseline). `plot` receives each
+    /// covered pixel and its coverage (0.0-1.0; always 1.0 for bitmap fonts).
+    /// Outline glyphs are cached per size and sub-pixel position, so redrawing
+    /// the same text (a clock, a marquee) doesn't rasterize it again.
+    pub fn draw_glyph(
+        &self,
+        c: char,
+        font_size: f32,
+        x: f32,
+        baseline: f32,
+        mut plot: impl FnMut(i32, i32, f32),
+    ) {
+        self.draw(
+            Glyph::Char(c),
+            font_size,
+            x,
+            baseline,
+            |x, y, coverage, _| plot(x, y, coverage),
+        )
+    }
+
+    /// Like [`Font::draw_glyph`], for a character or shaped glyph. `plot`
+    /// also receives the pixel's own color for color glyphs (emoji, icons),
+    /// or `None` where the text color should be used.
+    pub fn draw(
+        &self,
+        glyph: Glyph,
+        font_size: f32,
+        x: f32,
+        baseline: f32,
+        mut plot: impl FnMut(i32, i32, f32, Option<Rgb<u8>>),
+    ) {
+        let face = self.face_of(glyph);
+        if let (Face::Bitmap(_), Glyph::Char(c)) = (face, glyph) {
+            face.draw(c, font_size, x, baseline, |x, y, coverage| {
+                plot(x, y, coverage, None)
+            });
+            return;
+        }
+        let key = (
+            glyph,
+            font_size.to_bits(),
+            (x - x.floor()).to_bits(),
+            (baseline - baseline.floor()).to_bits(),
+        );
+        let bitmap = self.cache.bitmaps.get_or_insert_with(key, || {
+            let (fx, fy) = (x - x.floor(), baseline - baseline.floor());
+            Arc::new(face.rasterize(glyph, font_size, fx, fy).unwrap_or_default())
+        });
+        bitmap.draw(x.floor() as i32, baseline.floor() as i32, plot);
+    }
+}
+
+impl Default for Font {
+    /// Embedded fonts with system font fallback
+    fn default() -> Self {
+        Font::embedded().with_system_fallback()
+    }
+}
+
+fn embedded_face(data: &'static [u8]) -> Face {
+    Face::Outline(FontArc::new(
+        FontRef::try_from_slice(data).expect("Failed to load embedded font"),
+    ))
+}
+
+/// System fallback faces, loaded once on first use
+fn system_faces() -> &'static [Face] {
+    static SYSTEM: OnceLock<Vec<Face>> = OnceLock::new();
+    SYSTEM.get_or_init(|| {
+        SYSTEM_FALLBACK_PATHS
+            .iter()
+            .filter_map(|path| Font::from_file(Path::new(path)).ok())
+            .flat_map(|font| font.faces)
+            .collect()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dejavu_path() -> std::path::PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR")).join("assets/fonts/DejaVuSans.ttf")
+    }
+
+    #[test]
+    fn test_embedded_font_loads() {
+        let font = Font::embedded();
+        assert!(font.line_height(14.0) > 0.0);
+    }
+
+    #[test]
+    fn test_from_bytes_rejects_garbage() {
+        let result = Font::from_bytes(vec![0, 1, 2, 3]);
+        assert!(matches!(result, Err(FontError::InvalidFont)));
+    }
+
+    #[test]
+    fn test_from_file_missing() {
+        let result = Font::from_file(Path::new("/nonexistent/font.ttf"));
+        assert!(matches!(result, Err(FontError::ReadFailed(_))));
+    }
+
+    #[test]
+    fn test_from_file_loads_ttf() {
+        assert!(Font::from_file(&dejavu_path()).is_ok());
+    }
+
+    #[test]
+    fn test_with_fallback_appends_faces() {
+        let font = Font::from_file(&dejavu_path())
+            .unwrap()
+            .with_fallback(Font::embedded());
+        assert_eq!(font.face_count(), 1 + Font::embedded().face_count());
+    }
+
+    #[test]
+    fn test_glyph_found_in_primary() {
+        assert!(Font::embedded().has_glyph('A'));
+    }
+
+    #[test]
+    fn test_cyrillic_in_embedded() {
+        assert!(Font::embedded().has_glyph('Ж'));
+    }
+
+    #[test]
+    fn test_missing_glyph() {
+        // Private-use codepoint no font maps
+        assert!(!Font::embedded().has_glyph('\u{F8FF}'));
+    }
+
+    #[test]
+    fn test_load_builtin_bitmap_font() {
+        let font = Font::load(Path::new("5x7")).unwrap();
+        assert_eq!(font.line_height(8.0), 8.0);
+        assert_eq!(font.line_height(16.0), 16.0);
+        assert_eq!(font.advance('A', 8.0), 6.0);
+    }
+
+    #[test]
+    fn test_bitmap_falls_back_to_outline() {
+        let font = Font::load(Path::new("5x7"))
+            .unwrap()
+            .with_fallback(Font::embedded());
+        // 'Ж' is not in the 5x7 font, so the embedded outline font provides it
+        assert!(matches!(font.face_for('A'), Face::Bitmap(_)));
+        assert!(matches!(font.face_for('Ж'), Face::Outline(_)));
+    }
+
+    #[test]
+    fn test_bitmap_glyphs_are_not_antialiased() {
+        let font = Font::load(Path::new("5x7")).unwrap();
+        let mut coverages = Vec::new();
+        font.draw_glyph('A', 8.0, 0.0, 7.0, |_, _, c| coverages.push(c));
+        assert!(!coverages.is_empty());
+        assert!(coverages.iter().all(|&c| c == 1.0));
+    }
+
+    #[test]
+    fn test_cached_glyph_matches_direct_render() {
+        let font = Font::embedded();
+        for x in [0.0, 3.25, 17.6] {
+            let mut direct = Vec::new();
+            font.face_for('g').draw('g', 19.0, x, 15.0, |x, y, c| {
+                if c > 0.0 {
+                    direct.push((x, y, c))
+                }
+            });
+            for _ in 0..2 {
+                let mut cached = Vec::new();
+                font.draw_glyph('g', 19.0, x, 15.0, |x, y, c| cached.push((x, y, c)));
+                assert_eq!(cached, direct);
+            }
+        }
+    }
+
+    #[test]
+    fn test_clones_share_glyph_cache() {
+        let font = Font::embedded();
+        let clone = font.clone();
+        font.draw_glyph('A', 14.0, 2.0, 12.0, |_, _, _| {});
+        clone.draw_glyph('A', 14.0, 30.0, 12.0, |_, _, _| {});
+        assert_eq!(font.cache.bitmaps.len(), 1);
+        assert_eq!(clone.advance('A', 14.0), font.advance('A', 14.0));
+        assert_eq!(font.cache.metrics.len(), 1);
+    }
+
+    #[test]
+    fn test_with_fallback_resets_face_lookup() {
+        let font = Font::load(Path::new("5x7")).unwrap();
+        assert!(!font.has_glyph('Ж'));
+        font.advance('Ж', 8.0);
+        let font = font.with_fallback(Font::embedded());
+        assert!(matches!(font.face_for('Ж'), Face::Outline(_)));
+        assert!(font.advance('Ж', 8.0) > 0.0);
+    }
+}
@@ -0,0 +1,398 @@
+use crate::image::Orientation;
+use crate::panel::{ByteOrder, PanelSpec, PixelFormat, Rect};
+use image::{Rgb, RgbImage};
+use std::fs::{self, File};
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+use thiserror::Error;
+
+/// First bytes of a framebuffer state file
+const MAGIC: &[u8; 8] = b"DFSFB001";
+const STATE_DIR: &str = "display-fs";
+const STATE_FILE_PREFIX: &str = "framebuffer-";
+/// Shortest time between two writes of a state file
+const SAVE_INTERVAL: Duration = Duration::from_millis(500);
+/// Largest panel accepted from a state file, in pixels
+const MAX_PIXELS: u32 = 4096 * 4096;
+
+#[derive(Error, Debug)]
+pub enum FramebufferError {
+    #[error("Framebuffer file error: {0}")]
+    Io(#[from] io::Error),
+    #[error("Not a display-fs framebuffer file")]
+    BadMagic,
+    #[error("Unsupported framebuffer format")]
+    Unsupported,
+}
+
+/// What the panel shows: the last frame sent, with any windows drawn over
+/// it since, in the panel's native layout and pixel format. The device
+/// can't be read back, so this is the only record of its contents.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Framebuffer {
+    pub panel: PanelSpec,
+    /// Orientation of the last full frame, used to turn the image upright
+    pub orientation: Orientation,
+    pub data: Vec<u8>,
+}
+
+impl Framebuffer {
+    /// A black panel
+    pub fn new(panel: PanelSpec) -> Self {
+        Framebuffer {
+            panel,
+            orientation: Orientation::default(),
+            data: vec![0; panel.frame_bytes()],
+        }
+    }
+
+    /// Record a full frame
+    pub fn set_frame(&mut self, data: &[u8], orientation: Orientation) {
+        self.data.clear();
+        self.data.extend_from_slice(data);
+        self.data.resize(self.panel.frame_bytes(), 0);
+        self.orientation = orientation;
+    }
+
+    /// Record pixel data drawn into `rect`
+    pub fn blit(&mut self, rect: Rect, data: &[u8]) {
+        self.panel.paste(&mut self.data, rect, data);
+    }
+
+    /// The panel contents as an upright image in the last frame's
+    /// orientation (160x80 for the default panel in landscape)
+    pub fn to_image(&self) -> RgbImage {
+        let panel = self.panel;
+        let bpp = panel.pixel_format.bytes_per_pixel();
+        let pixel_at = |px: u32, py: u32| {
+            let start = (py * panel.width + px) as usize * bpp;
+            decode_pixel(panel.pixel_format, &self.data[start..start + bpp])
+        };
+        let rotated = panel.logical_width(self.orientation) != panel.width;
+        RgbImage::from_fn(
+            panel.logical_width(self.orientation),
+            panel.logical_height(self.orientation),
+            |lx, ly| {
+                if rotated {
+                    // Inverse of the rotation in `image_to_panel_bytes`
+                    pixel_at(panel.width - 1 - ly, lx)
+                } else {
+                    pixel_at(lx, ly)
+                }
+            },
+        )
+    }
+
+    /// Default state file of the display at `device` (a serial port or
+    /// `tcp://` address), so each display keeps its own:
+    /// `framebuffer-<device>.bin` in `$XDG_STATE_HOME/display-fs`,
+    /// `~/.local/state/display-fs` or `%LOCALAPPDATA%\display-fs`
+    pub fn default_path(device: &str) -> Option<PathBuf> {
+        let base = std::env::var_os("XDG_STATE_HOME")
+            .map(PathBuf::from)
+            .or_else(|| {
+                std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".local/state"))
+            })
+            .or_else(|| std::env::var_os("LOCALAPPDATA").map(PathBuf::from))?;
+        Some(base.join(STATE_DIR).join(state_file_name(device)))
+    }
+
+    /// Write to `path`, replacing it atomically so readers never see a
+    /// partial file
+    pub fn save(&self, path: &Path) -> Result<(), FramebufferError> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let partial = path.with_extension("tmp");
+        {
+            let mut out = BufWriter::new(File::create(&partial)?);
+            self.write_to(&mut out)?;
+            out.flush()?;
+        }
+        fs::rename(&partial, path)?;
+        Ok(())
+    }
+
+    pub fn load(path: &Path) -> Result<Self, FramebufferError> {
+        Framebuffer::read_from(BufReader::new(File::open(path)?))
+    }
+
+    /// Write the state file format: magic, width and height (u32 LE), pixel
+    /// format and orientation (one byte each), then the pixel data
+    pub fn write_to(&self, mut out: impl Write) -> Result<(), FramebufferError> {
+        out.write_all(MAGIC)?;
+        out.write_all(&self.panel.width.to_le_bytes())?;
+        out.write_all(&self.panel.height.to_le_bytes())?;
+        let format = match self.panel.pixel_format {
+            PixelFormat::Rgb565(ByteOrder::LittleEndian) => 0,
+            PixelFormat::Rgb565(ByteOrder::BigEndian) => 1,
+            PixelFormat::Rgb888 => 2,
+        };
+        let orientation = match self.orientation {
+            Orientation::Portrait => 0,
+            Orientation::Landscape => 1,
+        };
+        out.write_all(&[format, orientation])?;
+        out.write_all(&self.data)?;
+        Ok(())
+    }
+
+    pub fn read_from(mut reader: impl Read) -> Result<Self, FramebufferError> {
+        let mut header = [0u8; 18];
+        reader.read_exact(&mut header)?;
+        if &header[..8] != MAGIC {
+            return Err(FramebufferError::BadMagic);
+        }
+        let width = u32::from_le_bytes(header[8..12].try_into().unwrap_or_default());
+        let height = u32::from_le_bytes(header[12..16].try_into().unwrap_or_default());
+        let pixels = width.checked_mul(height);
+        if !pixels.is_some_and(|pixels| (1..=MAX_PIXELS).contains(&pixels)) {
+            return Err(FramebufferError::Unsupported);
+        }
+        let pixel_format = match header[16] {
+            0 => PixelFormat::Rgb565(ByteOrder::LittleEndian),
+            1 => PixelFormat::Rgb565(ByteOrder::BigEndian),
+            2 => PixelFormat::Rgb888,
+            _ => return Err(FramebufferError::Unsupported),
+        };
+        let orientation = match header[17] {
+            0 => Orientation::Portrait,
+            1 => Orientation::Landscape,
+            _ => return Err(FramebufferError::Unsupported),
+        };
+        let panel = PanelSpec {
+            width,
+            height,
+            pixel_format,
+        };
+        let mut data = vec![0u8; panel.frame_bytes()];
+        reader.read_exact(&mut data)?;
+        Ok(Framebuffer {
+            panel,
+            orientation,
+            data,
+        })
+    }
+}
+
+/// `/dev/ttyACM0` → `framebuffer-dev-ttyACM0.bin`,
+/// `tcp://pi:7777` → `framebuffer-tcp-pi-7777.bin`
+fn state_file_name(device: &str) -> String {
+    let key: Vec<&str> = device
+        .split(|c: char| !(c.is_ascii_alphanumeric() || c == '.' || c == '_'))
+        .filter(|part| !part.is_empty())
+        .collect();
+    format!("{}{}.bin", STATE_FILE_PREFIX, key.join("-"))
+}
+
+#[derive(Default)]
+struct WriterState {
+    /// Newest framebuffer not yet written
+    pending: Option<Framebuffer>,
+    closed: bool,
+}
+
+struct WriterShared {
+    state: Mutex<WriterState>,
+    changed: Condvar,
+}
+
+impl WriterShared {
+    fn lock(&self) -> MutexGuard<'_, WriterState> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// Writes a state file from a background thread, so sends never wait on
+/// the disk. Updates arriving within [`SAVE_INTERVAL`] of a write are
+/// coalesced, and the newest one is written when the writer is dropped.
+pub(crate) struct StateWriter {
+    shared: Arc<WriterShared>,
+    worker: Option<JoinHandle<()>>,
+}
+
+impl StateWriter {
+    pub fn spawn(path: PathBuf) -> Self {
+        let shared = Arc::new(WriterShared {
+            state: Mutex::new(WriterState::default()),
+            changed: Condvar::new(),
+        });
+        let worker = {
+            let shared = Arc::clone(&shared);
+            thread::spawn(move || write_states(&path, &shared))
+        };
+        StateWriter {
+            shared,
+            worker: Some(worker),
+        }
+    }
+
+    /// Queue `framebuffer` to be written, replacing any not written yet
+    pub fn update(&self, framebuffer: &Framebuffer) {
+        let mut state = self.shared.lock();
+        match &mut state.pending {
+            Some(pending) => pending.clone_from(framebuffer),
+            None => state.pending = Some(framebuffer.clone()),
+        }
+        self.shared.changed.notify_all();
+    }
+}
+
+impl Drop for StateWriter {
+    fn drop(&mut self) {
+        self.shared.lock().closed = true;
+        self.shared.changed.notify_all();
+        if let Some(worker) = self.worker.take() {
+            let _ = worker.join();
+        }
+    }
+}
+
+fn write_states(path: &Path, shared: &WriterShared) {
+    loop {
+        let framebuffer = {
+            let mut state = shared.lock();
+            while state.pending.is_none() && !state.closed {
+                state = shared
+                    .changed
+                    .wait(state)
+                    .unwrap_or_else(|e| e.into_inner());
+            }
+            match state.pending.take() {
+                Some(framebuffer) => framebuffer,
+                None => return,
+            }
+        };
+        // The state file only serves screenshots; failing to write it
+        // mustn't stop the display
+        let _ = framebuffer.save(path);
+
+        // Let updates collect before the next write; closing ends the wait
+        let deadline = Instant::now() + SAVE_INTERVAL;
+        let mut state = shared.lock();
+        while !state.closed {
+            let Some(left) = deadline.checked_duration_since(Instant::now()) else {
+                break;
+            };
+            state = shared
+                .changed
+                .wait_timeout(state, left)
+                .unwrap_or_else(|e| e.into_inner())
+                .0;
+        }
+    }
+}
+
+/// One pixel in `format` back to 8-bit RGB
+fn decode_pixel(format: PixelFormat, bytes: &[u8]) -> Rgb<u8> {
+    match format {
+        PixelFormat::Rgb565(order) => {
+            let bytes = [bytes[0], bytes[1]];
+            let value = match order {
+                ByteOrder::LittleEndian => u16::from_le_bytes(bytes),
+                ByteOrder::BigEndian => u16::from_be_bytes(bytes),
+            };
+            let r = (value >> 11) as u8 & 0x1F;
+            let g = (value >> 5) as u8 & 0x3F;
+            let b = value as u8 & 0x1F;
+            Rgb([
+                (r << 3) | (r >> 2),
+                (g << 2) | (g >> 4),
+                (b << 3) | (b >> 2),
+            ])
+        }
+        PixelFormat::Rgb888 => Rgb([bytes[0], bytes[1], bytes[2]]),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::image::image_to_panel_bytes;
+    use crate::quantize::ColorOptions;
+
+    #[test]
+    fn test_image_round_trips_through_panel_bytes() {
+        let panel = PanelSpec::WEACT_FS_096;
+        for orientation in [Orientation::Landscape, Orientation::Portrait] {
+            let (width, height) = (
+                panel.logical_width(orientation),
+                panel.logical_height(orientation),
+            );
+            let mut img = RgbImage::new(width, height);
+            img.put_pixel(width - 3, 5, Rgb([255, 0, 0]));
+            img.put_pixel(1, height - 2, Rgb([0, 0, 255]));
+            let data = image_to_panel_bytes(&img, orientation, ColorOptions::default(), panel);
+
+            let mut framebuffer = Framebuffer::new(panel);
+            framebuffer.set_frame(&data, orientation);
+            assert_eq!(framebuffer.to_image(), img, "{:?}", orientation);
+        }
+    }
+
+    #[test]
+    fn test_blit_draws_over_frame() {
+        let mut framebuffer = Framebuffer::new(PanelSpec::WEACT_FS_096);
+        framebuffer.set_frame(&[0; 80 * 160 * 2], Orientation::Portrait);
+        framebuffer.blit(Rect::new(10, 20, 1, 1), &0xFFFFu16.to_le_bytes());
+        assert_eq!(
+            framebuffer.to_image().get_pixel(10, 20),
+            &Rgb([255, 255, 255])
+        );
+    }
+
+    #[test]
+    fn test_state_file_per_device() {
+        assert_eq!(
+            state_file_name("/dev/ttyACM0"),
+            "framebuffer-dev-ttyACM0.bin"
+        );
+        assert_eq!(
+            state_file_name("tcp://pi.local:7777"),
+            "framebuffer-tcp-pi.local-7777.bin"
+        );
+        assert_ne!(state_file_name("COM3"), state_file_name("COM4"));
+    }
+
+    #[test]
+    fn test_state_writer_saves_latest_on_drop() {
+        let path = std::env::temp_dir().join(format!(
+            "display-fs-state-{}/framebuffer.bin",
+            std::process::id()
+        ));
+        let mut framebuffer = Framebuffer::new(PanelSpec::WEACT_FS_096);
+        {
+            let writer = StateWriter::spawn(path.clone());
+            for value in 1..=5u8 {
+                framebuffer.set_frame(&[value; 80 * 160 * 2], Orientation::Landscape);
+                writer.update(&framebuffer);
+            }
+        }
+        assert_eq!(Framebuffer::load(&path).unwrap(), framebuffer);
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn test_state_file_round_trip() {
+        let mut framebuffer = Framebuffer::new(PanelSpec::WEACT_FS_096);
+        framebuffer.set_frame(&[0xA5; 80 * 160 * 2], Orientation::Landscape);
+        let mut file = Vec::new();
+        framebuffer.write_to(&mut file).unwrap();
+        assert_eq!(Framebuffer::read_from(&file[..]).unwrap(), framebuffer);
+
+        assert!(matches!(
+            Framebuffer::read_from(&b"DFSCAP01__________"[..]),
+            Err(FramebufferError::BadMagic)
+        ));
+
+        // A corrupt size is rejected before anything is allocated
+        file[8..12].copy_from_slice(&u32::MAX.to_le_bytes());
+        assert!(matches!(
+            Framebuffer::read_from(&file[..]),
+            Err(FramebufferError::Unsupported)
+        ));
+    }
+}
@@ -0,0 +1,242 @@
+use crate::font::{Glyph, ShapedGlyph};
+use crate::layout::InkBounds;
+use image::{Rgb, RgbaImage};
+use std::collections::HashMap;
+use std::fmt;
+use std::hash::Hash;
+use std::sync::{Arc, Mutex, MutexGuard};
+
+/// Entries kept per table. A table is emptied when it fills up, which bounds
+/// memory for text that never repeats (e.g. scrolling logs).
+const MAX_ENTRIES: usize = 4096;
+
+/// Advance and ink bounds of one glyph at one size
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct GlyphMetrics {
+    pub advance: f32,
+    pub ink: Option<InkBounds>,
+}
+
+/// Samples per pixel along each axis when scaling color images
+const SUPERSAMPLE: u32 = 4;
+
+/// A rasterized glyph: coverage values row by row, placed relative to the
+/// whole-pixel origin it was rasterized for. Color glyphs (emoji, icons)
+/// carry their own color per pixel; others take the text color.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub(crate) struct GlyphBitmap {
+    pub left: i32,
+    pub top: i32,
+    pub width: usize,
+    pub coverage: Vec<f32>,
+    pub colors: Option<Vec<Rgb<u8>>>,
+}
+
+impl GlyphBitmap {
+    /// Scale a color image into the box at (`left`, `top`) of `width` x
+    /// `height` pixels, averaging the covered source pixels
+    pub fn from_rgba(image: &RgbaImage, left: f32, top: f32, width: f32, height: f32) -> Self {
+        let (x0, y0) = (left.floor() as i32, top.floor() as i32);
+        let (x1, y1) = ((left + width).ceil() as i32, (top + height).ceil() as i32);
+        let columns = (x1 - x0).max(0) as usize;
+        let rows = (y1 - y0).max(0) as usize;
+        let mut coverage = Vec::with_capacity(columns * rows);
+        let mut colors = Vec::with_capacity(columns * rows);
+
+        for py in y0..y1 {
+            for px in x0..x1 {
+                let mut alpha = 0.0;
+                let mut sum = [0.0f32; 3];
+                for sy in 0..SUPERSAMPLE {
+                    for sx in 0..SUPERSAMPLE {
+                        let x = px as f32 + (sx as f32 + 0.5) / SUPERSAMPLE as f32;
+                        let y = py as f32 + (sy as f32 + 0.5) / SUPERSAMPLE as f32;
+                        let u = (x - left) / width * image.width() as f32;
+                        let v = (y - top) / height * image.height() as f32;
+                        if u < 0.0
+                            || v < 0.0
+                            || u >= image.width() as f32
+                            || v >= image.height() as f32
+                        {
+                            continue;
+                        }
+                        let pixel = image.get_pixel(u as u32, v as u32).0;
+                        let a = pixel[3] as f32 / 255.0;
+                        alpha += a;
+                        for (total, channel) in sum.iter_mut().zip(pixel) {
+                            *total += channel as f32 * a;
+                        }
+                    }
+                }
+                let samples = (SUPERSAMPLE * SUPERSAMPLE) as f32;
+                coverage.push(alpha / samples);
+                colors.push(if alpha > 0.0 {
+                    Rgb(sum.map(|total| (total / alpha).round() as u8))
+                } else {
+                    Rgb([0, 0, 0])
+                });
+            }
+        }
+
+        GlyphBitmap {
+            left: x0,
+            top: y0,
+            width: columns,
+            coverage,
+            colors: Some(colors),
+        }
+    }
+
+    /// Call `plot(x, y, coverage, color)` for each pixel, with the origin at
+    /// (x, y). `color` is `None` for pixels drawn in the text color.
+    pub fn draw(&self, x: i32, y: i32, mut plot: impl FnMut(i32, i32, f32, Option<Rgb<u8>>)) {
+        if self.width == 0 {
+            return;
+        }
+        for (row, values) in self.coverage.chunks(self.width).enumerate() {
+            for (col, &coverage) in values.iter().enumerate() {
+                if coverage > 0.0 {
+                    let color = self
+                        .colors
+                        .as_ref()
+                        .map(|colors| colors[row * self.width + col]);
+                    plot(
+                        x + self.left + col as i32,
+                        y + self.top + row as i32,
+                        coverage,
+                        color,
+                    );
+                }
+            }
+        }
+    }
+}
+
+/// Key of a rasterized glyph: glyph, size, and the sub-pixel part of its
+/// origin (as `f32` bits, so cached renders match uncached ones exactly)
+pub(crate) type BitmapKey = (Glyph, u32, u32, u32);
+
+/// Key of a shaped run: text, size and whether it is right-to-left
+pub(crate) type ShapeKey = (String, u32, bool);
+
+/// Lookups shared by every clone of a `Font`: which face provides each
+/// character, glyph metrics, shaped runs and rasterized glyphs per size
+#[derive(Default)]
+pub(crate) struct GlyphCache {
+    pub faces: Table<char, usize>,
+    pub metrics: Table<(Glyph, u32), GlyphMetrics>,
+    pub shaped: Table<ShapeKey, Option<Arc<[ShapedGlyph]>>>,
+    pub bitmaps: Table<BitmapKey, Arc<GlyphBitmap>>,
+}
+
+impl fmt::Debug for GlyphCache {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("GlyphCache")
+            .field("faces", &self.faces.len())
+            .field("metrics", &self.metrics.len())
+            .field("shaped", &self.shaped.len())
+            .field("bitmaps", &self.bitmaps.len())
+            .finish()
+    }
+}
+
+/// A bounded, thread-safe memo table
+pub(crate) struct Table<K, V> {
+    entries: Mutex<HashMap<K, V>>,
+}
+
+impl<K, V> Default for Table<K, V> {
+    fn default() -> Self {
+        Table {
+            entries: Mutex::new(HashMap::new()),
+        }
+    }
+}
+
+impl<K: Hash + Eq, V: Clone> Table<K, V> {
+    /// The cached value for `key`, computing and storing it on a miss.
+    /// `compute` runs without the lock held, so it may use other tables.
+    pub fn get_or_insert_with(&self, key: K, compute: impl FnOnce() -> V) -> V {
+        if let Some(value) = self.lock().get(&key) {
+            return value.clone();
+        }
+        let value = compute();
+        let mut entries = self.lock();
+        if entries.len() >= MAX_ENTRIES {
+            entries.clear();
+        }
+        entries.insert(key, value.clone());
+        value
+    }
+
+    pub fn len(&self) -> usize {
+        self.lock().len()
+    }
+
+    fn lock(&self) -> MutexGuard<'_, HashMap<K, V>> {
+        // A panic while holding the lock can't leave a HashMap half-written
+        // in a way that matters for a cache, so ignore poisoning
+        self.entries
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_table_computes_once() {
+        let table = Table::default();
+        let mut calls = 0;
+        for _ in 0..3 {
+            let value = table.get_or_insert_with('a', || {
+                calls += 1;
+                42
+            });
+            assert_eq!(value, 42);
+        }
+        assert_eq!(calls, 1);
+        assert_eq!(table.len(), 1);
+    }
+
+    #[test]
+    fn test_table_is_bounded() {
+        let table = Table::default();
+        for i in 0..MAX_ENTRIES as u32 + 10 {
+            table.get_or_insert_with(i, || i);
+        }
+        assert!(table.len() <= MAX_ENTRIES);
+    }
+
+    #[test]
+    fn test_bitmap_draw_offsets() {
+        let bitmap = GlyphBitmap {
+            left: 1,
+            top: -2,
+            width: 2,
+            coverage: vec![1.0, 0.0, 0.5, 0.25],
+            colors: None,
+        };
+        let mut pixels = Vec::new();
+        bitmap.draw(10, 20, |x, y, c, _| pixels.push((x, y, c)));
+        assert_eq!(pixels, vec![(11, 18, 1.0), (11, 19, 0.5), (12, 19, 0.25)]);
+    }
+
+    #[test]
+    fn test_from_rgba_scales_up() {
+        let image = RgbaImage::from_fn(2, 1, |x, _| {
+            if x == 0 {
+                image::Rgba([255, 0, 0, 255])
+            } else {
+                image::Rgba([0, 0, 0, 0])
+            }
+        });
+        let bitmap = GlyphBitmap::from_rgba(&image, 0.0, -4.0, 4.0, 4.0);
+        assert_eq!((bitmap.left, bitmap.top, bitmap.width), (0, -4, 4));
+        // Left half opaque red, right half transparent
+        assert_eq!(&bitmap.coverage[..4], &[1.0, 1.0, 0.0, 0.0]);
+        assert_eq!(bitmap.colors.unwrap()[0], Rgb([255, 0, 0]));
+    }
+}
@@ -0,0 +1,212 @@
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::OnceLock;
+
+/// Hyphenation dictionaries tried (in order) before the built-in patterns
+const SYSTEM_PATTERN_PATHS: &[&str] = &[
+    "/usr/share/hyphen/hyph_en_US.dic",
+    "/usr/share/hyphen/hyph_en_GB.dic",
+    "/usr/share/hunspell/hyph_en_US.dic",
+    "/usr/share/myspell/dicts/hyph_en_US.dic",
+    "/opt/homebrew/share/hyphen/hyph_en_US.dic",
+    "/usr/local/share/hyphen/hyph_en_US.dic",
+];
+
+/// A small set of English patterns, enough for common suffixes, prefixes
+/// and double consonants when no system dictionary is installed
+const BUILTIN_PATTERNS: &str = "
+b1b c1c d1d f1f g1g l1l m1m n1n p1p r1r s1s t1t z1z
+1tion 1sion 1ment 1ness 1less 1ship 1hood 1ful 1able 1ible
+1ware 1work 1name 1file 1base 1line 1time 1path 1port 1ture
+.un1 .dis1 .non1 .over1 .under1 .inter1 .trans1 .super1 .multi1
+c2h s2h t2h p2h w2h c2k
+";
+
+/// Liang/TeX hyphenation patterns, as used by TeX and LibreOffice (`.dic`)
+#[derive(Debug, Clone)]
+pub struct Hyphenator {
+    /// Pattern letters mapped to the digits between (and around) them
+    patterns: HashMap<String, Vec<u8>>,
+    max_pattern_len: usize,
+    /// Minimum characters kept before the first hyphen
+    left_min: usize,
+    /// Minimum characters kept after the last hyphen
+    right_min: usize,
+}
+
+impl Hyphenator {
+    /// Parse whitespace-separated patterns such as `hy3ph` or `.un1`.
+    /// Accepts TeX pattern lists and LibreOffice `hyph_*.dic` files (whose
+    /// charset line and `LEFTHYPHENMIN`-style keywords are skipped).
+    pub fn parse(source: &str) -> Hyphenator {
+        let mut hyphenator = Hyphenator {
+            patterns: HashMap::new(),
+            max_pattern_len: 0,
+            left_min: 2,
+            right_min: 3,
+        };
+
+        for line in source.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('%') {
+                continue;
+            }
+            if let Some((keyword, value)) = line.split_once(char::is_whitespace) {
+                let value = value.trim().parse::<usize>().ok();
+                match (keyword, value) {
+                    // A hyphen needs at least one character on each side
+                    ("LEFTHYPHENMIN", Some(min)) => hyphenator.left_min = min.max(1),
+                    ("RIGHTHYPHENMIN", Some(min)) => hyphenator.right_min = min.max(1),
+                    _ if keyword.chars().all(|c| c.is_ascii_uppercase() || c == '_') => {}
+                    _ => hyphenator.add_patterns(line),
+                }
+                continue;
+            }
+            // A lone uppercase/charset token (e.g. `UTF-8`) is a header line
+            if !line.chars().any(|c| c.is_lowercase()) {
+                continue;
+            }
+            hyphenator.add_patterns(line);
+        }
+
+        hyphenator
+    }
+
+    pub fn load(path: &Path) -> std::io::Result<Hyphenator> {
+        let data = std::fs::read(path)?;
+        Ok(Hyphenator::parse(&String::from_utf8_lossy(&data)))
+    }
+
+    /// Patterns embedded in the binary
+    pub fn builtin() -> Hyphenator {
+        Hyphenator::parse(BUILTIN_PATTERNS)
+    }
+
+    /// English patterns: an installed system dictionary if there is one,
+    /// otherwise the built-in set. Loaded once on first use.
+    pub fn english() -> &'static Hyphenator {
+        static ENGLISH: OnceLock<Hyphenator> = OnceLock::new();
+        ENGLISH.get_or_init(|| {
+            SYSTEM_PATTERN_PATHS
+                .iter()
+                .find_map(|path| Hyphenator::load(Path::new(path)).ok())
+                .filter(|hyphenator| !hyphenator.patterns.is_empty())
+                .unwrap_or_else(Hyphenator::builtin)
+        })
+    }
+
+    fn add_patterns(&mut self, line: &str) {
+        'patterns: for pattern in line.split_whitespace() {
+            if pattern.starts_with('%') {
+                break;
+            }
+            let mut letters = String::new();
+            let mut values = vec![0u8];
+            for c in pattern.chars() {
+                match c.to_digit(10) {
+                    Some(digit) => *values.last_mut().unwrap() = digit as u8,
+                    None => {
+                        // Lowercasing can change the length (e.g. 'İ'), which
+                        // would shift the values; `hyphenate` skips such words too
+                        let mut lower = c.to_lowercase();
+                        let (Some(lower), None) = (lower.next(), lower.next()) else {
+                            continue 'patterns;
+                        };
+                        letters.push(lower);
+                        values.push(0);
+                    }
+                }
+            }
+            if letters.is_empty() {
+                continue;
+            }
+            self.max_pattern_len = self.max_pattern_len.max(letters.chars().count());
+            self.patterns.insert(letters, values);
+        }
+    }
+
+    /// Character positions in `word` where a hyphen may be inserted
+    /// (a position `i` means between `word[i - 1]` and `word[i]`)
+    pub fn hyphenate(&self, word: &str) -> Vec<usize> {
+        let len = word.chars().count();
+        if len < self.left_min + self.right_min {
+            return Vec::new();
+        }
+
+        let dotted: Vec<char> = std::iter::once('.')
+            .chain(word.chars().flat_map(char::to_lowercase))
+            .chain(std::iter::once('.'))
+            .collect();
+        // Lowercasing can change the length (e.g. 'İ'); skip such words
+        if dotted.len() != len + 2 {
+            return Vec::new();
+        }
+
+        let mut points = vec![0u8; dotted.len() + 1];
+        let mut key = String::new();
+        for start in 0..dotted.len() {
+            key.clear();
+            for &c in dotted[start..].iter().take(self.max_pattern_len) {
+                key.push(c);
+                if let Some(values) = self.patterns.get(&key) {
+                    for (offset, &value) in values.iter().enumerate() {
+                        let point = &mut points[start + offset];
+                        *point = (*point).max(value);
+                    }
+                }
+            }
+        }
+
+        // points[i + 1] sits between word[i - 1] and word[i]
+        (self.left_min..=len - self.right_min)
+            .filter(|&i| points[i + 1] % 2 == 1)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_skips_patterns_that_change_length_when_lowercased() {
+        let hyphenator = Hyphenator::parse("a1İ1b c1d");
+        assert_eq!(hyphenator.patterns.len(), 1);
+        assert_eq!(hyphenator.patterns.get("cd"), Some(&vec![0, 1, 0]));
+    }
+
+    #[test]
+    fn test_zero_hyphen_min_keeps_a_character() {
+        let hyphenator = Hyphenator::parse("LEFTHYPHENMIN 0\nRIGHTHYPHENMIN 0\n1a1 1b1");
+        assert_eq!(hyphenator.hyphenate("ab"), vec![1]);
+    }
+
+    #[test]
+    fn test_liang_example() {
+        // The patterns Liang's thesis uses to hyphenate "hyphenation"
+        let hyphenator = Hyphenator::parse("hy3ph he2n hena4 hen5at 1na n2at 1tio 2io o2n");
+        assert_eq!(hyphenator.hyphenate("hyphenation"), vec![2, 6]);
+    }
+
+    #[test]
+    fn test_respects_minimum_lengths() {
+        let hyphenator = Hyphenator::parse("1b");
+        assert!(hyphenator.hyphenate("abba").is_empty());
+        assert_eq!(hyphenator.hyphenate("aabbbaa"), vec![2, 3, 4]);
+    }
+
+    #[test]
+    fn test_parse_dic_header() {
+        let hyphenator = Hyphenator::parse("UTF-8\nLEFTHYPHENMIN 1\nRIGHTHYPHENMIN 1\n1b\n");
+        assert_eq!(hyphenator.left_min, 1);
+        assert_eq!(hyphenator.hyphenate("abab"), vec![1, 3]);
+    }
+
+    #[test]
+    fn test_builtin_patterns() {
+        let hyphenator = Hyphenator::builtin();
+        assert_eq!(hyphenator.hyphenate("configuration"), vec![9]);
+        assert_eq!(hyphenator.hyphenate("Butter"), vec![3]);
+        assert!(hyphenator.hyphenate("unhappiness").contains(&2));
+    }
+}
@@ -0,0 +1,317 @@
+use crate::glyph_cache::GlyphBitmap;
+use crate::layout::InkBounds;
+use image::{Rgba, RgbaImage};
+
+/// Codepoint of the first icon. Icons live in Supplementary Private Use
+/// Area-B, which icon fonts such as Nerd Fonts leave alone, so they can pass
+/// through wrapping and markup like any other character.
+const ICON_BASE: u32 = 0x10_0000;
+
+/// Icon height relative to the font size
+const ICON_SCALE: f32 = 0.8;
+
+/// A small color icon, drawn inline with text
+#[derive(Debug)]
+pub struct Icon {
+    pub name: &'static str,
+    /// Emoji and symbols drawn with this icon instead of a font glyph
+    pub aliases: &'static [char],
+    /// Pixel rows; see [`palette`] for the colors, `.` is transparent
+    rows: &'static [&'static str],
+}
+
+/// The built-in icons, referenced in markup as `:name:`
+pub const ICONS: &[Icon] = &[
+    Icon {
+        name: "wifi",
+        aliases: &['\u{1F4F6}'],
+        rows: &[
+            "............",
+            "...BBBBBB...",
+            ".BB......BB.",
+            "B...BBBB...B",
+            "..BB....BB..",
+            ".B..BBBB..B.",
+            "...B....B...",
+            "....BBBB....",
+            "............",
+            ".....BB.....",
+            ".....BB.....",
+            "............",
+        ],
+    },
+    Icon {
+        name: "cpu",
+        aliases: &[],
+        rows: &[
+            "..K.K..K.K..",
+            "..K.K..K.K..",
+            "KKSSSSSSSSKK",
+            "..SDDDDDDS..",
+            "KKSDDDDDDSKK",
+            "..SDDDDDDS..",
+            "..SDDDDDDS..",
+            "KKSDDDDDDSKK",
+            "..SDDDDDDS..",
+            "KKSSSSSSSSKK",
+            "..K.K..K.K..",
+            "..K.K..K.K..",
+        ],
+    },
+    Icon {
+        name: "play",
+        aliases: &['\u{25B6}'],
+        rows: &[
+            "............",
+            ".GG.........",
+            ".GGGG.......",
+            ".GGGGGG.....",
+            ".GGGGGGGG...",
+            ".GGGGGGGGGG.",
+            ".GGGGGGGGGG.",
+            ".GGGGGGGG...",
+            ".GGGGGG.....",
+            ".GGGG.......",
+            ".GG.........",
+            "............",
+        ],
+    },
+    Icon {
+        name: "pause",
+        aliases: &['\u{23F8}'],
+        rows: &[
+            "............",
+            ".YYYY..YYYY.",
+            ".YYYY..YYYY.",
+            ".YYYY..YYYY.",
+            ".YYYY..YYYY.",
+            ".YYYY..YYYY.",
+            ".YYYY..YYYY.",
+            ".YYYY..YYYY.",
+            ".YYYY..YYYY.",
+            ".YYYY..YYYY.",
+            ".YYYY..YYYY.",
+            "............",
+        ],
+    },
+    Icon {
+        name: "check",
+        aliases: &['\u{2705}', '\u{2714}'],
+        rows: &[
+            "............",
+            "..........GG",
+            ".........GGG",
+            "........GGG.",
+            ".......GGG..",
+            "GG....GGG...",
+            "GGG..GGG....",
+            ".GGGGGG.....",
+            "..GGGG......",
+            "...GG.......",
+            "............",
+            "............",
+        ],
+    },
+    Icon {
+        name: "cross",
+        aliases: &['\u{274C}', '\u{2716}'],
+        rows: &[
+            "RR........RR",
+            "RRR......RRR",
+            ".RRR....RRR.",
+            "..RRR..RRR..",
+            "...RRRRRR...",
+            "....RRRR....",
+            "....RRRR....",
+            "...RRRRRR...",
+            "..RRR..RRR..",
+            ".RRR....RRR.",
+            "RRR......RRR",
+            "RR........RR",
+        ],
+    },
+    Icon {
+        name: "sun",
+        aliases: &['\u{2600}'],
+        rows: &[
+            ".....Y.....",
+            ".Y...Y...Y.",
+            "..Y.....Y..",
+            "....OOO....",
+            "...OOOOO...",
+            "YY.OOOOO.YY",
+            "...OOOOO...",
+            "....OOO....",
+            "..Y.....Y..",
+            ".Y...Y...Y.",
+            ".....Y.....",
+        ],
+    },
+    Icon {
+        name: "mail",
+        aliases: &['\u{1F4E7}', '\u{2709}'],
+        rows: &[
+            "............",
+            "WWWWWWWWWWWW",
+            "WW........WW",
+            "W.W......W.W",
+            "W..W....W..W",
+            "W...W..W...W",
+            "W....WW....W",
+            "W..........W",
+            "W..........W",
+            "WWWWWWWWWWWW",
+            "............",
+            "............",
+        ],
+    },
+    Icon {
+        name: "music",
+        aliases: &['\u{266A}', '\u{1F3B5}'],
+        rows: &[
+            ".....CC.....",
+            ".....CCC....",
+            ".....C.CC...",
+            ".....C..CC..",
+            ".....C...C..",
+            ".....C......",
+            ".....C......",
+            "..CCCC......",
+            ".CCCCC......",
+            ".CCCCC......",
+            "..CCC.......",
+            "............",
+        ],
+    },
+];
+
+/// Colors of the icon pixel art
+fn palette(c: char) -> Option<Rgba<u8>> {
+    let [r, g, b] = match c {
+        'W' => [255, 255, 255],
+        'B' => [64, 156, 255],
+        'G' => [60, 208, 80],
+        'R' => [240, 64, 64],
+        'Y' => [255, 208, 32],
+        'O' => [255, 144, 16],
+        'C' => [64, 224, 224],
+        'K' => [144, 144, 144],
+        'S' => [192, 192, 192],
+        'D' => [48, 48, 64],
+        _ => return None,
+    };
+    Some(Rgba([r, g, b, 255]))
+}
+
+impl Icon {
+    /// The character that draws this icon
+    pub fn char(&self) -> char {
+        let index = ICONS
+            .iter()
+            .position(|icon| icon.name == self.name)
+            .unwrap_or(0);
+        char::from_u32(ICON_BASE + index as u32).unwrap_or(char::REPLACEMENT_CHARACTER)
+    }
+
+    fn side(font_size: f32) -> f32 {
+        (font_size * ICON_SCALE).round().max(1.0)
+    }
+
+    /// Space on either side of the icon
+    fn padding(font_size: f32) -> f32 {
+        (Icon::side(font_size) / 10.0).round()
+    }
+
+    pub fn advance(&self, font_size: f32) -> f32 {
+        Icon::side(font_size) + 2.0 * Icon::padding(font_size)
+    }
+
+    /// The icon's square, sitting slightly below the baseline like an emoji
+    pub fn ink_bounds(&self, font_size: f32) -> InkBounds {
+        let side = Icon::side(font_size);
+        let left = Icon::padding(font_size);
+        let top = (-side * 0.9).round();
+        InkBounds {
+            min_x: left,
+            min_y: top,
+            max_x: left + side,
+            max_y: top + side,
+        }
+    }
+
+    /// Draw the icon at `font_size`, for a pen origin `x` pixels right of
+    /// a whole pixel
+    pub(crate) fn rasterize(&self, font_size: f32, x: f32) -> GlyphBitmap {
+        let bounds = self.ink_bounds(font_size);
+        let width = self.rows.iter().map(|row| row.len()).max().unwrap_or(0) as u32;
+        let pixels = RgbaImage::from_fn(width, self.rows.len() as u32, |px, py| {
+            self.rows[py as usize]
+                .chars()
+                .nth(px as usize)
+                .and_then(palette)
+                .unwrap_or(Rgba([0, 0, 0, 0]))
+        });
+        // Keep the pixel art on whole pixels so it stays crisp
+        let left = (bounds.min_x + x).round();
+        GlyphBitmap::from_rgba(&pixels, left, bounds.min_y, bounds.width(), bounds.height())
+    }
+}
+
+/// Look up an icon by name
+pub fn icon(name: &str) -> Option<&'static Icon> {
+    ICONS.iter().find(|icon| icon.name == name)
+}
+
+/// The icon drawn for `c`: an icon character, or an emoji alias
+pub fn icon_for(c: char) -> Option<&'static Icon> {
+    let index = (c as u32).checked_sub(ICON_BASE);
+    index
+        .and_then(|index| ICONS.get(index as usize))
+        .or_else(|| ICONS.iter().find(|icon| icon.aliases.contains(&c)))
+}
+
+/// The icon a `:name:` reference at the start of `text` refers to, and the
+/// length of the reference
+pub fn icon_reference(text: &str) -> Option<(&'static Icon, usize)> {
+    let name = text.strip_prefix(':')?;
+    let end = name.find(':')?;
+    icon(&name[..end]).map(|icon| (icon, end + 2))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_icon_chars_round_trip() {
+        for icon in ICONS {
+            assert_eq!(icon_for(icon.char()).unwrap().name, icon.name);
+        }
+    }
+
+    #[test]
+    fn test_icon_reference() {
+        let (icon, len) = icon_reference(":wifi: ok").unwrap();
+        assert_eq!((icon.name, len), ("wifi", 6));
+        assert!(icon_reference(":nope:").is_none());
+        assert!(icon_reference("12:34").is_none());
+    }
+
+    #[test]
+    fn test_emoji_alias() {
+        assert_eq!(icon_for('\u{1F4E7}').unwrap().name, "mail");
+        assert!(icon_for('A').is_none());
+    }
+
+    #[test]
+    fn test_rasterize_is_colored_square() {
+        let bitmap = icon("cross").unwrap().rasterize(20.0, 0.0);
+        let side = Icon::side(20.0) as usize;
+        assert_eq!(bitmap.width, side);
+        assert_eq!(bitmap.coverage.len(), side * side);
+        let colors = bitmap.colors.as_ref().unwrap();
+        // The corners of the cross are red
+        assert!(bitmap.coverage[0] > 0.9);
+        assert_eq!(colors[0], image::Rgb([240, 64, 64]));
+    }
+}
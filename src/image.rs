@@ -1,10 +1,9 @@
-use ab_glyph::{FontRef, PxScale};
+use crate::font::Font;
+use crate::layout::{bold_offset, BlockLayout, LayoutOptions, LineLayout};
+use crate::panel::{PanelSpec, PixelFormat};
+use crate::quantize::{correct_colors, quantize_rgb565, ColorOptions};
+use crate::theme::Theme;
 use image::{Rgb, RgbImage};
-use imageproc::drawing::draw_text_mut;
-
-/// Physical display dimensions (hardware is 80x160 portrait)
-const PHYSICAL_WIDTH: u32 = 80;
-const PHYSICAL_HEIGHT: u32 = 160;
 
 /// Display orientation
 #[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
@@ -17,32 +16,20 @@ pub enum Orientation {
 }
 
 impl Orientation {
-    /// Get logical display width for this orientation
+    /// Get logical display width for this orientation on the default panel
     pub fn width(self) -> u32 {
-        match self {
-            Orientation::Landscape => PHYSICAL_HEIGHT, // 160
-            Orientation::Portrait => PHYSICAL_WIDTH,   // 80
-        }
+        PanelSpec::default().logical_width(self)
     }
 
-    /// Get logical display height for this orientation
+    /// Get logical display height for this orientation on the default panel
     pub fn height(self) -> u32 {
-        match self {
-            Orientation::Landscape => PHYSICAL_WIDTH, // 80
-            Orientation::Portrait => PHYSICAL_HEIGHT, // 160
-        }
+        PanelSpec::default().logical_height(self)
     }
 }
 
 // Legacy constants for backward compatibility (default to landscape: 160x80)
-pub const DISPLAY_WIDTH: u32 = PHYSICAL_HEIGHT; // 160
-pub const DISPLAY_HEIGHT: u32 = PHYSICAL_WIDTH; // 80
-
-#[cfg(feature = "japanese")]
-const FONT_DATA: &[u8] = include_bytes!("../assets/fonts/NotoSansJP-Regular.otf");
-
-#[cfg(not(feature = "japanese"))]
-const FONT_DATA: &[u8] = include_bytes!("../assets/fonts/DejaVuSans.ttf");
+pub const DISPLAY_WIDTH: u32 = PanelSpec::WEACT_FS_096.height; // 160
+pub const DISPLAY_HEIGHT: u32 = PanelSpec::WEACT_FS_096.width; // 80
 
 pub fn create_blank_image() -> RgbImage {
     RgbImage::from_pixel(DISPLAY_WIDTH, DISPLAY_HEIGHT, Rgb([0, 0, 0]))
@@ -63,94 +50,155 @@ pub fn create_text_image_oriented(
     font_size: f32,
     orientation: Orientation,
 ) -> RgbImage {
-    let mut img = create_blank_image_oriented(orientation);
-    draw_text_oriented(&mut img, text, font_size, orientation);
-    img
+    create_text_image_themed(
+        &Font::default(),
+        text,
+        font_size,
+        orientation,
+        Theme::default(),
+        LayoutOptions::default(),
+    )
 }
 
-fn draw_text_oriented(img: &mut RgbImage, text: &str, font_size: f32, orientation: Orientation) {
-    use ab_glyph::{Font, ScaleFont};
-
-    let font = FontRef::try_from_slice(FONT_DATA).expect("Failed to load embedded font");
-    let scale = PxScale::from(font_size);
-    let scaled_font = font.as_scaled(scale);
-    let line_height = scaled_font.height();
-
-    let lines: Vec<&str> = text.lines().collect();
-    let total_height = line_height * lines.len() as f32;
-
-    let display_width = orientation.width();
-    let display_height = orientation.height();
-
-    let start_y = ((display_height as f32 - total_height) / 2.0).max(0.0) as i32;
-
-    for (i, line) in lines.iter().enumerate() {
-        let (line_width, _) = measure_text(&font, scale, line);
-        let x = ((display_width as i32 - line_width as i32) / 2).max(0);
-        let y = start_y + (i as f32 * line_height) as i32;
+/// Create text image with specified font, orientation, colors and layout
+pub fn create_text_image_themed(
+    font: &Font,
+    text: &str,
+    font_size: f32,
+    orientation: Orientation,
+    theme: Theme,
+    options: LayoutOptions,
+) -> RgbImage {
+    let mut img = RgbImage::from_pixel(orientation.width(), orientation.height(), theme.background);
+    draw_text_oriented(
+        &mut img,
+        font,
+        text,
+        font_size,
+        orientation,
+        theme.foreground,
+        options,
+    );
+    img
+}
 
-        draw_text_mut(img, Rgb([255, 255, 255]), x, y, scale, &font, line);
+/// Draw markup text; spans without a color use `color`
+fn draw_text_oriented(
+    img: &mut RgbImage,
+    font: &Font,
+    text: &str,
+    font_size: f32,
+    orientation: Orientation,
+    color: Rgb<u8>,
+    options: LayoutOptions,
+) {
+    let block = BlockLayout::spaced(font, text, font_size, options.line_spacing);
+    let text_width = options.text_width(orientation.width()) as f32;
+    let text_height = options.text_height(orientation.height()) as f32;
+    let left = options.margin_x as f32;
+    let top = options.margin_y as f32 + block.block_top(text_height, options.vertical_align);
+
+    for (line, baseline) in block.lines.iter().zip(&block.baselines) {
+        let x = (left + block.line_x(line, text_width, options.horizontal_align)).round();
+        let y = (top + baseline).round();
+        draw_line(img, font, x, y, line, color);
     }
 }
 
-pub fn measure_text_with_font_size(text: &str, font_size: f32) -> (u32, u32) {
-    let font = FontRef::try_from_slice(FONT_DATA).expect("Failed to load embedded font");
-    let scale = PxScale::from(font_size);
-    measure_text(&font, scale, text)
+/// Draw a laid-out line with its pen origin at (x, baseline), picking each
+/// character's face from the font fallback chain.
+fn draw_line(
+    img: &mut RgbImage,
+    font: &Font,
+    x: f32,
+    baseline: f32,
+    line: &LineLayout,
+    color: Rgb<u8>,
+) {
+    for glyph in &line.glyphs {
+        let color = glyph.style.color.unwrap_or(color);
+        // Bold is faked by drawing the glyph a second time, shifted right
+        let offsets = [0.0, bold_offset(glyph.font_size)];
+        let strikes = if glyph.style.bold { 2 } else { 1 };
+        for offset in &offsets[..strikes] {
+            font.draw(
+                glyph.glyph,
+                glyph.font_size,
+                x + glyph.x + offset,
+                baseline + glyph.y,
+                |px, py, coverage, own_color| {
+                    blend_pixel(img, px, py, own_color.unwrap_or(color), coverage)
+                },
+            );
+        }
+    }
 }
 
-/// Measure multi-line text dimensions at given font size.
-/// Returns (max_line_width, total_height) for the text.
-pub fn measure_multiline_text(text: &str, font_size: f32) -> (u32, u32) {
-    use ab_glyph::{Font, ScaleFont};
-
-    let font = FontRef::try_from_slice(FONT_DATA).expect("Failed to load embedded font");
-    let scale = PxScale::from(font_size);
-    let scaled_font = font.as_scaled(scale);
-    let line_height = scaled_font.height();
-
-    let lines: Vec<&str> = text.lines().collect();
-    if lines.is_empty() {
-        return (0, 0);
+/// Blend `color` over the pixel at (x, y) with the given coverage (0.0-1.0)
+fn blend_pixel(img: &mut RgbImage, x: i32, y: i32, color: Rgb<u8>, coverage: f32) {
+    if x < 0 || y < 0 || x >= img.width() as i32 || y >= img.height() as i32 {
+        return;
     }
+    let alpha = coverage.clamp(0.0, 1.0);
+    let pixel = img.get_pixel_mut(x as u32, y as u32);
+    for (dst, src) in pixel.0.iter_mut().zip(color.0) {
+        *dst = (*dst as f32 * (1.0 - alpha) + src as f32 * alpha).round() as u8;
+    }
+}
 
-    let max_width = lines
-        .iter()
-        .map(|line| measure_text(&font, scale, line).0)
-        .max()
-        .unwrap_or(0);
-
-    let total_height = (line_height * lines.len() as f32) as u32;
+/// Measure a single line: kerned ink width and line height
+pub fn measure_text_with_font_size(font: &Font, text: &str, font_size: f32) -> (u32, u32) {
+    measure_text(font, font_size, text)
+}
 
-    (max_width, total_height)
+/// Measure multi-line markup text dimensions at given font size.
+/// Returns the widest line's ink width and the height of the drawn pixels
+/// across all lines, so auto-fit accounts for ascenders and descenders.
+pub fn measure_multiline_text(font: &Font, text: &str, font_size: f32) -> (u32, u32) {
+    let block = BlockLayout::new(font, text, font_size);
+    (
+        block.ink_width().ceil() as u32,
+        block.ink_height().ceil() as u32,
+    )
 }
 
-const MIN_FONT_SIZE: f32 = 8.0;
-const MAX_FONT_SIZE: f32 = 72.0;
-const HORIZONTAL_PADDING: u32 = 8;
-const VERTICAL_PADDING: u32 = 4;
+pub(crate) const MIN_FONT_SIZE: f32 = 8.0;
+pub(crate) const MAX_FONT_SIZE: f32 = 72.0;
 
 /// Calculate the largest font size that fits text within display bounds.
 /// Uses binary search between MIN_FONT_SIZE (8.0) and MAX_FONT_SIZE (72.0).
 pub fn calculate_auto_fit_size(text: &str) -> f32 {
-    calculate_auto_fit_size_oriented(text, Orientation::default())
+    calculate_auto_fit_size_oriented(
+        &Font::default(),
+        text,
+        Orientation::default(),
+        LayoutOptions::default(),
+    )
 }
 
-/// Calculate auto-fit size for a specific orientation
-pub fn calculate_auto_fit_size_oriented(text: &str, orientation: Orientation) -> f32 {
+/// Calculate auto-fit size for a specific font, orientation and layout
+/// (margins and line spacing), keeping the text's own line breaks. See
+/// [`crate::text::plan_layout`] for auto-fit that also wraps.
+pub fn calculate_auto_fit_size_oriented(
+    font: &Font,
+    text: &str,
+    orientation: Orientation,
+    options: LayoutOptions,
+) -> f32 {
     if text.is_empty() {
         return MIN_FONT_SIZE;
     }
 
-    let max_text_width = orientation.width() - HORIZONTAL_PADDING;
-    let max_text_height = orientation.height() - VERTICAL_PADDING;
+    let max_text_width = options.text_width(orientation.width()) as f32;
+    let max_text_height = options.text_height(orientation.height()) as f32;
 
     let mut low = MIN_FONT_SIZE;
     let mut high = MAX_FONT_SIZE;
 
     while high - low > 0.5 {
         let mid = (low + high) / 2.0;
-        let (width, height) = measure_multiline_text(text, mid);
+        let block = BlockLayout::spaced(font, text, mid, options.line_spacing);
+        let (width, height) = (block.ink_width().ceil(), block.ink_height().ceil());
 
         if width <= max_text_width && height <= max_text_height {
             low = mid;
@@ -162,57 +210,63 @@ pub fn calculate_auto_fit_size_oriented(text: &str, orientation: Orientation) ->
     low
 }
 
-fn measure_text(font: &FontRef, scale: PxScale, text: &str) -> (u32, u32) {
-    use ab_glyph::{Font, ScaleFont};
-
-    let scaled_font = font.as_scaled(scale);
-    let mut width = 0.0f32;
-    let height = scaled_font.height();
-
-    for c in text.chars() {
-        let glyph_id = font.glyph_id(c);
-        width += scaled_font.h_advance(glyph_id);
-    }
+/// Measure a single line's kerned ink width, using whichever face in the
+/// fallback chain provides each character
+fn measure_text(font: &Font, font_size: f32, text: &str) -> (u32, u32) {
+    let width = LineLayout::new(font, text, font_size).ink_width();
+    let height = font.line_height(font_size);
 
-    (width as u32, height as u32)
+    (width.ceil() as u32, height as u32)
 }
 
 pub fn calculate_max_chars_per_line(font_size: f32) -> usize {
-    calculate_max_chars_per_line_oriented(font_size, Orientation::default())
+    calculate_max_chars_per_line_oriented(
+        &Font::default(),
+        font_size,
+        Orientation::default(),
+        LayoutOptions::default(),
+    )
 }
 
-pub fn calculate_max_chars_per_line_oriented(font_size: f32, orientation: Orientation) -> usize {
-    let font = FontRef::try_from_slice(FONT_DATA).expect("Failed to load embedded font");
-    let scale = PxScale::from(font_size);
-
-    use ab_glyph::{Font, ScaleFont};
-    let scaled_font = font.as_scaled(scale);
-
+pub fn calculate_max_chars_per_line_oriented(
+    font: &Font,
+    font_size: f32,
+    orientation: Orientation,
+    options: LayoutOptions,
+) -> usize {
     // Use average character width based on 'x' (common reference character)
-    let avg_width = scaled_font.h_advance(font.glyph_id('x'));
+    let avg_width = font.advance('x', font_size);
 
     if avg_width > 0.0 {
-        (orientation.width() as f32 / avg_width).floor() as usize
+        (options.text_width(orientation.width()) as f32 / avg_width).floor() as usize
     } else {
         0
     }
 }
 
 pub fn calculate_max_lines(font_size: f32) -> usize {
-    calculate_max_lines_oriented(font_size, Orientation::default())
+    calculate_max_lines_oriented(
+        &Font::default(),
+        font_size,
+        Orientation::default(),
+        LayoutOptions::default(),
+    )
 }
 
-pub fn calculate_max_lines_oriented(font_size: f32, orientation: Orientation) -> usize {
-    let font = FontRef::try_from_slice(FONT_DATA).expect("Failed to load embedded font");
-    let scale = PxScale::from(font_size);
-
-    use ab_glyph::{Font, ScaleFont};
-    let scaled_font = font.as_scaled(scale);
-
-    let line_height = scaled_font.height();
-
-    if line_height > 0.0 {
-        (orientation.height() as f32 / line_height).floor() as usize
+/// Number of lines that fit between the top and bottom margins. Lines after
+/// the first take `line_height * line_spacing` each.
+pub fn calculate_max_lines_oriented(
+    font: &Font,
+    font_size: f32,
+    orientation: Orientation,
+    options: LayoutOptions,
+) -> usize {
+    let line_height = font.line_height(font_size);
+    let available = options.text_height(orientation.height()) as f32;
+
+    if line_height > 0.0 && line_height <= available {
+        let step = line_height * options.line_spacing;
+        ((available - line_height) / step).floor() as usize + 1
     } else {
         0
     }
@@ -220,36 +274,49 @@ pub fn calculate_max_lines_oriented(font_size: f32, orientation: Orientation) ->
 
 /// Convert image to RGB565 bytes for display (uses image dimensions)
 pub fn image_to_rgb565_bytes(img: &RgbImage) -> Vec<u8> {
-    image_to_rgb565_bytes_oriented(img, Orientation::default())
+    image_to_rgb565_bytes_oriented(img, Orientation::default(), ColorOptions::default())
 }
 
 /// Convert image to RGB565 bytes, rotating if needed for landscape orientation.
 /// The physical display is 80x160 (portrait), so landscape images must be rotated 90° CW.
-pub fn image_to_rgb565_bytes_oriented(img: &RgbImage, orientation: Orientation) -> Vec<u8> {
-    let mut data = Vec::with_capacity((PHYSICAL_WIDTH * PHYSICAL_HEIGHT * 2) as usize);
-
-    match orientation {
-        Orientation::Portrait => {
-            // Portrait: send as-is (80x160)
-            for y in 0..img.height() {
-                for x in 0..img.width() {
-                    let pixel = img.get_pixel(x, y);
-                    push_rgb565(&mut data, pixel[0], pixel[1], pixel[2]);
-                }
-            }
+/// Colors are corrected and quantized according to `options`.
+pub fn image_to_rgb565_bytes_oriented(
+    img: &RgbImage,
+    orientation: Orientation,
+    options: ColorOptions,
+) -> Vec<u8> {
+    image_to_panel_bytes(img, orientation, options, PanelSpec::default())
+}
+
+/// Convert image to the pixel format of `panel`, row by row in the panel's
+/// native orientation. Images drawn the other way round are rotated 90° CW;
+/// parts of the panel the image doesn't cover are sent black.
+pub fn image_to_panel_bytes(
+    img: &RgbImage,
+    orientation: Orientation,
+    options: ColorOptions,
+    panel: PanelSpec,
+) -> Vec<u8> {
+    let bpp = panel.pixel_format.bytes_per_pixel();
+    let pixels = encode_pixels(img, options, panel.pixel_format);
+    let pixel_at = |lx: u32, ly: u32| {
+        if lx < img.width() && ly < img.height() {
+            let start = (ly * img.width() + lx) as usize * bpp;
+            &pixels[start..start + bpp]
+        } else {
+            &[0u8; 3][..bpp]
         }
-        Orientation::Landscape => {
-            // Landscape: rotate 90° CW to fit physical 80x160 display
-            // Input is 160w x 80h, output is 80w x 160h
-            // Physical output scans row-by-row (py=0..160, px=0..80)
-            // Maps to logical: lx = py, ly = 79 - px
-            for py in 0..PHYSICAL_HEIGHT {
-                for px in 0..PHYSICAL_WIDTH {
-                    let lx = py;
-                    let ly = (PHYSICAL_WIDTH - 1) - px; // 79 - px
-                    let pixel = img.get_pixel(lx, ly);
-                    push_rgb565(&mut data, pixel[0], pixel[1], pixel[2]);
-                }
+    };
+
+    let mut data = Vec::with_capacity(panel.frame_bytes());
+    let rotated = panel.logical_width(orientation) != panel.width;
+    for py in 0..panel.height {
+        for px in 0..panel.width {
+            if rotated {
+                // Logical image is height x width; maps to lx = py, ly = width - 1 - px
+                data.extend_from_slice(pixel_at(py, panel.width - 1 - px));
+            } else {
+                data.extend_from_slice(pixel_at(px, py));
             }
         }
     }
@@ -257,13 +324,15 @@ pub fn image_to_rgb565_bytes_oriented(img: &RgbImage, orientation: Orientation)
     data
 }
 
-fn push_rgb565(data: &mut Vec<u8>, r: u8, g: u8, b: u8) {
-    let r5 = (r >> 3) & 0x1F;
-    let g6 = (g >> 2) & 0x3F;
-    let b5 = (b >> 3) & 0x1F;
-    let rgb565 = ((r5 as u16) << 11) | ((g6 as u16) << 5) | (b5 as u16);
-    data.push((rgb565 & 0xFF) as u8);
-    data.push((rgb565 >> 8) as u8);
+/// Row-major pixel bytes of `img` in `format`
+fn encode_pixels(img: &RgbImage, options: ColorOptions, format: PixelFormat) -> Vec<u8> {
+    match format {
+        PixelFormat::Rgb565(order) => quantize_rgb565(img, options)
+            .into_iter()
+            .flat_map(|pixel| order.rgb565_bytes(pixel))
+            .collect(),
+        PixelFormat::Rgb888 => correct_colors(img, options).into_raw(),
+    }
 }
 
 #[cfg(test)]
@@ -277,6 +346,7 @@ fn rgb_to_rgb565(r: u8, g: u8, b: u8) -> u16 {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::panel::ByteOrder;
 
     #[test]
     fn test_create_blank_image_dimensions() {
@@ -305,6 +375,264 @@ mod tests {
         assert_ne!(blank_bytes, text_bytes);
     }
 
+    #[test]
+    fn test_themed_image_uses_background() {
+        let theme = Theme::new(Rgb([0, 0, 0]), Rgb([255, 0, 0]));
+        let img = create_text_image_themed(
+            &Font::embedded(),
+            "",
+            14.0,
+            Orientation::Landscape,
+            theme,
+            LayoutOptions::default(),
+        );
+        assert_eq!(*img.get_pixel(0, 0), Rgb([255, 0, 0]));
+    }
+
+    #[test]
+    fn test_mixed_script_text_renders() {
+        let blank = create_blank_image();
+        let img = create_text_image("Hi Жж Ωμ", 14.0);
+        assert_ne!(image_to_rgb565_bytes(&blank), image_to_rgb565_bytes(&img));
+    }
+
+    #[test]
+    fn test_measure_uses_fallback_advances() {
+        let font = Font::embedded();
+        let (latin, _) = measure_text_with_font_size(&font, "AB", 14.0);
+        let (mixed, _) = measure_text_with_font_size(&font, "ABЖ", 14.0);
+        assert!(mixed > latin);
+    }
+
+    #[test]
+    fn test_bitmap_font_renders_crisp_pixels() {
+        let font = Font::load(std::path::Path::new("5x7")).unwrap();
+        let img = create_text_image_themed(
+            &font,
+            "Hi",
+            8.0,
+            Orientation::Landscape,
+            Theme::default(),
+            LayoutOptions::default(),
+        );
+        // Without antialiasing every pixel is either background or foreground
+        assert!(img
+            .pixels()
+            .all(|p| *p == Rgb([0, 0, 0]) || *p == Rgb([255, 255, 255])));
+        assert!(img.pixels().any(|p| *p == Rgb([255, 255, 255])));
+    }
+
+    #[test]
+    fn test_auto_fit_bitmap_font_uses_integer_scale() {
+        let font = Font::load(std::path::Path::new("5x7")).unwrap();
+        let size = calculate_auto_fit_size_oriented(
+            &font,
+            "Hi",
+            Orientation::Landscape,
+            LayoutOptions::default(),
+        );
+        let (_, height) = measure_multiline_text(&font, "Hi", size);
+        // "Hi" covers the 7 rows above the baseline, scaled by a whole number
+        assert_eq!(height % 7, 0, "ink height should be a multiple of 7");
+    }
+
+    #[test]
+    fn test_measure_applies_kerning() {
+        let font = Font::embedded();
+        let (kerned, _) = measure_text_with_font_size(&font, "AVAV", 40.0);
+        let unkerned: f32 = "AVAV".chars().map(|c| font.advance(c, 40.0)).sum();
+        assert!((kerned as f32) < unkerned);
+    }
+
+    #[test]
+    fn test_descenders_are_not_clipped() {
+        let font = Font::embedded();
+        let size = calculate_auto_fit_size_oriented(
+            &font,
+            "gjpqy",
+            Orientation::Landscape,
+            LayoutOptions::default(),
+        );
+        let img = create_text_image_themed(
+            &font,
+            "gjpqy",
+            size,
+            Orientation::Landscape,
+            Theme::default(),
+            LayoutOptions::default(),
+        );
+        let bottom_row_lit = (0..img.width()).any(|x| img.get_pixel(x, img.height() - 1)[0] > 0);
+        assert!(!bottom_row_lit, "descenders should stay inside the display");
+    }
+
+    #[test]
+    fn test_vertical_align_moves_text() {
+        use crate::layout::VerticalAlign;
+
+        let font = Font::embedded();
+        let ink_rows = |align| {
+            let options = LayoutOptions {
+                vertical_align: align,
+                margin_y: 0,
+                ..LayoutOptions::default()
+            };
+            let img = create_text_image_themed(
+                &font,
+                "Hi",
+                20.0,
+                Orientation::Landscape,
+                Theme::default(),
+                options,
+            );
+            let lit: Vec<u32> = (0..img.height())
+                .filter(|&y| (0..img.width()).any(|x| img.get_pixel(x, y)[0] > 0))
+                .collect();
+            (lit[0], *lit.last().unwrap())
+        };
+
+        let (top, _) = ink_rows(VerticalAlign::Top);
+        let (_, bottom) = ink_rows(VerticalAlign::Bottom);
+        let (middle_top, middle_bottom) = ink_rows(VerticalAlign::Middle);
+        assert!(top <= 1);
+        assert!(bottom >= DISPLAY_HEIGHT - 2);
+        assert!(middle_top.abs_diff(DISPLAY_HEIGHT - 1 - middle_bottom) <= 1);
+    }
+
+    #[test]
+    fn test_default_alignment_follows_paragraph_direction() {
+        let font = Font::embedded();
+        let ink_columns = |text| {
+            let img = create_text_image_themed(
+                &font,
+                text,
+                16.0,
+                Orientation::Landscape,
+                Theme::default(),
+                LayoutOptions::default(),
+            );
+            let lit: Vec<u32> = (0..img.width())
+                .filter(|&x| (0..img.height()).any(|y| img.get_pixel(x, y)[0] > 0))
+                .collect();
+            (lit[0], *lit.last().unwrap())
+        };
+
+        let margin = LayoutOptions::default().margin_x;
+        let (left, _) = ink_columns("Hi");
+        assert!(left <= margin + 1, "{}", left);
+        let (left, right) = ink_columns("שלום");
+        assert!(right >= DISPLAY_WIDTH - margin - 2, "{}", right);
+        assert!(left > DISPLAY_WIDTH / 2, "{}", left);
+    }
+
+    /// Compare a render against `tests/golden/<name>.png`. Set
+    /// `UPDATE_GOLDEN=1` to regenerate the reference images.
+    fn assert_golden(name: &str, img: &RgbImage) {
+        let path = std::path::Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/golden")
+            .join(format!("{}.png", name));
+        if std::env::var_os("UPDATE_GOLDEN").is_some() {
+            img.save(&path).unwrap();
+            return;
+        }
+        let golden = ::image::open(&path)
+            .unwrap_or_else(|e| panic!("missing golden image {}: {}", path.display(), e))
+            .to_rgb8();
+        assert_eq!(golden.dimensions(), img.dimensions());
+        // Allow tiny antialiasing differences between platforms
+        let differing = golden
+            .pixels()
+            .zip(img.pixels())
+            .filter(|(a, b)| a.0.iter().zip(b.0).any(|(x, y)| x.abs_diff(y) > 8))
+            .count();
+        assert!(
+            differing <= 4,
+            "{} differs from golden image in {} pixels",
+            name,
+            differing
+        );
+    }
+
+    #[test]
+    fn test_golden_images() {
+        use crate::layout::{HorizontalAlign, VerticalAlign};
+
+        let font = Font::embedded();
+        let aligned = |horizontal_align, vertical_align| LayoutOptions {
+            horizontal_align,
+            vertical_align,
+            ..LayoutOptions::default()
+        };
+        let centered = aligned(HorizontalAlign::Center, VerticalAlign::Middle);
+        let cases = [
+            ("kerning_av", "AVATAR", Orientation::Landscape, centered),
+            (
+                "descenders",
+                "Jumpy\ngqyp",
+                Orientation::Landscape,
+                centered,
+            ),
+            (
+                "align_top",
+                "Top",
+                Orientation::Landscape,
+                aligned(HorizontalAlign::Center, VerticalAlign::Top),
+            ),
+            (
+                "align_bottom",
+                "gy",
+                Orientation::Landscape,
+                aligned(HorizontalAlign::Center, VerticalAlign::Bottom),
+            ),
+            (
+                "markup",
+                "build: {red}FAILED{/}\n**12** tests {size=24}{#00ff00}ok{/}{/}",
+                Orientation::Landscape,
+                centered,
+            ),
+            (
+                "align_baseline",
+                "12:34",
+                Orientation::Portrait,
+                aligned(HorizontalAlign::Center, VerticalAlign::Baseline),
+            ),
+            (
+                "align_left_spaced",
+                "CPU 12%\nMEM 3%",
+                Orientation::Landscape,
+                LayoutOptions {
+                    horizontal_align: HorizontalAlign::Left,
+                    margin_x: 10,
+                    line_spacing: 1.5,
+                    ..LayoutOptions::default()
+                },
+            ),
+            (
+                "align_right",
+                "1\n22\n333",
+                Orientation::Portrait,
+                aligned(HorizontalAlign::Right, VerticalAlign::Top),
+            ),
+            (
+                "icons",
+                ":wifi: :cpu: :sun:\n:play: :pause: :mail:\n:check: ok :cross:",
+                Orientation::Landscape,
+                centered,
+            ),
+            (
+                "rtl_arabic",
+                "مرحبا بالعالم\nשלום 123",
+                Orientation::Landscape,
+                aligned(HorizontalAlign::Start, VerticalAlign::Middle),
+            ),
+        ];
+        for (name, text, orientation, options) in cases {
+            let size = calculate_auto_fit_size_oriented(&font, text, orientation, options);
+            let img =
+                create_text_image_themed(&font, text, size, orientation, Theme::default(), options);
+            assert_golden(name, &img);
+        }
+    }
+
     #[test]
     fn test_rgb565_black_converts_to_zero() {
         let rgb565 = rgb_to_rgb565(0, 0, 0);
@@ -349,6 +677,50 @@ mod tests {
         assert_eq!(data.len(), 25600);
     }
 
+    #[test]
+    fn test_panel_bytes_big_endian() {
+        let panel = PanelSpec {
+            pixel_format: PixelFormat::Rgb565(ByteOrder::BigEndian),
+            ..PanelSpec::default()
+        };
+        let img = RgbImage::from_pixel(80, 160, Rgb([255, 0, 0]));
+        let data =
+            image_to_panel_bytes(&img, Orientation::Portrait, ColorOptions::default(), panel);
+        assert_eq!(data.len(), 80 * 160 * 2);
+        assert_eq!(&data[..2], &[0xF8, 0x00]);
+    }
+
+    #[test]
+    fn test_panel_bytes_rgb888_rotated() {
+        // A 320x170 landscape panel driven with a portrait-native layout
+        let panel = PanelSpec {
+            width: 170,
+            height: 320,
+            pixel_format: PixelFormat::Rgb888,
+        };
+        let mut img = RgbImage::new(320, 170);
+        // Bottom-left of the landscape image is the first physical pixel
+        img.put_pixel(0, 169, Rgb([1, 2, 3]));
+        let data =
+            image_to_panel_bytes(&img, Orientation::Landscape, ColorOptions::default(), panel);
+        assert_eq!(data.len(), 170 * 320 * 3);
+        assert_eq!(&data[..3], &[1, 2, 3]);
+    }
+
+    #[test]
+    fn test_panel_bytes_pads_small_image() {
+        let img = RgbImage::from_pixel(10, 10, Rgb([255, 255, 255]));
+        let data = image_to_panel_bytes(
+            &img,
+            Orientation::Portrait,
+            ColorOptions::default(),
+            PanelSpec::default(),
+        );
+        assert_eq!(data.len(), 80 * 160 * 2);
+        assert_eq!(&data[..2], &[0xFF, 0xFF]);
+        assert_eq!(&data[20..22], &[0x00, 0x00]);
+    }
+
     #[test]
     fn test_display_dimensions() {
         // Landscape orientation (default): 160 wide × 80 tall
@@ -436,21 +808,60 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_margins_shrink_auto_fit() {
+        let font = Font::embedded();
+        let orientation = Orientation::Landscape;
+        let tight = LayoutOptions {
+            margin_x: 0,
+            margin_y: 0,
+            ..LayoutOptions::default()
+        };
+        let roomy = LayoutOptions {
+            margin_x: 30,
+            margin_y: 10,
+            ..LayoutOptions::default()
+        };
+        assert!(
+            calculate_auto_fit_size_oriented(&font, "Hello", orientation, roomy)
+                < calculate_auto_fit_size_oriented(&font, "Hello", orientation, tight)
+        );
+    }
+
+    #[test]
+    fn test_line_spacing_reduces_max_lines() {
+        let font = Font::embedded();
+        let spaced = LayoutOptions {
+            line_spacing: 2.0,
+            ..LayoutOptions::default()
+        };
+        let normal = calculate_max_lines_oriented(
+            &font,
+            14.0,
+            Orientation::Portrait,
+            LayoutOptions::default(),
+        );
+        let double = calculate_max_lines_oriented(&font, 14.0, Orientation::Portrait, spaced);
+        assert!(double < normal);
+        assert!(double >= 1);
+    }
+
     #[test]
     fn test_measure_dimensions() {
         let orientation = Orientation::default();
-        let max_text_width = orientation.width() - HORIZONTAL_PADDING;
-        let max_text_height = orientation.height() - VERTICAL_PADDING;
+        let options = LayoutOptions::default();
+        let max_text_width = options.text_width(orientation.width());
+        let max_text_height = options.text_height(orientation.height());
 
         for font_size in [20.0, 40.0, 60.0, 70.0] {
-            let (w, h) = measure_multiline_text("Hello", font_size);
+            let (w, h) = measure_multiline_text(&Font::embedded(), "Hello", font_size);
             eprintln!(
                 "Hello at {}px: {}w x {}h (max: {}x{})",
                 font_size, w, h, max_text_width, max_text_height
             );
         }
         let size = calculate_auto_fit_size("Hello");
-        let (w, h) = measure_multiline_text("Hello", size);
+        let (w, h) = measure_multiline_text(&Font::embedded(), "Hello", size);
         eprintln!("Auto-fit 'Hello': size={}, dims={}x{}", size, w, h);
 
         assert!(
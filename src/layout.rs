@@ -0,0 +1,609 @@
+use crate::font::{Font, Glyph};
+use crate::text::{parse_markup, split_lines, Overflow, Span, Style};
+use std::ops::Range;
+use unicode_bidi::ParagraphBidiInfo;
+
+/// Pixel bounds of drawn glyphs, relative to a pen origin on the baseline
+/// (y grows downwards, so ascenders have negative `min_y`)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct InkBounds {
+    pub min_x: f32,
+    pub min_y: f32,
+    pub max_x: f32,
+    pub max_y: f32,
+}
+
+impl InkBounds {
+    pub fn width(&self) -> f32 {
+        self.max_x - self.min_x
+    }
+
+    pub fn height(&self) -> f32 {
+        self.max_y - self.min_y
+    }
+
+    fn offset(self, dx: f32, dy: f32) -> InkBounds {
+        InkBounds {
+            min_x: self.min_x + dx,
+            min_y: self.min_y + dy,
+            max_x: self.max_x + dx,
+            max_y: self.max_y + dy,
+        }
+    }
+
+    fn union(self, other: InkBounds) -> InkBounds {
+        InkBounds {
+            min_x: self.min_x.min(other.min_x),
+            min_y: self.min_y.min(other.min_y),
+            max_x: self.max_x.max(other.max_x),
+            max_y: self.max_y.max(other.max_y),
+        }
+    }
+}
+
+fn union(a: Option<InkBounds>, b: Option<InkBounds>) -> Option<InkBounds> {
+    match (a, b) {
+        (Some(a), Some(b)) => Some(a.union(b)),
+        (a, b) => a.or(b),
+    }
+}
+
+/// Vertical placement of a text block on the display
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum VerticalAlign {
+    /// Top of the drawn glyphs at the top edge
+    Top,
+    /// Drawn glyphs centered (default)
+    #[default]
+    Middle,
+    /// Bottom of the drawn glyphs (including descenders) at the bottom edge
+    Bottom,
+    /// Font ascent/descent box centered, so the baseline stays put as text changes
+    Baseline,
+}
+
+/// Horizontal placement of each line on the display
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum HorizontalAlign {
+    Left,
+    Center,
+    Right,
+    /// Left for left-to-right paragraphs, right for right-to-left ones
+    /// (default)
+    #[default]
+    Start,
+    /// Right for left-to-right paragraphs, left for right-to-left ones
+    End,
+}
+
+/// Layout settings for drawing a text block
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LayoutOptions {
+    pub horizontal_align: HorizontalAlign,
+    pub vertical_align: VerticalAlign,
+    /// Empty space kept at the left and right edges, in pixels
+    pub margin_x: u32,
+    /// Empty space kept at the top and bottom edges, in pixels
+    pub margin_y: u32,
+    /// Multiplier for the distance between baselines
+    pub line_spacing: f32,
+    /// Handling of words too wide for a line
+    pub overflow: Overflow,
+}
+
+impl Default for LayoutOptions {
+    fn default() -> Self {
+        LayoutOptions {
+            horizontal_align: HorizontalAlign::default(),
+            vertical_align: VerticalAlign::default(),
+            margin_x: 4,
+            margin_y: 2,
+            line_spacing: 1.0,
+            overflow: Overflow::default(),
+        }
+    }
+}
+
+impl LayoutOptions {
+    /// Width available for text on a display `width` pixels wide
+    pub fn text_width(&self, width: u32) -> u32 {
+        width.saturating_sub(2 * self.margin_x)
+    }
+
+    /// Height available for text on a display `height` pixels tall
+    pub fn text_height(&self, height: u32) -> u32 {
+        height.saturating_sub(2 * self.margin_y)
+    }
+}
+
+/// Extra horizontal offset used to fake bold by drawing a glyph twice
+pub fn bold_offset(font_size: f32) -> f32 {
+    (font_size / 24.0).round().max(1.0)
+}
+
+/// A glyph placed on a line: the glyph, its pen position and style
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PositionedGlyph {
+    pub glyph: Glyph,
+    pub x: f32,
+    /// Offset below the baseline (negative for raised combining marks)
+    pub y: f32,
+    pub font_size: f32,
+    pub style: Style,
+}
+
+/// Zero-width characters that steer the bidi algorithm but aren't drawn
+fn is_bidi_control(c: char) -> bool {
+    matches!(
+        c,
+        '\u{061C}' | '\u{200E}' | '\u{200F}' | '\u{202A}'..='\u{202E}' | '\u{2066}'..='\u{2069}'
+    )
+}
+
+/// Characters that take no part in drawing: soft hyphens only show up when
+/// a line is broken at them, and bidi controls and variation selectors are
+/// invisible
+fn is_hidden(c: char) -> bool {
+    matches!(c, '\u{AD}' | '\u{FE00}'..='\u{FE0F}') || is_bidi_control(c)
+}
+
+/// Runs of a line in visual (left-to-right) order as char index ranges,
+/// with whether each runs right-to-left, plus the paragraph direction
+fn visual_runs(chars: &[char]) -> (Vec<(Range<usize>, bool)>, bool) {
+    let text: String = chars.iter().collect();
+    let bidi = ParagraphBidiInfo::new(&text, None);
+    let rtl = bidi.paragraph_level.is_rtl();
+    if !bidi.has_rtl() {
+        return (vec![(0..chars.len(), false)], rtl);
+    }
+
+    // Bidi works on byte offsets; map them back to char indices
+    let starts: Vec<usize> = text.char_indices().map(|(i, _)| i).collect();
+    let char_index = |byte: usize| starts.partition_point(|&start| start < byte);
+    let (levels, runs) = bidi.visual_runs(0..text.len());
+    let runs = runs
+        .into_iter()
+        .map(|run| {
+            let run_rtl = levels[run.start].is_rtl();
+            (char_index(run.start)..char_index(run.end), run_rtl)
+        })
+        .collect();
+    (runs, rtl)
+}
+
+/// A single line of text with kerning applied
+#[derive(Debug, Clone)]
+pub struct LineLayout {
+    pub glyphs: Vec<PositionedGlyph>,
+    /// Total pen advance, including kerning
+    pub advance: f32,
+    /// Bounds of the drawn pixels, `None` for blank lines
+    pub ink: Option<InkBounds>,
+    /// Largest ascent of the sizes used on this line
+    pub ascent: f32,
+    /// Largest line height of the sizes used on this line
+    pub line_height: f32,
+    /// Whether the line's paragraph runs right-to-left
+    pub rtl: bool,
+}
+
+impl LineLayout {
+    /// Lay out unstyled text at a single size
+    pub fn new(font: &Font, text: &str, font_size: f32) -> LineLayout {
+        let span = Span {
+            text: text.to_string(),
+            style: Style::default(),
+        };
+        LineLayout::styled(font, &[span], font_size)
+    }
+
+    /// Lay out styled spans; spans without a size use `font_size`. Mixed
+    /// left-to-right and right-to-left text is reordered with the Unicode
+    /// bidi algorithm, and right-to-left runs are shaped.
+    pub fn styled(font: &Font, spans: &[Span], font_size: f32) -> LineLayout {
+        let mut builder = LineBuilder::new(font, font_size);
+        let mut chars = Vec::new();
+        let mut styles = Vec::new();
+        for span in spans {
+            let size = span.style.size.unwrap_or(font_size);
+            builder.ascent = builder.ascent.max(font.ascent(size));
+            builder.line_height = builder.line_height.max(font.line_height(size));
+            for c in span.text.chars() {
+                chars.push(c);
+                styles.push(span.style);
+            }
+        }
+
+        let (runs, rtl) = visual_runs(&chars);
+        for (run, run_rtl) in runs {
+            if run_rtl {
+                builder.push_rtl(&chars[run.clone()], &styles[run]);
+            } else {
+                for (&c, &style) in chars[run.clone()].iter().zip(&styles[run]) {
+                    builder.push_char(c, style);
+                }
+            }
+        }
+        builder.finish(rtl)
+    }
+
+    /// Width of the drawn pixels (0 for blank lines)
+    pub fn ink_width(&self) -> f32 {
+        self.ink.map_or(0.0, |ink| ink.width())
+    }
+}
+
+/// Accumulates glyphs for [`LineLayout::styled`], left to right
+struct LineBuilder<'a> {
+    font: &'a Font,
+    font_size: f32,
+    glyphs: Vec<PositionedGlyph>,
+    ink: Option<InkBounds>,
+    pen_x: f32,
+    /// Last character and size, for kerning the next one
+    previous: Option<(char, f32)>,
+    ascent: f32,
+    line_height: f32,
+}
+
+impl<'a> LineBuilder<'a> {
+    fn new(font: &'a Font, font_size: f32) -> LineBuilder<'a> {
+        LineBuilder {
+            font,
+            font_size,
+            glyphs: Vec::new(),
+            ink: None,
+            pen_x: 0.0,
+            previous: None,
+            ascent: 0.0,
+            line_height: 0.0,
+        }
+    }
+
+    /// Add one character, kerned against the previous one
+    fn push_char(&mut self, c: char, style: Style) {
+        if is_hidden(c) {
+            return;
+        }
+        let size = style.size.unwrap_or(self.font_size);
+        match self.previous {
+            Some((prev, prev_size)) if prev_size == size => {
+                self.pen_x += self.font.kern(prev, c, size);
+            }
+            _ => {}
+        }
+        self.push(Glyph::Char(c), 0.0, 0.0, self.font.advance(c, size), style);
+        self.previous = Some((c, size));
+    }
+
+    /// Add a right-to-left run (given in logical order). Pieces that share
+    /// a face and style are shaped together; the run is laid out back to front.
+    fn push_rtl(&mut self, chars: &[char], styles: &[Style]) {
+        let mut pieces: Vec<(String, Style)> = Vec::new();
+        let mut last_face = None;
+        for (&c, &style) in chars.iter().zip(styles) {
+            if is_hidden(c) {
+                continue;
+            }
+            let face = self.font.face_index(c);
+            match pieces.last_mut() {
+                Some((text, piece_style)) if *piece_style == style && last_face == Some(face) => {
+                    text.push(c)
+                }
+                _ => pieces.push((c.to_string(), style)),
+            }
+            last_face = Some(face);
+        }
+
+        for (text, style) in pieces.iter().rev() {
+            let size = style.size.unwrap_or(self.font_size);
+            match self.font.shape(text, size, true) {
+                Some(shaped) => {
+                    for glyph in shaped.iter() {
+                        self.push(
+                            glyph.glyph,
+                            glyph.x_offset,
+                            glyph.y_offset,
+                            glyph.advance,
+                            *style,
+                        );
+                    }
+                    self.previous = None;
+                }
+                // Bitmap faces can't shape; just reverse the characters
+                None => {
+                    for c in text.chars().rev() {
+                        self.push_char(c, *style);
+                    }
+                }
+            }
+        }
+    }
+
+    fn push(&mut self, glyph: Glyph, x_offset: f32, y_offset: f32, advance: f32, style: Style) {
+        let size = style.size.unwrap_or(self.font_size);
+        let bold = if style.bold { bold_offset(size) } else { 0.0 };
+        let x = self.pen_x + x_offset;
+        self.glyphs.push(PositionedGlyph {
+            glyph,
+            x,
+            y: y_offset,
+            font_size: size,
+            style,
+        });
+        let glyph_ink = self.font.glyph_ink_bounds(glyph, size).map(|mut bounds| {
+            bounds.max_x += bold;
+            bounds.offset(x, y_offset)
+        });
+        self.ink = union(self.ink, glyph_ink);
+        self.pen_x += advance + bold;
+    }
+
+    fn finish(mut self, rtl: bool) -> LineLayout {
+        if self.glyphs.is_empty() {
+            self.ascent = self.font.ascent(self.font_size);
+            self.line_height = self.font.line_height(self.font_size);
+        }
+        LineLayout {
+            glyphs: self.glyphs,
+            advance: self.pen_x,
+            ink: self.ink,
+            ascent: self.ascent,
+            line_height: self.line_height,
+            rtl,
+        }
+    }
+}
+
+/// Multiple lines stacked by their line heights
+#[derive(Debug, Clone)]
+pub struct BlockLayout {
+    pub lines: Vec<LineLayout>,
+    /// Baseline of each line, relative to the top of the block
+    pub baselines: Vec<f32>,
+}
+
+impl BlockLayout {
+    /// Lay out multi-line markup text (see [`parse_markup`]) with single spacing
+    pub fn new(font: &Font, text: &str, font_size: f32) -> BlockLayout {
+        BlockLayout::spaced(font, text, font_size, 1.0)
+    }
+
+    /// Lay out multi-line markup text, multiplying each line's height by
+    /// `line_spacing` to get the distance to the next line
+    pub fn spaced(font: &Font, text: &str, font_size: f32, line_spacing: f32) -> BlockLayout {
+        let spans = parse_markup(text);
+        let lines: Vec<LineLayout> = if text.is_empty() {
+            Vec::new()
+        } else {
+            split_lines(&spans)
+                .iter()
+                .map(|line| LineLayout::styled(font, line, font_size))
+                .collect()
+        };
+
+        let mut top = 0.0;
+        let baselines = lines
+            .iter()
+            .map(|line| {
+                let baseline = top + line.ascent;
+                top += line.line_height * line_spacing;
+                baseline
+            })
+            .collect();
+
+        BlockLayout { lines, baselines }
+    }
+
+    /// Widest line's ink width
+    pub fn ink_width(&self) -> f32 {
+        self.lines
+            .iter()
+            .map(LineLayout::ink_width)
+            .fold(0.0, f32::max)
+    }
+
+    /// Vertical ink extent (top, bottom) relative to the top of the block
+    pub fn ink_extent(&self) -> Option<(f32, f32)> {
+        self.lines
+            .iter()
+            .zip(&self.baselines)
+            .filter_map(|(line, baseline)| {
+                line.ink
+                    .map(|ink| (baseline + ink.min_y, baseline + ink.max_y))
+            })
+            .reduce(|(top, bottom), (t, b)| (top.min(t), bottom.max(b)))
+    }
+
+    /// Height of the drawn pixels across all lines
+    pub fn ink_height(&self) -> f32 {
+        self.ink_extent().map_or(0.0, |(top, bottom)| bottom - top)
+    }
+
+    /// Height from the top of the first line's ascent/descent box to the
+    /// bottom of the last one's
+    pub fn metric_height(&self) -> f32 {
+        match (self.lines.last(), self.baselines.last()) {
+            (Some(line), Some(baseline)) => baseline + line.line_height - line.ascent,
+            _ => 0.0,
+        }
+    }
+
+    /// Y position of the top of the block when aligned within `height` pixels
+    pub fn block_top(&self, height: f32, align: VerticalAlign) -> f32 {
+        let metric_top = (height - self.metric_height()) / 2.0;
+        let Some((top, bottom)) = self.ink_extent() else {
+            return metric_top;
+        };
+        match align {
+            VerticalAlign::Top => -top,
+            VerticalAlign::Middle => (height - (bottom - top)) / 2.0 - top,
+            VerticalAlign::Bottom => height - bottom,
+            VerticalAlign::Baseline => metric_top,
+        }
+    }
+
+    /// Pen x position that aligns a line's ink within `width` pixels
+    pub fn line_x(&self, line: &LineLayout, width: f32, align: HorizontalAlign) -> f32 {
+        let (min_x, max_x) = line
+            .ink
+            .map_or((0.0, line.advance), |ink| (ink.min_x, ink.max_x));
+        match (align, line.rtl) {
+            (HorizontalAlign::Left, _)
+            | (HorizontalAlign::Start, false)
+            | (HorizontalAlign::End, true) => -min_x,
+            (HorizontalAlign::Center, _) => (width - (max_x - min_x)) / 2.0 - min_x,
+            (HorizontalAlign::Right, _)
+            | (HorizontalAlign::Start, true)
+            | (HorizontalAlign::End, false) => width - max_x,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_kerning_tightens_av() {
+        let font = Font::embedded();
+        let kerned = LineLayout::new(&font, "AV", 40.0).advance;
+        let unkerned = font.advance('A', 40.0) + font.advance('V', 40.0);
+        assert!(kerned < unkerned, "{} !< {}", kerned, unkerned);
+    }
+
+    #[test]
+    fn test_ink_bounds_include_descenders() {
+        let font = Font::embedded();
+        let line = LineLayout::new(&font, "gyp", 20.0);
+        let ink = line.ink.unwrap();
+        assert!(ink.max_y > 0.0, "descenders extend below the baseline");
+        assert!(ink.min_y < 0.0);
+    }
+
+    #[test]
+    fn test_blank_line_has_no_ink() {
+        let font = Font::embedded();
+        let line = LineLayout::new(&font, "   ", 20.0);
+        assert!(line.ink.is_none());
+        assert!(line.advance > 0.0);
+    }
+
+    #[test]
+    fn test_caps_ink_smaller_than_metric_box() {
+        let font = Font::embedded();
+        let block = BlockLayout::new(&font, "HELLO", 40.0);
+        assert!(block.ink_height() < block.metric_height());
+    }
+
+    #[test]
+    fn test_vertical_alignment_positions() {
+        let font = Font::embedded();
+        let block = BlockLayout::new(&font, "Hg", 20.0);
+        let (top, bottom) = block.ink_extent().unwrap();
+
+        let y = block.block_top(80.0, VerticalAlign::Top);
+        assert_eq!(y + top, 0.0);
+
+        let y = block.block_top(80.0, VerticalAlign::Bottom);
+        assert_eq!(y + bottom, 80.0);
+
+        let y = block.block_top(80.0, VerticalAlign::Middle);
+        assert!(((y + top) - (80.0 - (y + bottom))).abs() < 0.01);
+    }
+
+    #[test]
+    fn test_bold_widens_line() {
+        let font = Font::embedded();
+        let plain = BlockLayout::new(&font, "Bold", 20.0).ink_width();
+        let bold = BlockLayout::new(&font, "**Bold**", 20.0).ink_width();
+        assert!(bold > plain);
+    }
+
+    #[test]
+    fn test_larger_span_raises_line_height() {
+        let font = Font::embedded();
+        let block = BlockLayout::new(&font, "a {size=30}B{/}\nc", 14.0);
+        assert_eq!(block.lines[0].line_height, font.line_height(30.0));
+        assert_eq!(block.lines[1].line_height, font.line_height(14.0));
+        assert_eq!(block.baselines[0], font.ascent(30.0));
+    }
+
+    #[test]
+    fn test_line_spacing_moves_baselines() {
+        let font = Font::embedded();
+        let single = BlockLayout::new(&font, "a\nb", 20.0);
+        let double = BlockLayout::spaced(&font, "a\nb", 20.0, 2.0);
+        let gap = |block: &BlockLayout| block.baselines[1] - block.baselines[0];
+        assert_eq!(gap(&double), 2.0 * gap(&single));
+        assert!(double.metric_height() > single.metric_height());
+    }
+
+    #[test]
+    fn test_horizontal_alignment_positions() {
+        let font = Font::embedded();
+        let block = BlockLayout::new(&font, "Hi", 20.0);
+        let line = &block.lines[0];
+        let ink = line.ink.unwrap();
+
+        let x = block.line_x(line, 100.0, HorizontalAlign::Left);
+        assert_eq!(x + ink.min_x, 0.0);
+
+        let x = block.line_x(line, 100.0, HorizontalAlign::Right);
+        assert_eq!(x + ink.max_x, 100.0);
+    }
+
+    #[test]
+    fn test_baseline_align_ignores_content() {
+        let font = Font::embedded();
+        let digits = BlockLayout::new(&font, "12:00", 20.0);
+        let descenders = BlockLayout::new(&font, "gyp", 20.0);
+        assert_eq!(
+            digits.block_top(80.0, VerticalAlign::Baseline),
+            descenders.block_top(80.0, VerticalAlign::Baseline)
+        );
+    }
+
+    #[test]
+    fn test_rtl_paragraph_reorders_runs() {
+        let font = Font::embedded();
+        let line = LineLayout::new(&font, "שלום abc", 20.0);
+        assert!(line.rtl);
+        // The Latin run sits at the left end of a right-to-left line
+        assert_eq!(line.glyphs[0].glyph, Glyph::Char('a'));
+        assert!(matches!(
+            line.glyphs.last().unwrap().glyph,
+            Glyph::Shaped { .. }
+        ));
+
+        let line = LineLayout::new(&font, "abc שלום", 20.0);
+        assert!(!line.rtl);
+        assert_eq!(line.glyphs[0].glyph, Glyph::Char('a'));
+    }
+
+    #[test]
+    fn test_arabic_is_shaped_into_joined_forms() {
+        let font = Font::embedded();
+        let line = LineLayout::new(&font, "ببب", 20.0);
+        let ids: Vec<Glyph> = line.glyphs.iter().map(|g| g.glyph).collect();
+        assert_eq!(ids.len(), 3);
+        // Final, medial and initial forms (left to right) are different glyphs
+        assert_ne!(ids[0], ids[1]);
+        assert_ne!(ids[1], ids[2]);
+        assert!(line.ink_width() > 0.0);
+    }
+
+    #[test]
+    fn test_start_alignment_follows_direction() {
+        let font = Font::embedded();
+        let block = BlockLayout::new(&font, "שלום\nHello", 20.0);
+        let (hebrew, latin) = (&block.lines[0], &block.lines[1]);
+
+        let x = block.line_x(hebrew, 100.0, HorizontalAlign::Start);
+        assert_eq!(x + hebrew.ink.unwrap().max_x, 100.0);
+        let x = block.line_x(latin, 100.0, HorizontalAlign::Start);
+        assert_eq!(x + latin.ink.unwrap().min_x, 0.0);
+        let x = block.line_x(hebrew, 100.0, HorizontalAlign::End);
+        assert_eq!(x + hebrew.ink.unwrap().min_x, 0.0);
+    }
+}
@@ -1,19 +1,73 @@
 #![warn(clippy::all)]
 
+pub mod alert;
+#[cfg(feature = "async")]
+pub mod async_display;
+pub mod bitmap_font;
+pub mod bridge;
+pub mod capture;
+pub mod config;
+pub mod display;
+pub mod driver;
+pub mod font;
+pub mod framebuffer;
+mod glyph_cache;
+pub mod hyphenation;
+pub mod icons;
 pub mod image;
+pub mod layout;
+pub mod mirror;
+pub mod panel;
 pub mod port;
 pub mod protocol;
+pub mod quantize;
+pub mod sender;
 pub mod spotify;
 pub mod text;
+pub mod theme;
 
+pub use alert::{AlertLevel, AlertMonitor, AlertRule, Threshold};
+#[cfg(feature = "async")]
+pub use async_display::{open_async_connection, AsyncConnection, AsyncDisplay};
+pub use bitmap_font::{BitmapFont, BUILTIN_BITMAP_FONTS};
+pub use bridge::Bridge;
+pub use capture::{read_capture, replay, Capture, CaptureError, CaptureRecord, Tee};
+pub use config::{Config, ConfigError, SerialConfig, WidgetConfig};
+pub use display::{Display, DisplayError};
+pub use driver::{
+    driver_for, driver_named, parse_driver, select_driver, Capabilities, DeviceInfo, DisplayDriver,
+    WeActFsV1, DRIVERS,
+};
+pub use font::{Font, FontError, Glyph, ShapedGlyph};
+pub use framebuffer::{Framebuffer, FramebufferError};
+pub use hyphenation::Hyphenator;
 pub use image::{
     calculate_auto_fit_size, calculate_auto_fit_size_oriented, calculate_max_chars_per_line,
     calculate_max_chars_per_line_oriented, calculate_max_lines, calculate_max_lines_oriented,
-    create_text_image, create_text_image_oriented, image_to_rgb565_bytes,
-    image_to_rgb565_bytes_oriented, measure_text_with_font_size, Orientation, DISPLAY_HEIGHT,
-    DISPLAY_WIDTH,
+    create_text_image, create_text_image_oriented, create_text_image_themed, image_to_panel_bytes,
+    image_to_rgb565_bytes, image_to_rgb565_bytes_oriented, measure_multiline_text,
+    measure_text_with_font_size, Orientation, DISPLAY_HEIGHT, DISPLAY_WIDTH,
+};
+pub use layout::{
+    BlockLayout, HorizontalAlign, InkBounds, LayoutOptions, LineLayout, PositionedGlyph,
+    VerticalAlign,
+};
+pub use mirror::{decode_frame, fit_frame, read_frame, FrameFormat, FrameSource};
+pub use panel::{ByteOrder, PanelSpec, PixelFormat, Rect};
+pub use port::{
+    find_display_port, is_display_connected, open_connection, open_connection_with, open_device,
+    probe_baud_rate, resolve_baud_rate, BaudRate, Connection, FlowControl, PortError, PortInfo,
+    SerialSettings, DEFAULT_BAUD_RATE, PROBE_BAUD_RATES, TCP_PREFIX,
 };
-pub use port::{find_display_port, is_display_connected, open_connection, PortInfo};
-pub use protocol::{send_image_to_display, send_image_to_display_oriented};
+pub use protocol::{
+    send_image_to_display, send_image_to_display_oriented, send_image_to_panel, set_brightness,
+    Command, ProtocolError, ReplyParser, Step,
+};
+pub use quantize::{correct_colors, quantize_rgb565, ColorOptions, Quantizer};
+pub use sender::{FrameSender, SenderError, SenderMetrics};
 pub use spotify::{get_now_playing, NowPlaying};
-pub use text::split_into_pages;
+pub use text::{
+    escape_markup, parse_markup, plan_layout, split_into_pages, split_into_pages_oriented,
+    to_markup, LayoutPlan, Overflow, Span, Style,
+};
+pub use theme::{parse_color, Theme};
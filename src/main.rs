@@ -1,17 +1,29 @@
 use clap::{Parser, Subcommand, ValueEnum};
 use display_fs::{
-    calculate_auto_fit_size_oriented, create_text_image_oriented, find_display_port,
-    get_now_playing, image_to_rgb565_bytes_oriented, is_display_connected, open_connection,
-    send_image_to_display_oriented, split_into_pages, Orientation,
+    create_text_image_themed, driver_for, escape_markup, find_display_port, fit_frame,
+    get_now_playing, is_display_connected, open_connection_with, open_device, parse_color,
+    parse_driver, plan_layout, probe_baud_rate, read_capture, replay, resolve_baud_rate,
+    select_driver, split_into_pages_oriented, AlertLevel, AlertMonitor, AlertRule, BaudRate,
+    Bridge, ColorOptions, Config, Display, DisplayDriver, DisplayError, Font, FrameFormat,
+    FrameSender, FrameSource, Framebuffer, HorizontalAlign, LayoutOptions, LayoutPlan, Orientation,
+    Overflow, PortInfo, Quantizer, SenderError, SerialSettings, Theme, Threshold, VerticalAlign,
 };
+use image::imageops::FilterType;
+use image::Rgb;
+use std::net::TcpListener;
+use std::path::{Path, PathBuf};
 use std::process::{Command, ExitCode};
 use std::thread;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 #[derive(Parser)]
 #[command(name = "display-fs")]
 #[command(about = "Display text on WeAct Studio Display FS V1 (0.96 inch)")]
 struct Cli {
+    /// Config file [default: ~/.config/display-fs/config.toml]
+    #[arg(long, global = true)]
+    config: Option<PathBuf>,
+
     #[command(subcommand)]
     command: Option<Commands>,
 }
@@ -26,6 +38,9 @@ enum Commands {
 
         #[command(flatten)]
         display: DisplayOptions,
+
+        #[command(flatten)]
+        alert: AlertArgs,
     },
     /// List all available presets
     Presets,
@@ -33,11 +48,102 @@ enum Commands {
     Demo {
         #[command(flatten)]
         display: DisplayOptions,
+
+        #[command(flatten)]
+        alert: AlertArgs,
     },
     /// Display text on the screen (default command)
     Show(ShowArgs),
     /// Show currently playing Spotify track
     Spotify(SpotifyArgs),
+    /// Mirror raw frames rendered by another program
+    Mirror(MirrorArgs),
+    /// Show the connected display's driver, capabilities and firmware version
+    Info {
+        /// Display driver [default: detected from USB VID/PID]
+        #[arg(long, value_name = "NAME", value_parser = parse_driver)]
+        driver: Option<&'static dyn DisplayDriver>,
+
+        /// Serial baud rate, or `auto` to probe [default: 115200, or config]
+        #[arg(long, value_name = "RATE")]
+        baud: Option<BaudRate>,
+
+        /// Display to use: serial port, or tcp://host:port of a bridge [default: detected]
+        #[arg(long, value_name = "ADDR")]
+        device: Option<String>,
+    },
+    /// Run another command, recording every byte sent to the display
+    Capture {
+        /// Capture file to write
+        #[arg(long, value_name = "FILE")]
+        out: PathBuf,
+
+        /// Command to run, e.g. `show "Hello"`
+        #[arg(
+            value_name = "COMMAND",
+            trailing_var_arg = true,
+            allow_hyphen_values = true,
+            required = true
+        )]
+        command: Vec<String>,
+    },
+    /// Resend a capture file to a display with its original timing
+    Replay {
+        /// Capture file written by `capture`
+        file: PathBuf,
+
+        /// Serial port or tcp://host:port to send to (e.g. an emulator's pty) [default: detected display]
+        #[arg(long, value_name = "ADDR")]
+        port: Option<String>,
+
+        /// Serial baud rate, or `auto` to probe [default: 115200, or config]
+        #[arg(long, value_name = "RATE")]
+        baud: Option<BaudRate>,
+    },
+    /// Save what the display shows as a PNG
+    Screenshot {
+        /// PNG file to write
+        output: PathBuf,
+
+        /// Upscale by this whole factor, keeping pixels sharp
+        #[arg(long, default_value = "1", value_parser = clap::value_parser!(u32).range(1..=32))]
+        scale: u32,
+
+        /// Display to capture: serial port, or tcp://host:port of a bridge [default: detected]
+        #[arg(long, value_name = "ADDR")]
+        device: Option<String>,
+
+        /// State file recording the display's contents [default: ~/.local/state/display-fs/framebuffer-<device>.bin]
+        #[arg(long, value_name = "PATH")]
+        state: Option<PathBuf>,
+    },
+    /// Serve the local display over TCP, for clients using `--device tcp://host:port`
+    Bridge {
+        /// Address to listen on
+        #[arg(long, value_name = "ADDR", default_value = "0.0.0.0:7777")]
+        listen: String,
+
+        /// Serial port of the display [default: detected]
+        #[arg(long, value_name = "PATH")]
+        port: Option<String>,
+
+        /// Serial baud rate, or `auto` to probe [default: 115200, or config]
+        #[arg(long, value_name = "RATE")]
+        baud: Option<BaudRate>,
+    },
+}
+
+impl Commands {
+    /// Display options of commands that drive the display
+    fn display_options_mut(&mut self) -> Option<&mut DisplayOptions> {
+        match self {
+            Commands::Preset { display, .. } | Commands::Demo { display, .. } => Some(display),
+            Commands::Show(args) => Some(&mut args.display),
+            Commands::Spotify(args) => Some(&mut args.display),
+            Commands::Mirror(args) => Some(&mut args.display),
+            _ => None,
+        }
+    }
 }
 
 #[derive(Clone, Copy, Default, ValueEnum)]
@@ -58,20 +164,171 @@ impl From<OrientationArg> for Orientation {
     }
 }
 
+#[derive(Clone, Copy, Default, ValueEnum)]
+enum AlignArg {
+    Left,
+    /// Each line centered
+    Center,
+    Right,
+    /// Left, or right for right-to-left text (Arabic, Hebrew) (default)
+    #[default]
+    Start,
+    /// Right, or left for right-to-left text
+    End,
+}
+
+impl From<AlignArg> for HorizontalAlign {
+    fn from(arg: AlignArg) -> Self {
+        match arg {
+            AlignArg::Left => HorizontalAlign::Left,
+            AlignArg::Center => HorizontalAlign::Center,
+            AlignArg::Right => HorizontalAlign::Right,
+            AlignArg::Start => HorizontalAlign::Start,
+            AlignArg::End => HorizontalAlign::End,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Default, ValueEnum)]
+enum OverflowArg {
+    /// Break between any two characters
+    Break,
+    /// Break at hyphenation points, underscores and camelCase (default)
+    #[default]
+    Hyphenate,
+    /// Cut the word short with …
+    Ellipsis,
+    /// Shrink the word to fit on one line
+    Shrink,
+}
+
+impl From<OverflowArg> for Overflow {
+    fn from(arg: OverflowArg) -> Self {
+        match arg {
+            OverflowArg::Break => Overflow::BreakAnywhere,
+            OverflowArg::Hyphenate => Overflow::Hyphenate,
+            OverflowArg::Ellipsis => Overflow::Ellipsize,
+            OverflowArg::Shrink => Overflow::ShrinkToFit,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Default, ValueEnum)]
+enum VAlignArg {
+    /// Glyph tops at the top edge
+    Top,
+    /// Drawn glyphs centered (default)
+    #[default]
+    Middle,
+    /// Descenders at the bottom edge
+    Bottom,
+    /// Fixed baseline, so changing text doesn't jump
+    Baseline,
+}
+
+impl From<VAlignArg> for VerticalAlign {
+    fn from(arg: VAlignArg) -> Self {
+        match arg {
+            VAlignArg::Top => VerticalAlign::Top,
+            VAlignArg::Middle => VerticalAlign::Middle,
+            VAlignArg::Bottom => VerticalAlign::Bottom,
+            VAlignArg::Baseline => VerticalAlign::Baseline,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Default, ValueEnum)]
+enum DitherArg {
+    /// Drop the low bits (default)
+    #[default]
+    Truncate,
+    /// Round to the nearest color
+    Round,
+    /// Ordered dithering with a fixed pattern
+    Bayer,
+    /// Floyd–Steinberg error diffusion, best for gradients and images
+    Floyd,
+}
+
+impl From<DitherArg> for Quantizer {
+    fn from(arg: DitherArg) -> Self {
+        match arg {
+            DitherArg::Truncate => Quantizer::Truncate,
+            DitherArg::Round => Quantizer::Round,
+            DitherArg::Bayer => Quantizer::Bayer,
+            DitherArg::Floyd => Quantizer::FloydSteinberg,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Default, ValueEnum)]
+enum FrameFormatArg {
+    /// 3 bytes per pixel (default)
+    #[default]
+    Rgb,
+    /// 4 bytes per pixel, composited over black
+    Rgba,
+}
+
+impl From<FrameFormatArg> for FrameFormat {
+    fn from(arg: FrameFormatArg) -> Self {
+        match arg {
+            FrameFormatArg::Rgb => FrameFormat::Rgb,
+            FrameFormatArg::Rgba => FrameFormat::Rgba,
+        }
+    }
+}
+
 #[derive(clap::Args, Clone)]
 struct DisplayOptions {
     /// Font size in pixels
     #[arg(short = 's', long, default_value = "14")]
     font_size: f32,
 
-    /// Auto-fit text to largest readable size
+    /// Auto-fit text to largest readable size, wrapping as needed
     #[arg(short = 'a', long)]
     auto: bool,
 
+    /// Pages --auto may split text across to use a larger size
+    #[arg(long, value_name = "N", default_value = "1")]
+    max_pages: usize,
+
     /// Display orientation
     #[arg(short = 'o', long, value_enum, default_value = "landscape")]
     orientation: OrientationArg,
 
+    /// Horizontal alignment of each line
+    #[arg(long, value_enum, default_value = "start")]
+    align: AlignArg,
+
+    /// Vertical alignment of the text block
+    #[arg(long, value_enum, default_value = "middle")]
+    valign: VAlignArg,
+
+    /// Empty space at the left and right edges in pixels
+    #[arg(long, value_name = "PX", default_value = "4")]
+    margin_x: u32,
+
+    /// Empty space at the top and bottom edges in pixels
+    #[arg(long, value_name = "PX", default_value = "2")]
+    margin_y: u32,
+
+    /// Line spacing multiplier (1.0 = font line height)
+    #[arg(long, value_name = "MULT", default_value = "1.0", value_parser = validate_positive_f32)]
+    line_spacing: f32,
+
+    /// How to fit words wider than the display
+    #[arg(long, value_enum, default_value = "hyphenate")]
+    overflow: OverflowArg,
+
+    /// How colors are reduced to the panel's 16-bit RGB565
+    #[arg(long, value_enum, default_value = "truncate")]
+    dither: DitherArg,
+
+    /// Gamma correction for the panel (>1 darkens midtones)
+    #[arg(long, default_value = "1.0", value_parser = validate_positive_f32)]
+    gamma: f32,
+
     /// Delay between pages/updates in seconds (must be positive)
     #[arg(short, long, default_value = "2.0", value_parser = validate_positive_f32)]
     delay: f32,
@@ -83,6 +340,42 @@ struct DisplayOptions {
     /// Speed preset (overrides --delay if provided)
     #[arg(long, value_enum)]
     speed: Option<SpeedPreset>,
+
+    /// Text color (name or #RRGGBB)
+    #[arg(long, default_value = "white", value_parser = parse_color)]
+    fg: Rgb<u8>,
+
+    /// Background color (name or #RRGGBB)
+    #[arg(long, default_value = "black", value_parser = parse_color)]
+    bg: Rgb<u8>,
+
+    /// Font: TTF/OTF/BDF/PCF file or built-in bitmap font (5x7, 3x5, 8x16)
+    #[arg(long, value_name = "FONT")]
+    font: Option<PathBuf>,
+
+    /// Font tried for characters the main font lacks (repeatable, after config fallbacks)
+    #[arg(long = "fallback-font", value_name = "PATH")]
+    fallback_fonts: Vec<PathBuf>,
+
+    /// Display driver (weact-fs-v1) [default: detected from USB VID/PID]
+    #[arg(long, value_name = "NAME", value_parser = parse_driver)]
+    driver: Option<&'static dyn DisplayDriver>,
+
+    /// Serial baud rate, or `auto` to probe [default: 115200, or config]
+    #[arg(long, value_name = "RATE")]
+    baud: Option<BaudRate>,
+
+    /// Display to use: serial port, or tcp://host:port of a bridge [default: detected]
+    #[arg(long, value_name = "ADDR")]
+    device: Option<String>,
+
+    /// Serial settings: `--baud` over the config's `[serial]`
+    #[arg(skip)]
+    serial: SerialSettings,
+
+    /// Capture file recording what is sent (set by the `capture` command)
+    #[arg(skip)]
+    capture: Option<PathBuf>,
 }
 
 impl DisplayOptions {
@@ -93,11 +386,126 @@ impl DisplayOptions {
     pub fn orientation(&self) -> Orientation {
         self.orientation.into()
     }
+
+    pub fn theme(&self) -> Theme {
+        Theme::new(self.fg, self.bg)
+    }
+
+    pub fn layout(&self) -> LayoutOptions {
+        LayoutOptions {
+            horizontal_align: self.align.into(),
+            vertical_align: self.valign.into(),
+            margin_x: self.margin_x,
+            margin_y: self.margin_y,
+            line_spacing: self.line_spacing,
+            overflow: self.overflow.into(),
+        }
+    }
+
+    /// The display at `--device`, else the first one detected
+    pub fn locate(&self) -> Option<PortInfo> {
+        locate_display(self.device.as_deref())
+    }
+
+    /// Open the display on `port`, recording what it shows for `screenshot`
+    /// and capturing what is sent if requested
+    pub fn open_display(&self, port: &PortInfo) -> Result<Display, DisplayError> {
+        let mut display = Display::open_with(port, self.driver, &self.serial)?;
+        if let Some(path) = Framebuffer::default_path(&port.name) {
+            display = display.with_state_file(path);
+        }
+        match &self.capture {
+            Some(path) => Ok(display.capture_to(path)?),
+            None => Ok(display),
+        }
+    }
+
+    pub fn colors(&self) -> ColorOptions {
+        ColorOptions {
+            quantizer: self.dither.into(),
+            gamma: self.gamma,
+            ..ColorOptions::default()
+        }
+    }
+
+    /// Build the font fallback chain for a widget: `--font` (else the widget's
+    /// config font, else the config font), config fallbacks, `--fallback-font`s,
+    /// the embedded font, then system fonts
+    pub fn load_font(&self, config: &Config, widget: &str) -> Font {
+        let widget_font = config.widgets.get(widget).and_then(|w| w.font.as_ref());
+        let primary = self
+            .font
+            .iter()
+            .chain(widget_font)
+            .chain(config.font.iter())
+            .take(1);
+        let paths = primary
+            .chain(&config.fallback_fonts)
+            .chain(&self.fallback_fonts);
+
+        let mut chain: Option<Font> = None;
+        for path in paths {
+            match Font::load(path) {
+                Ok(font) => {
+                    chain = Some(match chain {
+                        Some(chain) => chain.with_fallback(font),
+                        None => font,
+                    })
+                }
+                Err(e) => println!("⚠ Skipping font {}: {}", path.display(), e),
+            }
+        }
+
+        match chain {
+            Some(chain) => chain.with_fallback(Font::embedded()),
+            None => Font::embedded(),
+        }
+        .with_system_fallback()
+    }
+}
+
+#[derive(clap::Args, Clone)]
+struct AlertArgs {
+    /// Warning threshold: number (>= value), <number, or /regex/ on the output
+    #[arg(long, value_parser = Threshold::parse)]
+    warn: Option<Threshold>,
+
+    /// Critical threshold: number (>= value), <number, or /regex/ on the output
+    #[arg(long, value_parser = Threshold::parse)]
+    critical: Option<Threshold>,
+
+    /// Blink the backlight while critical
+    #[arg(long)]
+    blink: bool,
+
+    /// Invert the screen colors while warning or critical
+    #[arg(long)]
+    invert: bool,
+}
+
+impl AlertArgs {
+    /// Thresholds from the command line, falling back to the preset defaults
+    fn rule_for(&self, preset: PresetName) -> AlertRule {
+        let defaults = preset.default_alert();
+        AlertRule::new(
+            self.warn.clone().or(defaults.warning),
+            self.critical.clone().or(defaults.critical),
+        )
+    }
+
+    fn theme_for(&self, level: AlertLevel, base: Theme) -> Theme {
+        let theme = level.theme(base);
+        if self.invert && level != AlertLevel::Ok {
+            theme.inverted()
+        } else {
+            theme
+        }
+    }
 }
 
 #[derive(clap::Args)]
 struct ShowArgs {
-    /// Text to display (default: "Hello World!")
+    /// Text to display; supports {red}color{/}, **bold** and {size=20} markup
     #[arg(default_value = "Hello World!")]
     text: String,
 
@@ -119,6 +527,28 @@ struct SpotifyArgs {
     display: DisplayOptions,
 }
 
+#[derive(clap::Args)]
+struct MirrorArgs {
+    /// FIFO or pipe streaming frames, or a file rewritten in place (e.g. in /dev/shm)
+    #[arg(long, value_name = "PATH")]
+    source: PathBuf,
+
+    /// Size of the source frames
+    #[arg(long, value_name = "WxH", value_parser = parse_size)]
+    size: (u32, u32),
+
+    /// Pixel layout of the source frames
+    #[arg(long, value_enum, default_value = "rgb")]
+    format: FrameFormatArg,
+
+    /// How often a file source is re-read, in frames per second
+    #[arg(long, default_value = "10", value_parser = validate_fps)]
+    fps: f32,
+
+    #[command(flatten)]
+    display: DisplayOptions,
+}
+
 #[derive(Clone, Copy, Debug, ValueEnum)]
 enum PresetName {
     /// Current time (HH:MM:SS)
@@ -139,6 +569,8 @@ enum PresetName {
     Cpu,
     /// Memory pressure (macOS)
     Memory,
+    /// Root filesystem usage
+    Disk,
     /// Docker container count
     Docker,
     /// Now playing from Spotify (macOS)
@@ -172,6 +604,10 @@ impl PresetName {
                 "Memory pressure (macOS)",
                 "memory_pressure 2>/dev/null | grep 'System-wide' | awk '{print \"Mem: \" $NF}' || echo 'Mem: N/A'",
             ),
+            PresetName::Disk => (
+                "Disk usage",
+                "df -P / | awk 'NR==2 {print \"Disk: \" $5}'",
+            ),
             PresetName::Docker => (
                 "Docker containers",
                 "echo \"Docker: $(docker ps -q 2>/dev/null | wc -l | tr -d ' ') running\"",
@@ -184,6 +620,30 @@ impl PresetName {
         }
     }
 
+    /// Name used on the command line and for `[widgets.<name>]` config sections
+    pub fn widget_name(self) -> String {
+        self.to_possible_value()
+            .map(|v| v.get_name().to_string())
+            .unwrap_or_default()
+    }
+
+    /// Built-in warning/critical thresholds for metric presets
+    pub fn default_alert(self) -> AlertRule {
+        match self {
+            PresetName::Cpu => {
+                AlertRule::new(Some(Threshold::Above(70.0)), Some(Threshold::Above(90.0)))
+            }
+            // memory_pressure reports the free percentage
+            PresetName::Memory => {
+                AlertRule::new(Some(Threshold::Below(25.0)), Some(Threshold::Below(10.0)))
+            }
+            PresetName::Disk => {
+                AlertRule::new(Some(Threshold::Above(80.0)), Some(Threshold::Above(90.0)))
+            }
+            _ => AlertRule::default(),
+        }
+    }
+
     pub fn run_command(self) -> String {
         let (_, cmd) = self.info();
         match Command::new("sh").arg("-c").arg(cmd).output() {
@@ -224,22 +684,99 @@ fn validate_positive_f32(s: &str) -> Result<f32, String> {
     let value: f32 = s
         .parse()
         .map_err(|_| format!("'{}' is not a valid number", s))?;
-    if value <= 0.0 {
-        Err("delay must be a positive number".to_string())
-    } else {
+    // Also rejects NaN and infinity, which durations can't be built from
+    if value.is_finite() && value > 0.0 {
         Ok(value)
+    } else {
+        Err("must be a positive number".to_string())
+    }
+}
+
+/// Largest mirror source side, keeping a frame buffer under 256 MB
+const MAX_FRAME_SIDE: u32 = 8192;
+
+fn parse_size(s: &str) -> Result<(u32, u32), String> {
+    let invalid = || format!("invalid size '{}' (expected WIDTHxHEIGHT, e.g. 320x160)", s);
+    let (width, height) = s.split_once(['x', 'X']).ok_or_else(invalid)?;
+    let in_range = |side| (1..=MAX_FRAME_SIDE).contains(&side);
+    match (width.trim().parse(), height.trim().parse()) {
+        (Ok(width), Ok(height)) if in_range(width) && in_range(height) => Ok((width, height)),
+        (Ok(_), Ok(_)) => Err(format!(
+            "invalid size '{}' (each side must be 1 to {})",
+            s, MAX_FRAME_SIDE
+        )),
+        _ => Err(invalid()),
+    }
+}
+
+/// Frame rates the mirror poll interval can be built from
+const FPS_RANGE: std::ops::RangeInclusive<f32> = 0.01..=1000.0;
+
+fn validate_fps(s: &str) -> Result<f32, String> {
+    let fps = validate_positive_f32(s)?;
+    if FPS_RANGE.contains(&fps) {
+        Ok(fps)
+    } else {
+        Err(format!(
+            "must be between {} and {}",
+            FPS_RANGE.start(),
+            FPS_RANGE.end()
+        ))
     }
 }
 
 fn main() -> ExitCode {
     let cli = Cli::parse();
 
-    match cli.command {
-        Some(Commands::Preset { name, display }) => run_preset(name, display),
+    let config = match cli.config.as_deref() {
+        Some(path) => Config::load(path),
+        None => Config::load_default(),
+    };
+    let config = match config {
+        Ok(c) => c,
+        Err(e) => {
+            println!("✗ {}", e);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    run_command(cli.command, &config)
+}
+
+fn run_command(mut command: Option<Commands>, config: &Config) -> ExitCode {
+    if let Some(display) = command.as_mut().and_then(Commands::display_options_mut) {
+        display.serial = config.serial.settings(display.baud);
+    }
+
+    match command {
+        Some(Commands::Preset {
+            name,
+            display,
+            alert,
+        }) => run_preset(name, display, alert, config),
         Some(Commands::Presets) => list_presets(),
-        Some(Commands::Demo { display }) => run_demo(display),
-        Some(Commands::Show(args)) => run_show(args),
-        Some(Commands::Spotify(args)) => run_spotify(args),
+        Some(Commands::Demo { display, alert }) => run_demo(display, alert, config),
+        Some(Commands::Show(args)) => run_show(args, config),
+        Some(Commands::Spotify(args)) => run_spotify(args, config),
+        Some(Commands::Mirror(args)) => run_mirror(args),
+        Some(Commands::Info {
+            driver,
+            baud,
+            device,
+        }) => show_info(driver, device.as_deref(), config.serial.settings(baud)),
+        Some(Commands::Capture { out, command }) => run_capture(out, command, config),
+        Some(Commands::Replay { file, port, baud }) => {
+            run_replay(&file, port, config.serial.settings(baud))
+        }
+        Some(Commands::Screenshot {
+            output,
+            scale,
+            device,
+            state,
+        }) => run_screenshot(&output, scale, device.as_deref(), state),
+        Some(Commands::Bridge { listen, port, baud }) => {
+            run_bridge(&listen, port.as_deref(), config.serial.settings(baud))
+        }
         None => {
             // Default: show help
             use clap::CommandFactory;
@@ -250,12 +787,219 @@ fn main() -> ExitCode {
     }
 }
 
-fn run_show(args: ShowArgs) -> ExitCode {
+fn run_show(args: ShowArgs, config: &Config) -> ExitCode {
     if args.detect {
-        return detect_display();
+        return detect_display(args.display.driver, args.display.device.as_deref());
+    }
+
+    let font = args.display.load_font(config, "show");
+    display_text(&font, &args.text, &args.display, args.display.theme())
+}
+
+fn run_capture(out: PathBuf, command: Vec<String>, config: &Config) -> ExitCode {
+    let args = std::iter::once("display-fs".to_string()).chain(command);
+    let mut inner = match Cli::try_parse_from(args) {
+        Ok(cli) => cli.command,
+        Err(e) => e.exit(),
+    };
+    match inner.as_mut().and_then(Commands::display_options_mut) {
+        Some(display) => display.capture = Some(out.clone()),
+        None => {
+            println!("✗ Only commands that drive the display can be captured");
+            return ExitCode::FAILURE;
+        }
+    }
+
+    println!("Capturing to {}", out.display());
+    run_command(inner, config)
+}
+
+fn run_replay(file: &Path, port: Option<String>, serial: SerialSettings) -> ExitCode {
+    let capture = match read_capture(file) {
+        Ok(c) => c,
+        Err(e) => {
+            println!("✗ {}: {}", file.display(), e);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let port_info = match locate_display(port.as_deref()) {
+        Some(p) => p,
+        None => {
+            println!("✗ Display FS V1 not found");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let baud_rate = match resolve_baud_rate(&port_info, &serial, select_driver(&port_info, None)) {
+        Ok(rate) => rate,
+        Err(e) => {
+            println!("✗ Failed to open connection: {}", e);
+            return ExitCode::FAILURE;
+        }
+    };
+    let serial = SerialSettings {
+        baud_rate: BaudRate::Fixed(baud_rate),
+        ..serial
+    };
+
+    let mut connection = match open_device(&port_info, &serial) {
+        Ok(c) => c,
+        Err(e) => {
+            println!("✗ Failed to open connection: {}", e);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let duration = capture
+        .records
+        .last()
+        .map_or(0.0, |r| r.offset.as_secs_f32());
+    println!(
+        "Replaying {} writes ({:.1}s) to {}...",
+        capture.records.len(),
+        duration,
+        port_info.name
+    );
+    if let Err(e) = replay(&capture, connection.as_mut()) {
+        println!("✗ Failed to replay: {}", e);
+        return ExitCode::FAILURE;
     }
+    println!("✓ Replay finished");
+    ExitCode::SUCCESS
+}
+
+fn run_bridge(listen: &str, port: Option<&str>, serial: SerialSettings) -> ExitCode {
+    let port_info = match locate_display(port) {
+        Some(p) if p.tcp_address().is_none() => p,
+        Some(_) => {
+            println!("✗ The bridge needs a local serial port");
+            return ExitCode::FAILURE;
+        }
+        None => {
+            println!("✗ Display FS V1 not found");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let baud_rate = match resolve_baud_rate(&port_info, &serial, select_driver(&port_info, None)) {
+        Ok(rate) => rate,
+        Err(e) => {
+            println!("✗ Failed to open connection: {}", e);
+            return ExitCode::FAILURE;
+        }
+    };
+    let serial = SerialSettings {
+        baud_rate: BaudRate::Fixed(baud_rate),
+        ..serial
+    };
+    let connection = match open_connection_with(&port_info, &serial) {
+        Ok(c) => c,
+        Err(e) => {
+            println!("✗ Failed to open connection: {}", e);
+            return ExitCode::FAILURE;
+        }
+    };
+    let reader = match connection.try_clone() {
+        Ok(r) => r,
+        Err(e) => {
+            println!("✗ Failed to open connection: {}", e);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let listener = match TcpListener::bind(listen) {
+        Ok(l) => l,
+        Err(e) => {
+            println!("✗ Failed to listen on {}: {}", listen, e);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    println!(
+        "✓ Bridging {} ({} baud) on {} (Ctrl+C to stop)",
+        port_info.name, baud_rate, listen
+    );
+    let mut bridge = Bridge::new(reader, connection);
+    for stream in listener.incoming() {
+        let stream = match stream {
+            Ok(s) => s,
+            Err(e) => {
+                println!("⚠ Failed to accept client: {}", e);
+                continue;
+            }
+        };
+        let peer = stream
+            .peer_addr()
+            .map_or_else(|_| "unknown".to_string(), |addr| addr.to_string());
+        println!("✓ Client connected: {}", peer);
+        match bridge.serve_client(stream) {
+            Ok(bytes) => println!("  Client {} disconnected ({} bytes)", peer, bytes),
+            Err(e) => println!("⚠ Client {}: {}", peer, e),
+        }
+    }
+    ExitCode::SUCCESS
+}
+
+fn run_screenshot(
+    output: &Path,
+    scale: u32,
+    device: Option<&str>,
+    state: Option<PathBuf>,
+) -> ExitCode {
+    let state = match state {
+        Some(state) => state,
+        None => {
+            let Some(port) = locate_display(device) else {
+                println!("✗ No display found (pass --device or --state)");
+                return ExitCode::FAILURE;
+            };
+            let Some(state) = Framebuffer::default_path(&port.name) else {
+                println!("✗ No state file location (set HOME or pass --state)");
+                return ExitCode::FAILURE;
+            };
+            state
+        }
+    };
+
+    let framebuffer = match Framebuffer::load(&state) {
+        Ok(f) => f,
+        Err(e) => {
+            println!("✗ Nothing recorded in {}: {}", state.display(), e);
+            println!("  Send something to the display first.");
+            return ExitCode::FAILURE;
+        }
+    };
 
-    display_text(&args.text, &args.display)
+    let mut img = framebuffer.to_image();
+    if scale > 1 {
+        img = image::imageops::resize(
+            &img,
+            img.width() * scale,
+            img.height() * scale,
+            FilterType::Nearest,
+        );
+    }
+
+    if let Err(e) = img.save(output) {
+        println!("✗ Failed to save {}: {}", output.display(), e);
+        return ExitCode::FAILURE;
+    }
+    println!(
+        "✓ Saved {}x{} screenshot to {}",
+        img.width(),
+        img.height(),
+        output.display()
+    );
+    ExitCode::SUCCESS
+}
+
+/// The display at `device` (a serial port or `tcp://host:port`), else the
+/// first one detected
+fn locate_display(device: Option<&str>) -> Option<PortInfo> {
+    device
+        .map(PortInfo::from_address)
+        .or_else(find_display_port)
 }
 
 fn list_presets() -> ExitCode {
@@ -276,17 +1020,120 @@ fn list_presets() -> ExitCode {
     ExitCode::SUCCESS
 }
 
-fn run_preset(name: PresetName, display: DisplayOptions) -> ExitCode {
+fn run_preset(
+    name: PresetName,
+    display: DisplayOptions,
+    alert: AlertArgs,
+    config: &Config,
+) -> ExitCode {
     let (desc, _) = name.info();
     println!("Running preset: {}", desc);
 
-    let text = name.run_command();
-    println!("Output: {}", text);
+    let font = display.load_font(config, &name.widget_name());
+    let mut monitor = AlertMonitor::new(alert.rule_for(name));
+
+    if !display.r#loop {
+        let text = name.run_command();
+        println!("Output: {}", text);
+        monitor.update(&text);
+        let theme = alert.theme_for(monitor.level(), display.theme());
+        return display_text(&font, &escape_markup(&text), &display, theme);
+    }
+
+    let orientation = display.orientation();
+
+    let port_info = match display.locate() {
+        Some(p) => p,
+        None => {
+            println!("✗ Display FS V1 not found");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    println!("✓ Found display on {}", port_info.name);
+
+    let sender = match display.open_display(&port_info) {
+        Ok(c) => FrameSender::spawn(c),
+        Err(e) => {
+            println!("✗ Failed to open connection: {}", e);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let delay_duration = Duration::from_secs_f32(display.effective_delay());
+    let mut dropped = 0;
+
+    loop {
+        let text = name.run_command();
+        println!("Output: {}", text);
+        if let Some((from, to)) = monitor.update(&text) {
+            log_alert_transition(desc, from, to, &text);
+        }
+
+        let theme = alert.theme_for(monitor.level(), display.theme());
+        let markup = escape_markup(&text);
+        let plan = plan_text(&font, &markup, &display);
+        let img = create_text_image_themed(
+            &font,
+            plan.first_page(&markup),
+            plan.font_size,
+            orientation,
+            theme,
+            display.layout(),
+        );
+        if let Err(e) = sender.submit_image(&img, orientation, display.colors()) {
+            println!("✗ Failed to send image: {}", e);
+            return ExitCode::FAILURE;
+        }
+
+        let metrics = sender.metrics();
+        if metrics.dropped > dropped {
+            dropped = metrics.dropped;
+            println!(
+                "⚠ Display can't keep up: {} frames dropped, link {:.0}% busy",
+                dropped,
+                metrics.utilization() * 100.0
+            );
+        }
+
+        let blink = alert.blink && monitor.level() == AlertLevel::Critical;
+        if let Err(e) = wait_with_blink(&sender, delay_duration, blink) {
+            println!("✗ Failed to set brightness: {}", e);
+            return ExitCode::FAILURE;
+        }
+    }
+}
+
+fn log_alert_transition(desc: &str, from: AlertLevel, to: AlertLevel, output: &str) {
+    let marker = if to == AlertLevel::Ok { "✓" } else { "⚠" };
+    println!("{} [{}] {} → {}: {}", marker, desc, from, to, output);
+}
+
+const BLINK_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Sleep for `duration`, toggling the backlight when `blink` is set.
+/// Always leaves the backlight at full brightness.
+fn wait_with_blink(
+    sender: &FrameSender,
+    duration: Duration,
+    blink: bool,
+) -> Result<(), SenderError> {
+    if !blink {
+        thread::sleep(duration);
+        return Ok(());
+    }
 
-    display_text(&text, &display)
+    let start = Instant::now();
+    let mut lit = true;
+    while start.elapsed() < duration {
+        lit = !lit;
+        sender.set_brightness(if lit { 255 } else { 0 })?;
+        thread::sleep(BLINK_INTERVAL.min(duration.saturating_sub(start.elapsed())));
+    }
+    sender.set_brightness(255)
 }
 
-const ALL_PRESETS: [PresetName; 12] = [
+const ALL_PRESETS: [PresetName; 13] = [
     PresetName::Clock,
     PresetName::DateTime,
     PresetName::Uptime,
@@ -296,12 +1143,17 @@ const ALL_PRESETS: [PresetName; 12] = [
     PresetName::Pwd,
     PresetName::Cpu,
     PresetName::Memory,
+    PresetName::Disk,
     PresetName::Docker,
     PresetName::Spotify,
     PresetName::Fortune,
 ];
 
-fn run_demo(display: DisplayOptions) -> ExitCode {
+fn run_demo(display: DisplayOptions, alert: AlertArgs, config: &Config) -> ExitCode {
+    let fonts: Vec<Font> = ALL_PRESETS
+        .iter()
+        .map(|preset| display.load_font(config, &preset.widget_name()))
+        .collect();
     let delay = display.effective_delay();
     let orientation = display.orientation();
     println!("Demo mode: cycling through all presets (Ctrl+C to stop)");
@@ -310,7 +1162,7 @@ fn run_demo(display: DisplayOptions) -> ExitCode {
         delay, orientation
     );
 
-    let port_info = match find_display_port() {
+    let port_info = match display.locate() {
         Some(p) => p,
         None => {
             println!("✗ Display FS V1 not found");
@@ -320,8 +1172,8 @@ fn run_demo(display: DisplayOptions) -> ExitCode {
 
     println!("✓ Found display on {}", port_info.name);
 
-    let mut connection = match open_connection(&port_info) {
-        Ok(c) => c,
+    let sender = match display.open_display(&port_info) {
+        Ok(c) => FrameSender::spawn(c),
         Err(e) => {
             println!("✗ Failed to open connection: {}", e);
             return ExitCode::FAILURE;
@@ -329,33 +1181,50 @@ fn run_demo(display: DisplayOptions) -> ExitCode {
     };
 
     let delay_duration = Duration::from_secs_f32(delay);
+    let mut monitors: Vec<AlertMonitor> = ALL_PRESETS
+        .iter()
+        .map(|&preset| AlertMonitor::new(alert.rule_for(preset)))
+        .collect();
 
     loop {
-        for preset in ALL_PRESETS {
+        for ((preset, monitor), font) in ALL_PRESETS.iter().zip(monitors.iter_mut()).zip(&fonts) {
             let (desc, _) = preset.info();
             let text = preset.run_command();
             println!("[{}] {}", desc, text);
+            if let Some((from, to)) = monitor.update(&text) {
+                log_alert_transition(desc, from, to, &text);
+            }
 
-            let font_size = get_effective_font_size(&text, &display);
-            let img = create_text_image_oriented(&text, font_size, orientation);
-            let image_data = image_to_rgb565_bytes_oriented(&img, orientation);
-
-            if let Err(e) =
-                send_image_to_display_oriented(&mut connection, &image_data, orientation)
-            {
+            let theme = alert.theme_for(monitor.level(), display.theme());
+            let markup = escape_markup(&text);
+            let plan = plan_text(font, &markup, &display);
+            let img = create_text_image_themed(
+                font,
+                plan.first_page(&markup),
+                plan.font_size,
+                orientation,
+                theme,
+                display.layout(),
+            );
+            if let Err(e) = sender.submit_image(&img, orientation, display.colors()) {
                 println!("✗ Failed to send image: {}", e);
                 return ExitCode::FAILURE;
             }
 
-            thread::sleep(delay_duration);
+            let blink = alert.blink && monitor.level() == AlertLevel::Critical;
+            if let Err(e) = wait_with_blink(&sender, delay_duration, blink) {
+                println!("✗ Failed to set brightness: {}", e);
+                return ExitCode::FAILURE;
+            }
         }
     }
 }
 
-fn run_spotify(args: SpotifyArgs) -> ExitCode {
+fn run_spotify(args: SpotifyArgs, config: &Config) -> ExitCode {
+    let font = args.display.load_font(config, "spotify");
     let orientation = args.display.orientation();
 
-    let port_info = match find_display_port() {
+    let port_info = match args.display.locate() {
         Some(p) => p,
         None => {
             println!("✗ Display FS V1 not found");
@@ -365,7 +1234,7 @@ fn run_spotify(args: SpotifyArgs) -> ExitCode {
 
     println!("✓ Found display on {}", port_info.name);
 
-    let mut connection = match open_connection(&port_info) {
+    let mut connection = match args.display.open_display(&port_info) {
         Ok(c) => c,
         Err(e) => {
             println!("✗ Failed to open connection: {}", e);
@@ -380,16 +1249,16 @@ fn run_spotify(args: SpotifyArgs) -> ExitCode {
         let text = match get_now_playing() {
             Some(np) if np.is_playing => {
                 format!(
-                    "♪ {}\nby {}",
-                    truncate(&np.track, 18),
-                    truncate(&np.artist, 18)
+                    ":play: {}\nby {}",
+                    escape_markup(&truncate(&np.track, 18)),
+                    escape_markup(&truncate(&np.artist, 18))
                 )
             }
             Some(np) => {
                 format!(
-                    "|| {}\nby {}",
-                    truncate(&np.track, 18),
-                    truncate(&np.artist, 18)
+                    ":pause: {}\nby {}",
+                    escape_markup(&truncate(&np.track, 18)),
+                    escape_markup(&truncate(&np.artist, 18))
                 )
             }
             None => "Spotify not running".to_string(),
@@ -399,13 +1268,17 @@ fn run_spotify(args: SpotifyArgs) -> ExitCode {
         let should_update = current != last_track;
 
         if should_update {
-            let font_size = get_effective_font_size(&text, &args.display);
-            let img = create_text_image_oriented(&text, font_size, orientation);
-            let image_data = image_to_rgb565_bytes_oriented(&img, orientation);
-
-            if let Err(e) =
-                send_image_to_display_oriented(&mut connection, &image_data, orientation)
-            {
+            let plan = plan_text(&font, &text, &args.display);
+            let theme = args.display.theme();
+            let img = create_text_image_themed(
+                &font,
+                plan.first_page(&text),
+                plan.font_size,
+                orientation,
+                theme,
+                args.display.layout(),
+            );
+            if let Err(e) = connection.send_image(&img, orientation, args.display.colors()) {
                 println!("✗ Failed to send image: {}", e);
                 return ExitCode::FAILURE;
             }
@@ -424,6 +1297,85 @@ fn run_spotify(args: SpotifyArgs) -> ExitCode {
     ExitCode::SUCCESS
 }
 
+fn run_mirror(args: MirrorArgs) -> ExitCode {
+    let (width, height) = args.size;
+    let mut source = match FrameSource::open(&args.source, width, height, args.format.into()) {
+        Ok(s) => s,
+        Err(e) => {
+            println!("✗ {}: {}", args.source.display(), e);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let port_info = match args.display.locate() {
+        Some(p) => p,
+        None => {
+            println!("✗ Display FS V1 not found");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    println!("✓ Found display on {}", port_info.name);
+
+    let sender = match args.display.open_display(&port_info) {
+        Ok(c) => FrameSender::spawn(c),
+        Err(e) => {
+            println!("✗ Failed to open connection: {}", e);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let orientation = args.display.orientation();
+    let panel = sender.panel();
+    let (out_width, out_height) = (
+        panel.logical_width(orientation),
+        panel.logical_height(orientation),
+    );
+    let interval = Duration::from_secs_f32(1.0 / args.fps);
+    println!(
+        "Mirroring {} ({}x{}) to {}x{} (Ctrl+C to stop)",
+        args.source.display(),
+        width,
+        height,
+        out_width,
+        out_height
+    );
+
+    loop {
+        let started = Instant::now();
+        match source.read_frame() {
+            Ok(Some(frame)) => {
+                let img = fit_frame(&frame, out_width, out_height);
+                if let Err(e) = sender.submit_image(&img, orientation, args.display.colors()) {
+                    println!("✗ Failed to send image: {}", e);
+                    return ExitCode::FAILURE;
+                }
+            }
+            // A stream that ended is done; a file may be mid-rewrite
+            Ok(None) if !source.is_polled() => break,
+            Ok(None) => {}
+            Err(e) => {
+                println!("✗ Failed to read frame: {}", e);
+                return ExitCode::FAILURE;
+            }
+        }
+        if source.is_polled() {
+            thread::sleep(interval.saturating_sub(started.elapsed()));
+        }
+    }
+
+    if let Err(e) = sender.flush() {
+        println!("✗ Failed to send image: {}", e);
+        return ExitCode::FAILURE;
+    }
+    let metrics = sender.metrics();
+    println!(
+        "✓ Source closed: {} frames, {} sent, {} dropped",
+        metrics.submitted, metrics.sent, metrics.dropped
+    );
+    ExitCode::SUCCESS
+}
+
 fn truncate(s: &str, max_len: usize) -> String {
     if s.chars().count() <= max_len {
         s.to_string()
@@ -432,13 +1384,30 @@ fn truncate(s: &str, max_len: usize) -> String {
     }
 }
 
-fn detect_display() -> ExitCode {
+fn detect_display(driver: Option<&'static dyn DisplayDriver>, device: Option<&str>) -> ExitCode {
+    if let Some(address) = device {
+        let port = PortInfo::from_address(address);
+        return match open_device(&port, &SerialSettings::default()) {
+            Ok(_) => {
+                println!("✓ Display reachable at {}", port.name);
+                ExitCode::SUCCESS
+            }
+            Err(e) => {
+                println!("✗ {}", e);
+                ExitCode::FAILURE
+            }
+        };
+    }
+
     println!("Looking for Display FS V1...");
 
     if is_display_connected() {
         if let Some(port) = find_display_port() {
             println!("✓ Found display on {}", port.name);
             println!("  VID: {:04X}, PID: {:04X}", port.vid, port.pid);
+            if let Some(driver) = driver.or_else(|| driver_for(port.vid, port.pid)) {
+                println!("  Driver: {} - {}", driver.name(), driver.description());
+            }
             return ExitCode::SUCCESS;
         }
     }
@@ -449,25 +1418,119 @@ fn detect_display() -> ExitCode {
     ExitCode::FAILURE
 }
 
-fn get_effective_font_size(text: &str, display: &DisplayOptions) -> f32 {
+fn show_info(
+    driver: Option<&'static dyn DisplayDriver>,
+    device: Option<&str>,
+    serial: SerialSettings,
+) -> ExitCode {
+    let port_info = match locate_display(device) {
+        Some(p) => p,
+        None => {
+            println!("✗ Display FS V1 not found");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let driver = select_driver(&port_info, driver);
+    let probed = match serial.baud_rate {
+        BaudRate::Auto => match probe_baud_rate(&port_info, &serial, driver) {
+            Ok(rate) => Some(rate),
+            Err(e) => {
+                println!("✗ Failed to open connection: {}", e);
+                return ExitCode::FAILURE;
+            }
+        },
+        BaudRate::Fixed(_) => None,
+    };
+    let baud_rate = probed.flatten().unwrap_or(serial.baud_rate.rate());
+    let serial = SerialSettings {
+        baud_rate: BaudRate::Fixed(baud_rate),
+        ..serial
+    };
+
+    let mut connection = match Display::open_with(&port_info, Some(driver), &serial) {
+        Ok(c) => c,
+        Err(e) => {
+            println!("✗ Failed to open connection: {}", e);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let driver = connection.driver();
+    let capabilities = connection.capabilities();
+    let panel = capabilities.panel;
+    println!("✓ Found display on {}", port_info.name);
+    println!("  VID: {:04X}, PID: {:04X}", port_info.vid, port_info.pid);
+    println!("  Driver: {} - {}", driver.name(), driver.description());
+    match probed {
+        Some(Some(_)) => println!("  Baud rate: {} (probed)", baud_rate),
+        Some(None) => println!("  Baud rate: {} (no reply at any probed rate)", baud_rate),
+        None => println!("  Baud rate: {}", baud_rate),
+    }
+    println!(
+        "  Panel: {}x{}, {:?}",
+        panel.width, panel.height, panel.pixel_format
+    );
+    println!(
+        "  Brightness control: {}, partial updates: {}",
+        yes_no(capabilities.brightness),
+        yes_no(capabilities.partial_updates)
+    );
+
+    match connection.query_info() {
+        Ok(info) => println!(
+            "  Firmware: {}",
+            info.firmware.as_deref().unwrap_or("not reported")
+        ),
+        Err(e) => {
+            println!("✗ Failed to query device: {}", e);
+            return ExitCode::FAILURE;
+        }
+    }
+
+    ExitCode::SUCCESS
+}
+
+fn yes_no(value: bool) -> &'static str {
+    if value {
+        "yes"
+    } else {
+        "no"
+    }
+}
+
+/// Pick the font size and wrapped pages: with `--auto` the largest size that
+/// fits in `--max-pages` pages, otherwise `--font-size`
+fn plan_text(font: &Font, text: &str, display: &DisplayOptions) -> LayoutPlan {
+    let orientation = display.orientation();
     if display.auto {
-        let size = calculate_auto_fit_size_oriented(text, display.orientation());
-        println!("Auto-fit font size: {:.1}", size);
-        size
+        let plan = plan_layout(font, text, orientation, display.layout(), display.max_pages);
+        println!("Auto-fit font size: {:.1}", plan.font_size);
+        plan
     } else {
-        display.font_size
+        LayoutPlan {
+            font_size: display.font_size,
+            pages: split_into_pages_oriented(
+                font,
+                text,
+                display.font_size,
+                orientation,
+                display.layout(),
+            ),
+        }
     }
 }
 
-fn display_text(text: &str, display: &DisplayOptions) -> ExitCode {
-    let font_size = get_effective_font_size(text, display);
+fn display_text(font: &Font, text: &str, display: &DisplayOptions, theme: Theme) -> ExitCode {
+    let plan = plan_text(font, text, display);
+    let font_size = plan.font_size;
     let delay = display.effective_delay();
     let loop_mode = display.r#loop;
     let orientation = display.orientation();
 
     println!("Looking for Display FS V1...");
 
-    let port_info = match find_display_port() {
+    let port_info = match display.locate() {
         Some(p) => p,
         None => {
             println!("✗ Display FS V1 not found");
@@ -479,11 +1542,10 @@ fn display_text(text: &str, display: &DisplayOptions) -> ExitCode {
 
     println!("✓ Found display on {}", port_info.name);
 
-    let pages = split_into_pages(text, font_size);
-    let pages = if pages.is_empty() {
+    let pages = if plan.pages.is_empty() {
         vec![text.to_string()]
     } else {
-        pages
+        plan.pages
     };
 
     let page_count = pages.len();
@@ -495,7 +1557,7 @@ fn display_text(text: &str, display: &DisplayOptions) -> ExitCode {
     );
 
     println!("Opening connection to {}...", port_info.name);
-    let mut connection = match open_connection(&port_info) {
+    let mut connection = match display.open_display(&port_info) {
         Ok(c) => c,
         Err(e) => {
             println!("✗ Failed to open connection: {}", e);
@@ -512,10 +1574,15 @@ fn display_text(text: &str, display: &DisplayOptions) -> ExitCode {
                 println!("Displaying page {}/{}...", i + 1, page_count);
             }
 
-            let img = create_text_image_oriented(page, font_size, orientation);
-            let image_data = image_to_rgb565_bytes_oriented(&img, orientation);
-
-            match send_image_to_display_oriented(&mut connection, &image_data, orientation) {
+            let img = create_text_image_themed(
+                font,
+                page,
+                font_size,
+                orientation,
+                theme,
+                display.layout(),
+            );
+            match connection.send_image(&img, orientation, display.colors()) {
                 Ok(()) => {
                     if page_count == 1 && !loop_mode {
                         println!("✓ Image sent successfully!");
@@ -0,0 +1,185 @@
+use image::imageops::{self, FilterType};
+use image::{Rgb, RgbImage};
+use std::fs::File;
+use std::io::{self, Read, Seek, SeekFrom};
+use std::path::Path;
+
+/// Pixel layout of raw frames from another program
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum FrameFormat {
+    /// 3 bytes per pixel: R, G, B
+    #[default]
+    Rgb,
+    /// 4 bytes per pixel: R, G, B, A (composited over black)
+    Rgba,
+}
+
+impl FrameFormat {
+    pub fn bytes_per_pixel(self) -> usize {
+        match self {
+            FrameFormat::Rgb => 3,
+            FrameFormat::Rgba => 4,
+        }
+    }
+}
+
+/// Raw frames of a fixed size, read from a FIFO or pipe (one frame after
+/// another) or from a regular file such as one in `/dev/shm` that the
+/// producer rewrites in place (the latest frame, re-read on every call)
+pub struct FrameSource {
+    file: File,
+    /// Re-read the same frame from the start instead of streaming
+    rewind: bool,
+    pub width: u32,
+    pub height: u32,
+    pub format: FrameFormat,
+}
+
+impl FrameSource {
+    pub fn open(path: &Path, width: u32, height: u32, format: FrameFormat) -> io::Result<Self> {
+        let file = File::open(path)?;
+        let rewind = file.metadata()?.is_file();
+        Ok(FrameSource {
+            file,
+            rewind,
+            width,
+            height,
+            format,
+        })
+    }
+
+    /// Whether frames are polled from a file rather than streamed
+    pub fn is_polled(&self) -> bool {
+        self.rewind
+    }
+
+    /// The next frame, or `None` when a stream has ended or a polled file
+    /// is shorter than a frame (e.g. while being rewritten)
+    pub fn read_frame(&mut self) -> io::Result<Option<RgbImage>> {
+        if self.rewind {
+            self.file.seek(SeekFrom::Start(0))?;
+        }
+        read_frame(&mut self.file, self.width, self.height, self.format)
+    }
+}
+
+/// Read one raw frame from `reader`. `None` if the data runs out first.
+pub fn read_frame(
+    reader: &mut impl Read,
+    width: u32,
+    height: u32,
+    format: FrameFormat,
+) -> io::Result<Option<RgbImage>> {
+    let len = (width as usize)
+        .checked_mul(height as usize)
+        .and_then(|pixels| pixels.checked_mul(format.bytes_per_pixel()))
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "frame size too large"))?;
+    let mut data = vec![0u8; len];
+    match reader.read_exact(&mut data) {
+        Ok(()) => Ok(Some(decode_frame(&data, width, height, format))),
+        Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+/// Convert raw pixel data to an image. `data` must hold a full frame.
+pub fn decode_frame(data: &[u8], width: u32, height: u32, format: FrameFormat) -> RgbImage {
+    let bpp = format.bytes_per_pixel();
+    let mut pixels = data.chunks_exact(bpp);
+    RgbImage::from_fn(width, height, |_, _| {
+        let pixel = pixels.next().unwrap_or(&[0, 0, 0, 0]);
+        match format {
+            FrameFormat::Rgb => Rgb([pixel[0], pixel[1], pixel[2]]),
+            FrameFormat::Rgba => {
+                let alpha = pixel[3] as u16;
+                let blend = |c: u8| ((c as u16 * alpha + 127) / 255) as u8;
+                Rgb([blend(pixel[0]), blend(pixel[1]), blend(pixel[2])])
+            }
+        }
+    })
+}
+
+/// Scale `img` to fit `width`x`height`, keeping its aspect ratio, centered
+/// on black
+pub fn fit_frame(img: &RgbImage, width: u32, height: u32) -> RgbImage {
+    if img.dimensions() == (width, height) {
+        return img.clone();
+    }
+    let scale = (width as f32 / img.width() as f32).min(height as f32 / img.height() as f32);
+    let scaled_width = ((img.width() as f32 * scale).round() as u32).clamp(1, width);
+    let scaled_height = ((img.height() as f32 * scale).round() as u32).clamp(1, height);
+    let scaled = imageops::resize(img, scaled_width, scaled_height, FilterType::Triangle);
+
+    let mut canvas = RgbImage::new(width, height);
+    let x = (width - scaled_width) / 2;
+    let y = (height - scaled_height) / 2;
+    imageops::replace(&mut canvas, &scaled, x as i64, y as i64);
+    canvas
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_read_stream_frames() {
+        let data: Vec<u8> = [[255, 0, 0], [0, 0, 255]]
+            .iter()
+            .flat_map(|color| color.repeat(4))
+            .collect();
+        let mut reader = &data[..];
+        let first = read_frame(&mut reader, 2, 2, FrameFormat::Rgb)
+            .unwrap()
+            .unwrap();
+        assert_eq!(first.get_pixel(1, 1), &Rgb([255, 0, 0]));
+        let second = read_frame(&mut reader, 2, 2, FrameFormat::Rgb)
+            .unwrap()
+            .unwrap();
+        assert_eq!(second.get_pixel(0, 0), &Rgb([0, 0, 255]));
+        assert!(read_frame(&mut reader, 2, 2, FrameFormat::Rgb)
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn test_oversized_frame_is_an_error() {
+        let error = read_frame(&mut &[][..], u32::MAX, u32::MAX, FrameFormat::Rgba).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidInput);
+    }
+
+    #[test]
+    fn test_rgba_composited_over_black() {
+        let img = decode_frame(&[200, 100, 0, 255, 200, 100, 0, 0], 2, 1, FrameFormat::Rgba);
+        assert_eq!(img.get_pixel(0, 0), &Rgb([200, 100, 0]));
+        assert_eq!(img.get_pixel(1, 0), &Rgb([0, 0, 0]));
+    }
+
+    #[test]
+    fn test_fit_frame_letterboxes() {
+        let img = RgbImage::from_pixel(320, 80, Rgb([255, 255, 255]));
+        let fitted = fit_frame(&img, 160, 80);
+        assert_eq!(fitted.dimensions(), (160, 80));
+        // 320x80 scales to 160x40, centered vertically
+        assert_eq!(fitted.get_pixel(80, 10), &Rgb([0, 0, 0]));
+        assert_eq!(fitted.get_pixel(80, 40), &Rgb([255, 255, 255]));
+        assert_eq!(fitted.get_pixel(80, 70), &Rgb([0, 0, 0]));
+    }
+
+    #[test]
+    fn test_polled_file_rereads_latest_frame() {
+        let path = std::env::temp_dir().join(format!("display-fs-mirror-{}", std::process::id()));
+        std::fs::write(&path, [1, 2, 3]).unwrap();
+        let mut source = FrameSource::open(&path, 1, 1, FrameFormat::Rgb).unwrap();
+        assert!(source.is_polled());
+        assert_eq!(
+            source.read_frame().unwrap().unwrap().get_pixel(0, 0),
+            &Rgb([1, 2, 3])
+        );
+        std::fs::write(&path, [4, 5, 6]).unwrap();
+        assert_eq!(
+            source.read_frame().unwrap().unwrap().get_pixel(0, 0),
+            &Rgb([4, 5, 6])
+        );
+        std::fs::remove_file(&path).unwrap();
+    }
+}
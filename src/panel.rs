@@ -0,0 +1,227 @@
+use crate::image::Orientation;
+
+/// Byte order of multi-byte pixels on the wire
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ByteOrder {
+    /// Low byte first (WeAct Display FS)
+    #[default]
+    LittleEndian,
+    /// High byte first (many Turing-style displays)
+    BigEndian,
+}
+
+/// How pixels are encoded in the image data sent to a panel
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PixelFormat {
+    /// 16-bit 5/6/5 color
+    Rgb565(ByteOrder),
+    /// 24-bit color, one byte per channel in R, G, B order
+    Rgb888,
+}
+
+impl Default for PixelFormat {
+    fn default() -> Self {
+        PixelFormat::Rgb565(ByteOrder::LittleEndian)
+    }
+}
+
+impl PixelFormat {
+    pub fn bytes_per_pixel(self) -> usize {
+        match self {
+            PixelFormat::Rgb565(_) => 2,
+            PixelFormat::Rgb888 => 3,
+        }
+    }
+}
+
+impl ByteOrder {
+    /// The two bytes of an RGB565 value in this order
+    pub fn rgb565_bytes(self, rgb565: u16) -> [u8; 2] {
+        match self {
+            ByteOrder::LittleEndian => rgb565.to_le_bytes(),
+            ByteOrder::BigEndian => rgb565.to_be_bytes(),
+        }
+    }
+}
+
+/// Physical layout of a display panel. Width and height are in the panel's
+/// native (unrotated) orientation; landscape images are rotated to fit it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PanelSpec {
+    pub width: u32,
+    pub height: u32,
+    pub pixel_format: PixelFormat,
+}
+
+impl PanelSpec {
+    /// WeAct Studio Display FS V1: 0.96" 80x160 portrait, little-endian RGB565
+    pub const WEACT_FS_096: PanelSpec = PanelSpec {
+        width: 80,
+        height: 160,
+        pixel_format: PixelFormat::Rgb565(ByteOrder::LittleEndian),
+    };
+
+    /// Width of images drawn for this panel in `orientation`
+    pub fn logical_width(&self, orientation: Orientation) -> u32 {
+        match orientation {
+            Orientation::Landscape => self.width.max(self.height),
+            Orientation::Portrait => self.width.min(self.height),
+        }
+    }
+
+    /// Height of images drawn for this panel in `orientation`
+    pub fn logical_height(&self, orientation: Orientation) -> u32 {
+        match orientation {
+            Orientation::Landscape => self.width.min(self.height),
+            Orientation::Portrait => self.width.max(self.height),
+        }
+    }
+
+    /// Size of one full frame of image data in bytes
+    pub fn frame_bytes(&self) -> usize {
+        (self.width * self.height) as usize * self.pixel_format.bytes_per_pixel()
+    }
+
+    /// Size of one physical row of image data in bytes
+    pub fn row_bytes(&self) -> usize {
+        self.width as usize * self.pixel_format.bytes_per_pixel()
+    }
+
+    /// Smallest rectangle holding every pixel that differs between two
+    /// full frames, or `None` if they are the same
+    pub fn dirty_rect(&self, old: &[u8], new: &[u8]) -> Option<Rect> {
+        let bpp = self.pixel_format.bytes_per_pixel();
+        let mut bounds: Option<(usize, usize, usize, usize)> = None;
+        let rows = old
+            .chunks(self.row_bytes())
+            .zip(new.chunks(self.row_bytes()));
+        for (y, (a, b)) in rows.enumerate() {
+            if a == b {
+                continue;
+            }
+            let mut pixels = a.chunks(bpp).zip(b.chunks(bpp));
+            let first = pixels.position(|(p, q)| p != q).unwrap_or(0);
+            let last = a.chunks(bpp).zip(b.chunks(bpp)).rposition(|(p, q)| p != q);
+            let last = last.unwrap_or(first);
+            bounds = Some(match bounds {
+                None => (first, y, last, y),
+                Some((x0, y0, x1, _)) => (x0.min(first), y0, x1.max(last), y),
+            });
+        }
+        bounds.map(|(x0, y0, x1, y1)| {
+            Rect::new(
+                x0 as u32,
+                y0 as u32,
+                (x1 - x0 + 1) as u32,
+                (y1 - y0 + 1) as u32,
+            )
+        })
+    }
+
+    /// The pixel data of `rect` within a full frame
+    pub fn crop(&self, frame: &[u8], rect: Rect) -> Vec<u8> {
+        let bpp = self.pixel_format.bytes_per_pixel();
+        let mut data = Vec::with_capacity((rect.width * rect.height) as usize * bpp);
+        for y in rect.y..rect.y + rect.height {
+            let start = y as usize * self.row_bytes() + rect.x as usize * bpp;
+            data.extend_from_slice(&frame[start..start + rect.width as usize * bpp]);
+        }
+        data
+    }
+
+    /// Copy pixel data for `rect` into a full frame
+    pub fn paste(&self, frame: &mut [u8], rect: Rect, data: &[u8]) {
+        let bpp = self.pixel_format.bytes_per_pixel();
+        let width = rect.width as usize * bpp;
+        for (row, y) in data.chunks(width).zip(rect.y..rect.y + rect.height) {
+            let start = y as usize * self.row_bytes() + rect.x as usize * bpp;
+            frame[start..start + width].copy_from_slice(row);
+        }
+    }
+}
+
+impl Default for PanelSpec {
+    fn default() -> Self {
+        PanelSpec::WEACT_FS_096
+    }
+}
+
+/// A rectangle of panel pixels, in the panel's native orientation
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Rect {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl Rect {
+    pub fn new(x: u32, y: u32, width: u32, height: u32) -> Self {
+        Rect {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+
+    /// The whole of `panel`
+    pub fn full(panel: PanelSpec) -> Self {
+        Rect::new(0, 0, panel.width, panel.height)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.width == 0 || self.height == 0
+    }
+
+    /// Whether the rectangle is non-empty and lies within `panel`
+    pub fn fits(&self, panel: PanelSpec) -> bool {
+        !self.is_empty()
+            && self
+                .x
+                .checked_add(self.width)
+                .is_some_and(|x1| x1 <= panel.width)
+            && self
+                .y
+                .checked_add(self.height)
+                .is_some_and(|y1| y1 <= panel.height)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_panel_is_weact() {
+        let panel = PanelSpec::default();
+        assert_eq!((panel.width, panel.height), (80, 160));
+        assert_eq!(panel.frame_bytes(), 80 * 160 * 2);
+        assert_eq!(panel.logical_width(Orientation::Landscape), 160);
+        assert_eq!(panel.logical_height(Orientation::Portrait), 160);
+    }
+
+    #[test]
+    fn test_dirty_rect() {
+        let panel = PanelSpec::WEACT_FS_096;
+        let old = vec![0; panel.frame_bytes()];
+        assert_eq!(panel.dirty_rect(&old, &old), None);
+
+        let mut new = old.clone();
+        let rect = Rect::new(3, 10, 5, 2);
+        panel.paste(&mut new, rect, &[0xFF; 5 * 2 * 2]);
+        assert_eq!(panel.dirty_rect(&old, &new), Some(rect));
+        assert_eq!(panel.crop(&new, rect), vec![0xFF; 20]);
+
+        // Only one byte of a pixel differs
+        let mut new = old.clone();
+        new[panel.row_bytes() * 4 + 7] = 1;
+        assert_eq!(panel.dirty_rect(&old, &new), Some(Rect::new(3, 4, 1, 1)));
+    }
+
+    #[test]
+    fn test_rgb565_byte_order() {
+        assert_eq!(ByteOrder::LittleEndian.rgb565_bytes(0xF800), [0x00, 0xF8]);
+        assert_eq!(ByteOrder::BigEndian.rgb565_bytes(0xF800), [0xF8, 0x00]);
+    }
+}
@@ -1,15 +1,21 @@
-use serialport::{SerialPort, SerialPortInfo, SerialPortType};
+use crate::driver::{driver_for, DisplayDriver};
+use serde::{Deserialize, Deserializer};
+use serialport::{ClearBuffer, SerialPort, SerialPortInfo, SerialPortType};
+use std::io::{self, Read, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::str::FromStr;
 use std::time::Duration;
 use thiserror::Error;
 
-const BAUD_RATE: u32 = 115200;
-const TIMEOUT_MS: u64 = 1000;
+/// Rate the stock firmware runs at
+pub const DEFAULT_BAUD_RATE: u32 = 115200;
+const DEFAULT_TIMEOUT_MS: u64 = 1000;
 
-const DISPLAY_FS_VID_PID: [(u16, u16); 3] = [
-    (0x1A86, 0x7523), // CH340
-    (0x1A86, 0x5523), // CH341
-    (0x1A86, 0xFE0C), // WeAct Studio Display FS V1
-];
+/// Prefix of device addresses that reach a display through `display-fs bridge`
+pub const TCP_PREFIX: &str = "tcp://";
+
+/// Rates tried when probing, most common first
+pub const PROBE_BAUD_RATES: &[u32] = &[115200, 230400, 460800, 921600, 1_500_000, 2_000_000];
 
 #[derive(Error, Debug)]
 pub enum PortError {
@@ -17,6 +23,8 @@ pub enum PortError {
     NotFound,
     #[error("Failed to open port: {0}")]
     OpenFailed(#[from] serialport::Error),
+    #[error("Failed to connect to {address}: {source}")]
+    ConnectFailed { address: String, source: io::Error },
 }
 
 #[derive(Debug, Clone)]
@@ -26,16 +34,128 @@ pub struct PortInfo {
     pub pid: u16,
 }
 
+/// Serial line speed: a fixed rate, or probed from the device's replies
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BaudRate {
+    Fixed(u32),
+    Auto,
+}
+
+impl Default for BaudRate {
+    fn default() -> Self {
+        BaudRate::Fixed(DEFAULT_BAUD_RATE)
+    }
+}
+
+impl BaudRate {
+    /// The rate to open the port at without probing
+    pub fn rate(self) -> u32 {
+        match self {
+            BaudRate::Fixed(rate) => rate,
+            BaudRate::Auto => DEFAULT_BAUD_RATE,
+        }
+    }
+}
+
+impl FromStr for BaudRate {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.eq_ignore_ascii_case("auto") {
+            return Ok(BaudRate::Auto);
+        }
+        match s.parse() {
+            Ok(rate) if rate > 0 => Ok(BaudRate::Fixed(rate)),
+            _ => Err(format!("invalid baud rate '{}' (a number or 'auto')", s)),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for BaudRate {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Spec {
+            Rate(u32),
+            Name(String),
+        }
+        let spec = match Spec::deserialize(deserializer)? {
+            Spec::Rate(rate) => rate.to_string(),
+            Spec::Name(name) => name,
+        };
+        spec.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+/// Serial flow control
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FlowControl {
+    #[default]
+    None,
+    /// XON/XOFF
+    Software,
+    /// RTS/CTS
+    Hardware,
+}
+
+impl From<FlowControl> for serialport::FlowControl {
+    fn from(flow_control: FlowControl) -> Self {
+        match flow_control {
+            FlowControl::None => serialport::FlowControl::None,
+            FlowControl::Software => serialport::FlowControl::Software,
+            FlowControl::Hardware => serialport::FlowControl::Hardware,
+        }
+    }
+}
+
+/// How the serial port is opened
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SerialSettings {
+    pub baud_rate: BaudRate,
+    pub flow_control: FlowControl,
+    /// How long reads and writes wait before failing
+    pub timeout: Duration,
+}
+
+impl Default for SerialSettings {
+    fn default() -> Self {
+        SerialSettings {
+            baud_rate: BaudRate::default(),
+            flow_control: FlowControl::default(),
+            timeout: Duration::from_millis(DEFAULT_TIMEOUT_MS),
+        }
+    }
+}
+
+impl PortInfo {
+    /// A device given by address: a serial port path, or `tcp://host:port`
+    /// for a display behind a bridge. The VID/PID are unknown (0).
+    pub fn from_address(address: &str) -> Self {
+        PortInfo {
+            name: address.to_string(),
+            vid: 0,
+            pid: 0,
+        }
+    }
+
+    /// `host:port` of a bridged display
+    pub fn tcp_address(&self) -> Option<&str> {
+        self.name.strip_prefix(TCP_PREFIX)
+    }
+}
+
 pub fn list_ports() -> Vec<SerialPortInfo> {
     serialport::available_ports().unwrap_or_default()
 }
 
+/// The first USB serial port whose VID/PID a known driver handles
 pub fn find_display_port() -> Option<PortInfo> {
     for port in list_ports() {
         if let SerialPortType::UsbPort(usb_info) = &port.port_type {
             let vid = usb_info.vid;
             let pid = usb_info.pid;
-            if DISPLAY_FS_VID_PID.contains(&(vid, pid)) {
+            if driver_for(vid, pid).is_some() {
                 return Some(PortInfo {
                     name: port.port_name,
                     vid,
@@ -52,15 +172,164 @@ pub fn is_display_connected() -> bool {
 }
 
 pub fn open_connection(port: &PortInfo) -> Result<Box<dyn SerialPort>, PortError> {
-    let connection = serialport::new(&port.name, BAUD_RATE)
-        .timeout(Duration::from_millis(TIMEOUT_MS))
+    open_connection_with(port, &SerialSettings::default())
+}
+
+/// Open `port` with `settings`. [`BaudRate::Auto`] opens at the default
+/// rate; use [`resolve_baud_rate`] first to probe.
+pub fn open_connection_with(
+    port: &PortInfo,
+    settings: &SerialSettings,
+) -> Result<Box<dyn SerialPort>, PortError> {
+    let connection = serialport::new(&port.name, settings.baud_rate.rate())
+        .flow_control(settings.flow_control.into())
+        .timeout(settings.timeout)
         .open()?;
     Ok(connection)
 }
 
+/// Open `port` as a connection: the serial port, or a TCP connection to a
+/// bridge for `tcp://` addresses
+pub fn open_device(
+    port: &PortInfo,
+    settings: &SerialSettings,
+) -> Result<Box<dyn Connection>, PortError> {
+    match port.tcp_address() {
+        Some(address) => Ok(Box::new(connect_tcp(address, settings.timeout)?)),
+        None => Ok(Box::new(open_connection_with(port, settings)?)),
+    }
+}
+
+fn connect_tcp(address: &str, timeout: Duration) -> Result<TcpStream, PortError> {
+    let failed = |source| PortError::ConnectFailed {
+        address: address.to_string(),
+        source,
+    };
+    let mut last_error = io::Error::new(io::ErrorKind::NotFound, "no addresses");
+    for addr in address.to_socket_addrs().map_err(failed)? {
+        match TcpStream::connect_timeout(&addr, timeout) {
+            Ok(stream) => {
+                stream.set_nodelay(true).map_err(failed)?;
+                stream.set_write_timeout(Some(timeout)).map_err(failed)?;
+                return Ok(stream);
+            }
+            Err(e) => last_error = e,
+        }
+    }
+    Err(failed(last_error))
+}
+
+/// Find the rate the device on `port` answers at, trying each of
+/// [`PROBE_BAUD_RATES`] with a firmware version query. `None` if it
+/// answers at none of them (e.g. firmware without version replies), or
+/// for bridged displays, whose rate is set on the bridge.
+pub fn probe_baud_rate(
+    port: &PortInfo,
+    settings: &SerialSettings,
+    driver: &dyn DisplayDriver,
+) -> Result<Option<u32>, PortError> {
+    if port.tcp_address().is_some() {
+        return Ok(None);
+    }
+    probe_rates(PROBE_BAUD_RATES, driver, |rate| {
+        let settings = SerialSettings {
+            baud_rate: BaudRate::Fixed(rate),
+            ..*settings
+        };
+        Ok(Box::new(open_connection_with(port, &settings)?))
+    })
+}
+
+/// The fixed rate to open `port` at: the configured one, or the probed one
+/// for [`BaudRate::Auto`] (falling back to the default rate)
+pub fn resolve_baud_rate(
+    port: &PortInfo,
+    settings: &SerialSettings,
+    driver: &dyn DisplayDriver,
+) -> Result<u32, PortError> {
+    match settings.baud_rate {
+        BaudRate::Fixed(rate) => Ok(rate),
+        BaudRate::Auto => Ok(probe_baud_rate(port, settings, driver)?.unwrap_or(DEFAULT_BAUD_RATE)),
+    }
+}
+
+fn probe_rates(
+    rates: &[u32],
+    driver: &dyn DisplayDriver,
+    mut open: impl FnMut(u32) -> Result<Box<dyn Connection>, PortError>,
+) -> Result<Option<u32>, PortError> {
+    for &rate in rates {
+        let mut connection = open(rate)?;
+        // A garbled or missing reply just means the rate is wrong
+        if let Ok(info) = driver.query_info(connection.as_mut()) {
+            if info.firmware.is_some() {
+                return Ok(Some(rate));
+            }
+        }
+    }
+    Ok(None)
+}
+
+/// A byte stream to a display. Serial ports are the usual transport; the
+/// protocol and drivers only need to read, write and drop stale input.
+pub trait Connection: Read + Write + Send {
+    /// Discard buffered bytes in both directions
+    fn clear(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+
+    /// Read, waiting at most `timeout` for data, without changing the
+    /// timeout used for other reads and writes
+    fn read_timeout(&mut self, buf: &mut [u8], _timeout: Duration) -> io::Result<usize> {
+        self.read(buf)
+    }
+}
+
+impl Connection for Box<dyn SerialPort> {
+    fn clear(&mut self) -> io::Result<()> {
+        SerialPort::clear(self.as_ref(), ClearBuffer::All).map_err(io::Error::other)
+    }
+
+    fn read_timeout(&mut self, buf: &mut [u8], timeout: Duration) -> io::Result<usize> {
+        let previous = self.timeout();
+        self.set_timeout(timeout)?;
+        let result = self.read(buf);
+        self.set_timeout(previous)?;
+        result
+    }
+}
+
+impl Connection for TcpStream {
+    fn clear(&mut self) -> io::Result<()> {
+        // Drop whatever has arrived so far
+        self.set_nonblocking(true)?;
+        let mut buf = [0u8; 256];
+        let result = loop {
+            match self.read(&mut buf) {
+                Ok(0) => break Ok(()),
+                Ok(_) => {}
+                Err(e) if e.kind() == io::ErrorKind::WouldBlock => break Ok(()),
+                Err(e) => break Err(e),
+            }
+        };
+        self.set_nonblocking(false)?;
+        result
+    }
+
+    fn read_timeout(&mut self, buf: &mut [u8], timeout: Duration) -> io::Result<usize> {
+        let previous = TcpStream::read_timeout(self)?;
+        self.set_read_timeout(Some(timeout.max(Duration::from_millis(1))))?;
+        let result = self.read(buf);
+        self.set_read_timeout(previous)?;
+        result
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::driver::tests::Recorder;
+    use crate::driver::WeActFsV1;
 
     #[test]
     fn test_list_ports_returns_vec() {
@@ -86,6 +355,7 @@ mod tests {
     }
 
     #[test]
+    #[allow(clippy::bool_comparison)]
     fn test_is_display_connected_returns_bool() {
         let result = is_display_connected();
         // Just verify it returns a bool without panicking
@@ -93,11 +363,35 @@ mod tests {
     }
 
     #[test]
-    fn test_vid_pid_constants_defined() {
-        // Verify CH340, CH341, and WeAct VID/PIDs are defined
-        assert!(DISPLAY_FS_VID_PID.contains(&(0x1A86, 0x7523))); // CH340
-        assert!(DISPLAY_FS_VID_PID.contains(&(0x1A86, 0x5523))); // CH341
-        assert!(DISPLAY_FS_VID_PID.contains(&(0x1A86, 0xFE0C))); // WeAct
+    fn test_parse_baud_rate() {
+        assert_eq!("921600".parse(), Ok(BaudRate::Fixed(921600)));
+        assert_eq!("AUTO".parse(), Ok(BaudRate::Auto));
+        assert!("0".parse::<BaudRate>().is_err());
+        assert!("fast".parse::<BaudRate>().is_err());
+    }
+
+    #[test]
+    fn test_probe_finds_answering_rate() {
+        let mut tried = Vec::new();
+        let rate = probe_rates(&[115200, 460800, 921600], &WeActFsV1, |rate| {
+            tried.push(rate);
+            // Only answers at 460800
+            let replies: &[u8] = if rate == 460800 { b"\xC2v2\n" } else { b"" };
+            Ok(Box::new(Recorder::with_replies(replies)))
+        })
+        .unwrap();
+        assert_eq!(rate, Some(460800));
+        assert_eq!(tried, vec![115200, 460800]);
+
+        let rate = probe_rates(&[115200], &WeActFsV1, |_| Ok(Box::new(Recorder::default())));
+        assert_eq!(rate.unwrap(), None);
+    }
+
+    #[test]
+    fn test_tcp_address() {
+        let port = PortInfo::from_address("tcp://rack-pi:7777");
+        assert_eq!(port.tcp_address(), Some("rack-pi:7777"));
+        assert_eq!(PortInfo::from_address("/dev/ttyUSB0").tcp_address(), None);
     }
 
     #[test]
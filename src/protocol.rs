@@ -1,98 +1,543 @@
 use crate::image::Orientation;
-use serialport::SerialPort;
-use std::io::Write;
+use crate::panel::{PanelSpec, Rect};
+use crate::port::Connection;
+use std::io::ErrorKind;
 use std::thread::sleep;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 use thiserror::Error;
 
 const CMD_SET_ORIENTATION: u8 = 0x02;
+const CMD_SET_BRIGHTNESS: u8 = 0x03;
 const CMD_SET_BITMAP: u8 = 0x05;
+const CMD_SYSTEM_VERSION: u8 = 0x42;
 const CMD_END: u8 = 0x0A;
 
+/// Set on a command byte to read instead of write; replies start with the
+/// command byte with this bit set and end with `CMD_END`
+const CMD_READ: u8 = 0x80;
+
+/// Longest reply we wait for before treating the byte stream as garbage
+const MAX_REPLY_LEN: usize = 64;
+
+/// Time the firmware needs to apply an orientation change
+const ORIENTATION_DELAY: Duration = Duration::from_millis(50);
+/// Time the firmware needs to draw a bitmap before the next command
+const BITMAP_DELAY: Duration = Duration::from_millis(100);
+/// How long to wait for a reply to a query
+const QUERY_TIMEOUT: Duration = Duration::from_millis(300);
+
 #[derive(Error, Debug)]
 pub enum ProtocolError {
     #[error("Failed to send data: {0}")]
     SendFailed(#[from] std::io::Error),
+    #[error("Window {rect:?} does not fit the {width}x{height} panel")]
+    WindowOutOfBounds { rect: Rect, width: u32, height: u32 },
+    #[error("Window {rect:?} needs {expected} bytes of pixel data, got {actual}")]
+    PayloadLength {
+        rect: Rect,
+        expected: usize,
+        actual: usize,
+    },
+    #[error("Malformed command: {0}")]
+    Malformed(String),
+}
+
+/// A command to the display, as sent on the wire
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Command {
+    SetOrientation(Orientation),
+    /// Backlight level 0-255, faded over `fade_ms` milliseconds
+    SetBrightness {
+        level: u8,
+        fade_ms: u16,
+    },
+    /// Start drawing into a window; its pixel data follows the command
+    SetBitmap(Rect),
+    QueryVersion,
+}
+
+impl Command {
+    /// Encode for the wire. Only a `SetBitmap` whose window doesn't fit
+    /// the protocol's 16-bit coordinates can fail.
+    pub fn encode(&self) -> Result<Vec<u8>, ProtocolError> {
+        let bytes = match *self {
+            Command::SetOrientation(orientation) => {
+                // Orientation values: 0=portrait, 1=landscape, 2=portrait_flip, 3=landscape_flip
+                let value = match orientation {
+                    Orientation::Portrait => 0,
+                    Orientation::Landscape => 1,
+                };
+                vec![CMD_SET_ORIENTATION, value, CMD_END]
+            }
+            Command::SetBrightness { level, fade_ms } => {
+                let [lo, hi] = fade_ms.to_le_bytes();
+                vec![CMD_SET_BRIGHTNESS, level, lo, hi, CMD_END]
+            }
+            Command::SetBitmap(rect) => {
+                let out_of_range = || ProtocolError::WindowOutOfBounds {
+                    rect,
+                    width: 1 << 16,
+                    height: 1 << 16,
+                };
+                let last = |start: u32, len: u32| {
+                    start
+                        .checked_add(len)
+                        .map(|end| end.saturating_sub(1))
+                        .ok_or_else(out_of_range)
+                };
+                let (x1, y1) = (last(rect.x, rect.width)?, last(rect.y, rect.height)?);
+                let mut bytes = vec![CMD_SET_BITMAP];
+                for value in [rect.x, rect.y, x1, y1] {
+                    let value = u16::try_from(value).map_err(|_| out_of_range())?;
+                    bytes.extend_from_slice(&value.to_le_bytes());
+                }
+                bytes.push(CMD_END);
+                bytes
+            }
+            Command::QueryVersion => vec![CMD_SYSTEM_VERSION | CMD_READ, CMD_END],
+        };
+        Ok(bytes)
+    }
+
+    /// Parse the command at the start of `bytes`, returning it and its
+    /// encoded length. A `SetBitmap`'s pixel data is not included.
+    pub fn decode(bytes: &[u8]) -> Result<(Command, usize), ProtocolError> {
+        let malformed = |message: &str| ProtocolError::Malformed(message.to_string());
+        let (&cmd, _) = bytes.split_first().ok_or_else(|| malformed("empty"))?;
+        let len = match cmd {
+            CMD_SET_ORIENTATION => 3,
+            CMD_SET_BRIGHTNESS => 5,
+            CMD_SET_BITMAP => 10,
+            c if c == CMD_SYSTEM_VERSION | CMD_READ => 2,
+            other => {
+                return Err(ProtocolError::Malformed(format!(
+                    "unknown command 0x{:02X}",
+                    other
+                )))
+            }
+        };
+        if bytes.len() < len {
+            return Err(malformed("truncated"));
+        }
+        if bytes[len - 1] != CMD_END {
+            return Err(malformed("missing end byte"));
+        }
+        let u16_at = |i: usize| u16::from_le_bytes([bytes[i], bytes[i + 1]]) as u32;
+
+        let command = match cmd {
+            CMD_SET_ORIENTATION => Command::SetOrientation(match bytes[1] {
+                0 => Orientation::Portrait,
+                1 => Orientation::Landscape,
+                other => {
+                    return Err(ProtocolError::Malformed(format!(
+                        "unsupported orientation {}",
+                        other
+                    )))
+                }
+            }),
+            CMD_SET_BRIGHTNESS => Command::SetBrightness {
+                level: bytes[1],
+                fade_ms: u16_at(2) as u16,
+            },
+            CMD_SET_BITMAP => {
+                let (x0, y0, x1, y1) = (u16_at(1), u16_at(3), u16_at(5), u16_at(7));
+                if x1 < x0 || y1 < y0 {
+                    return Err(malformed("window ends before it starts"));
+                }
+                Command::SetBitmap(Rect::new(x0, y0, x1 - x0 + 1, y1 - y0 + 1))
+            }
+            _ => Command::QueryVersion,
+        };
+        Ok((command, len))
+    }
+
+    /// Check the command makes sense for `panel`
+    pub fn validate(&self, panel: PanelSpec) -> Result<(), ProtocolError> {
+        match *self {
+            Command::SetBitmap(rect) if !rect.fits(panel) => {
+                Err(ProtocolError::WindowOutOfBounds {
+                    rect,
+                    width: panel.width,
+                    height: panel.height,
+                })
+            }
+            _ => Ok(()),
+        }
+    }
 }
 
 pub fn create_bitmap_header() -> [u8; 10] {
     create_bitmap_header_oriented(Orientation::default())
 }
 
-/// Physical display dimensions (always 80x160 portrait)
-const PHYSICAL_WIDTH: u16 = 80;
-const PHYSICAL_HEIGHT: u16 = 160;
-
 pub fn create_bitmap_header_oriented(_orientation: Orientation) -> [u8; 10] {
     // Always use physical dimensions - rotation is handled in image data
-    let x0: u16 = 0;
-    let y0: u16 = 0;
-    let x1: u16 = PHYSICAL_WIDTH - 1;
-    let y1: u16 = PHYSICAL_HEIGHT - 1;
-
-    [
-        CMD_SET_BITMAP,
-        (x0 & 0xFF) as u8,
-        (x0 >> 8) as u8,
-        (y0 & 0xFF) as u8,
-        (y0 >> 8) as u8,
-        (x1 & 0xFF) as u8,
-        (x1 >> 8) as u8,
-        (y1 & 0xFF) as u8,
-        (y1 >> 8) as u8,
-        CMD_END,
-    ]
+    create_bitmap_header_for(PanelSpec::default()).expect("the default panel fits 16 bits")
+}
+
+/// Bitmap header covering the whole of `panel`
+pub fn create_bitmap_header_for(panel: PanelSpec) -> Result<[u8; 10], ProtocolError> {
+    create_bitmap_header_window(Rect::full(panel))
+}
+
+/// Bitmap header for a window of the panel; the pixel data that follows
+/// fills it row by row
+pub fn create_bitmap_header_window(rect: Rect) -> Result<[u8; 10], ProtocolError> {
+    let mut header = [0; 10];
+    header.copy_from_slice(&Command::SetBitmap(rect).encode()?);
+    Ok(header)
 }
 
 pub fn send_image_to_display(
-    port: &mut Box<dyn SerialPort>,
+    port: &mut dyn Connection,
     image_data: &[u8],
 ) -> Result<(), ProtocolError> {
     send_image_to_display_oriented(port, image_data, Orientation::default())
 }
 
-/// Create orientation command to initialize display orientation
-fn create_orientation_command(orientation: Orientation) -> [u8; 3] {
-    // Orientation values: 0=portrait, 1=landscape, 2=portrait_flip, 3=landscape_flip
-    let orientation_value = match orientation {
-        Orientation::Portrait => 0,
-        Orientation::Landscape => 1,
-    };
-    [CMD_SET_ORIENTATION, orientation_value, CMD_END]
+/// One step of talking to the display. Operations are built as a list of
+/// steps so the blocking and async senders share the same encoding.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Step {
+    /// Bytes to write
+    Write(Vec<u8>),
+    /// Flush, then give the firmware `delay` to finish `command`. An
+    /// acknowledgement of `command` ends the wait early.
+    Settle { command: u8, delay: Duration },
 }
 
-pub fn send_image_to_display_oriented(
-    port: &mut Box<dyn SerialPort>,
+/// Encode a command that has no window, which can't fail
+fn encode_infallible(command: Command) -> Vec<u8> {
+    command
+        .encode()
+        .expect("only SetBitmap windows can be out of range")
+}
+
+/// Steps to set the display orientation and wait for the firmware to apply it
+pub fn orientation_steps(orientation: Orientation) -> Vec<Step> {
+    vec![
+        Step::Write(encode_infallible(Command::SetOrientation(orientation))),
+        Step::Settle {
+            command: CMD_SET_ORIENTATION,
+            delay: ORIENTATION_DELAY,
+        },
+    ]
+}
+
+/// Steps to set backlight brightness (0 = off, 255 = full) without fading
+pub fn brightness_steps(level: u8) -> Vec<Step> {
+    vec![Step::Write(encode_infallible(Command::SetBrightness {
+        level,
+        fade_ms: 0,
+    }))]
+}
+
+/// Steps to draw pixel data for `panel` into a window of it. Fails if the
+/// window is outside the panel or the data doesn't fill it.
+pub fn bitmap_steps(
+    panel: PanelSpec,
+    rect: Rect,
     image_data: &[u8],
+) -> Result<Vec<Step>, ProtocolError> {
+    let command = Command::SetBitmap(rect);
+    command.validate(panel)?;
+    let expected = (rect.width * rect.height) as usize * panel.pixel_format.bytes_per_pixel();
+    if image_data.len() != expected {
+        return Err(ProtocolError::PayloadLength {
+            rect,
+            expected,
+            actual: image_data.len(),
+        });
+    }
+
+    let mut steps = vec![Step::Write(command.encode()?)];
+    steps.extend(
+        image_data
+            .chunks(chunk_size(panel))
+            .map(|chunk| Step::Write(chunk.to_vec())),
+    );
+    steps.push(Step::Settle {
+        command: CMD_SET_BITMAP,
+        delay: BITMAP_DELAY,
+    });
+    Ok(steps)
+}
+
+/// Run steps on a blocking connection
+pub fn run_steps(port: &mut dyn Connection, steps: &[Step]) -> Result<(), ProtocolError> {
+    for step in steps {
+        match step {
+            Step::Write(bytes) => port.write_all(bytes)?,
+            Step::Settle { command, delay } => {
+                port.flush()?;
+                wait_for_ack(port, *command, *delay)?;
+            }
+        }
+    }
+    port.flush()?;
+    Ok(())
+}
+
+/// Set backlight brightness (0 = off, 255 = full) without fading
+pub fn set_brightness(port: &mut dyn Connection, level: u8) -> Result<(), ProtocolError> {
+    run_steps(port, &brightness_steps(level))
+}
+
+/// Set the display orientation and wait for the firmware to apply it
+pub fn set_orientation(
+    port: &mut dyn Connection,
     orientation: Orientation,
 ) -> Result<(), ProtocolError> {
-    port.clear(serialport::ClearBuffer::All)
-        .map_err(|e| ProtocolError::SendFailed(std::io::Error::other(e)))?;
+    run_steps(port, &orientation_steps(orientation))
+}
 
-    // Send orientation command first
-    let orient_cmd = create_orientation_command(orientation);
-    port.write_all(&orient_cmd)?;
+/// Ask the firmware for its version. `None` if it doesn't reply.
+pub fn query_version(port: &mut dyn Connection) -> Result<Option<String>, ProtocolError> {
+    port.write_all(&Command::QueryVersion.encode()?)?;
     port.flush()?;
-    sleep(Duration::from_millis(50));
+    let reply = read_reply(port, CMD_SYSTEM_VERSION, Instant::now() + QUERY_TIMEOUT)?;
+    Ok(reply.and_then(|payload| parse_version(&payload)))
+}
 
-    let header = create_bitmap_header_oriented(orientation);
-    port.write_all(&header)?;
-    port.flush()?;
+/// A version reply is either text ("1.0.3") or one byte per component
+fn parse_version(payload: &[u8]) -> Option<String> {
+    if payload.is_empty() {
+        return None;
+    }
+    if payload.iter().all(|b| b.is_ascii_graphic() || *b == b' ') {
+        return Some(String::from_utf8_lossy(payload).trim().to_string());
+    }
+    let parts: Vec<String> = payload.iter().map(|b| b.to_string()).collect();
+    Some(parts.join("."))
+}
+
+/// Picks the reply to one command out of a byte stream, skipping anything
+/// before it
+#[derive(Debug, Clone)]
+pub struct ReplyParser {
+    command: u8,
+    payload: Option<Vec<u8>>,
+}
 
-    let chunk_size = PHYSICAL_WIDTH as usize * 4;
-    for chunk in image_data.chunks(chunk_size) {
-        port.write_all(chunk)?;
+impl ReplyParser {
+    pub fn new(command: u8) -> Self {
+        ReplyParser {
+            command,
+            payload: None,
+        }
     }
 
-    port.flush()?;
-    sleep(Duration::from_millis(100));
+    /// Feed one byte; returns the reply's payload once it is complete
+    pub fn push(&mut self, byte: u8) -> Option<Vec<u8>> {
+        match (&mut self.payload, byte) {
+            (None, b) if b == self.command | CMD_READ => self.payload = Some(Vec::new()),
+            (None, _) => {}
+            (Some(_), CMD_END) => return self.payload.take(),
+            (Some(bytes), _) if bytes.len() >= MAX_REPLY_LEN => self.payload = None,
+            (Some(bytes), b) => bytes.push(b),
+        }
+        None
+    }
+}
+
+/// Read the reply to `cmd`, skipping anything before it. Returns the bytes
+/// between the reply's command byte and `CMD_END`, or `None` if no complete
+/// reply arrives by `deadline`.
+pub fn read_reply(
+    port: &mut dyn Connection,
+    cmd: u8,
+    deadline: Instant,
+) -> Result<Option<Vec<u8>>, ProtocolError> {
+    let mut parser = ReplyParser::new(cmd);
+    let mut byte = [0u8; 1];
+
+    loop {
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        if remaining.is_zero() {
+            return Ok(None);
+        }
+        match port.read_timeout(&mut byte, remaining) {
+            Ok(0) => return Ok(None),
+            Ok(_) => {}
+            Err(e)
+                if matches!(
+                    e.kind(),
+                    ErrorKind::TimedOut | ErrorKind::WouldBlock | ErrorKind::Interrupted
+                ) =>
+            {
+                continue
+            }
+            Err(e) => return Err(e.into()),
+        }
+
+        if let Some(payload) = parser.push(byte[0]) {
+            return Ok(Some(payload));
+        }
+    }
+}
 
+/// Wait up to `delay` for the firmware to acknowledge `cmd`. Firmware that
+/// acknowledges lets us continue as soon as it is ready; otherwise the full
+/// delay passes, as a fixed sleep would.
+fn wait_for_ack(port: &mut dyn Connection, cmd: u8, delay: Duration) -> Result<(), ProtocolError> {
+    let deadline = Instant::now() + delay;
+    if read_reply(port, cmd, deadline)?.is_none() {
+        sleep(deadline.saturating_duration_since(Instant::now()));
+    }
     Ok(())
 }
 
+/// Draw pixel data for `panel` into a window of it. Fails without sending
+/// anything if the window is outside the panel or the data doesn't fill it.
+pub fn send_bitmap(
+    port: &mut dyn Connection,
+    panel: PanelSpec,
+    rect: Rect,
+    image_data: &[u8],
+) -> Result<(), ProtocolError> {
+    run_steps(port, &bitmap_steps(panel, rect, image_data)?)
+}
+
+pub fn send_image_to_display_oriented(
+    port: &mut dyn Connection,
+    image_data: &[u8],
+    orientation: Orientation,
+) -> Result<(), ProtocolError> {
+    send_image_to_panel(port, image_data, orientation, PanelSpec::default())
+}
+
+/// Send image data already converted for `panel` (see
+/// [`crate::image::image_to_panel_bytes`])
+pub fn send_image_to_panel(
+    port: &mut dyn Connection,
+    image_data: &[u8],
+    orientation: Orientation,
+    panel: PanelSpec,
+) -> Result<(), ProtocolError> {
+    port.clear()?;
+
+    // Send orientation command first
+    set_orientation(port, orientation)?;
+    send_bitmap(port, panel, Rect::full(panel), image_data)
+}
+
+/// Bytes written per chunk: two rows of the panel
+fn chunk_size(panel: PanelSpec) -> usize {
+    panel.row_bytes() * 2
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::driver::tests::Recorder;
+    use crate::panel::PixelFormat;
+    use proptest::prelude::*;
+
+    fn command() -> impl Strategy<Value = Command> {
+        let rect = (0u32..0xFFFF, 0u32..0xFFFF, 1u32..=0x10000, 1u32..=0x10000).prop_map(
+            |(x, y, width, height)| {
+                // Keep the last column and row addressable in 16 bits
+                Rect::new(x, y, width.min(0x10000 - x), height.min(0x10000 - y))
+            },
+        );
+        prop_oneof![
+            prop_oneof![Just(Orientation::Portrait), Just(Orientation::Landscape)]
+                .prop_map(Command::SetOrientation),
+            (any::<u8>(), any::<u16>())
+                .prop_map(|(level, fade_ms)| Command::SetBrightness { level, fade_ms }),
+            rect.prop_map(Command::SetBitmap),
+            Just(Command::QueryVersion),
+        ]
+    }
+
+    proptest! {
+        #[test]
+        fn prop_commands_round_trip(command in command()) {
+            let bytes = command.encode().unwrap();
+            prop_assert_eq!(Command::decode(&bytes).unwrap(), (command, bytes.len()));
+        }
+
+        #[test]
+        fn prop_command_streams_decode_in_order(commands in prop::collection::vec(command(), 1..8)) {
+            let bytes: Vec<u8> = commands.iter().flat_map(|c| c.encode().unwrap()).collect();
+            let mut rest = &bytes[..];
+            for command in commands {
+                let (decoded, len) = Command::decode(rest).unwrap();
+                prop_assert_eq!(decoded, command);
+                rest = &rest[len..];
+            }
+            prop_assert!(rest.is_empty());
+        }
+
+        #[test]
+        fn prop_out_of_range_windows_are_rejected(
+            x in 0u32..=u32::MAX,
+            width in 1u32..=u32::MAX,
+            y in 0u32..0x100,
+            height in 1u32..0x100,
+        ) {
+            let rect = Rect::new(x, y, width, height);
+            let fits = x as u64 + width as u64 <= 0x10000;
+            prop_assert_eq!(Command::SetBitmap(rect).encode().is_ok(), fits);
+        }
+
+        #[test]
+        fn prop_decode_never_panics(bytes in prop::collection::vec(any::<u8>(), 0..16)) {
+            let _ = Command::decode(&bytes);
+        }
+    }
+
+    #[test]
+    fn test_decode_rejects_malformed() {
+        for bytes in [
+            &[][..],
+            &[0x7F, 0x0A],
+            &[CMD_SET_ORIENTATION, 1],
+            &[CMD_SET_ORIENTATION, 1, 0x00],
+            &[CMD_SET_ORIENTATION, 7, CMD_END],
+            // x1 < x0
+            &[CMD_SET_BITMAP, 5, 0, 0, 0, 4, 0, 0, 0, CMD_END],
+        ] {
+            assert!(
+                matches!(Command::decode(bytes), Err(ProtocolError::Malformed(_))),
+                "{:?}",
+                bytes
+            );
+        }
+    }
+
+    #[test]
+    fn test_send_bitmap_validates_before_sending() {
+        let panel = PanelSpec::default();
+        let mut port = Recorder::default();
+
+        let outside = Rect::new(70, 0, 20, 10);
+        let result = send_bitmap(&mut port, panel, outside, &[0; 20 * 10 * 2]);
+        assert!(matches!(
+            result,
+            Err(ProtocolError::WindowOutOfBounds { .. })
+        ));
+
+        let empty = Rect::new(0, 0, 0, 10);
+        let result = send_bitmap(&mut port, panel, empty, &[]);
+        assert!(matches!(
+            result,
+            Err(ProtocolError::WindowOutOfBounds { .. })
+        ));
+
+        let rect = Rect::new(0, 0, 10, 10);
+        let result = send_bitmap(&mut port, panel, rect, &[0; 10]);
+        assert!(matches!(
+            result,
+            Err(ProtocolError::PayloadLength {
+                expected: 200,
+                actual: 10,
+                ..
+            })
+        ));
+
+        assert!(port.written().is_empty());
+    }
 
     #[test]
     fn test_bitmap_header_structure() {
@@ -129,9 +574,94 @@ mod tests {
         assert_eq!(CMD_END, 0x0A);
     }
 
+    #[test]
+    fn test_brightness_command_structure() {
+        let cmd = Command::SetBrightness {
+            level: 0x80,
+            fade_ms: 500,
+        }
+        .encode()
+        .unwrap();
+        assert_eq!(cmd, [CMD_SET_BRIGHTNESS, 0x80, 0xF4, 0x01, CMD_END]);
+    }
+
     #[test]
     fn test_chunk_size_physical() {
         // Always uses physical width: 80 * 4 = 320
-        assert_eq!(PHYSICAL_WIDTH as usize * 4, 320);
+        assert_eq!(chunk_size(PanelSpec::default()), 320);
+    }
+
+    #[test]
+    fn test_bitmap_header_for_larger_panel() {
+        let panel = PanelSpec {
+            width: 320,
+            height: 480,
+            pixel_format: PixelFormat::Rgb888,
+        };
+        let header = create_bitmap_header_for(panel).unwrap();
+        // x1 = 319 = 0x013F, y1 = 479 = 0x01DF
+        assert_eq!(&header[5..9], &[0x3F, 0x01, 0xDF, 0x01]);
+        assert_eq!(chunk_size(panel), 320 * 3 * 2);
+    }
+
+    #[test]
+    fn test_read_reply_skips_noise() {
+        let mut port = Recorder::with_replies(&[0x00, 0x82, 0x0A, 0xC2, 1, 0, 3, 0x0A]);
+        let deadline = Instant::now() + QUERY_TIMEOUT;
+        let reply = read_reply(&mut port, CMD_SYSTEM_VERSION, deadline).unwrap();
+        assert_eq!(reply, Some(vec![1, 0, 3]));
+        // Nothing left to read
+        let reply = read_reply(&mut port, CMD_SYSTEM_VERSION, deadline).unwrap();
+        assert_eq!(reply, None);
+    }
+
+    #[test]
+    fn test_parse_version() {
+        assert_eq!(parse_version(b"1.0.3 "), Some("1.0.3".to_string()));
+        assert_eq!(parse_version(&[1, 0, 3]), Some("1.0.3".to_string()));
+        assert_eq!(parse_version(&[]), None);
+    }
+
+    #[test]
+    fn test_bitmap_header_window() {
+        let header = create_bitmap_header_window(Rect::new(10, 300, 20, 2)).unwrap();
+        // x0 = 10, y0 = 300 = 0x012C, x1 = 29, y1 = 301 = 0x012D
+        assert_eq!(
+            header,
+            [
+                CMD_SET_BITMAP,
+                10,
+                0,
+                0x2C,
+                0x01,
+                29,
+                0,
+                0x2D,
+                0x01,
+                CMD_END
+            ]
+        );
+    }
+
+    #[test]
+    fn test_encode_rejects_out_of_range_window() {
+        for rect in [
+            Rect::new(u32::MAX, 0, 1, 1),
+            Rect::new(0, 1, 10, u32::MAX),
+            Rect::new(0xFFFF, 0, 2, 1),
+            Rect::new(0x10000, 0, 0, 1),
+        ] {
+            assert!(
+                matches!(
+                    Command::SetBitmap(rect).encode(),
+                    Err(ProtocolError::WindowOutOfBounds { .. })
+                ),
+                "{:?}",
+                rect
+            );
+        }
+        assert!(Command::SetBitmap(Rect::new(0xFFFF, 0, 1, 1))
+            .encode()
+            .is_ok());
     }
 }
@@ -0,0 +1,276 @@
+use image::RgbImage;
+
+/// 4x4 Bayer threshold matrix (values 0-15)
+const BAYER_4X4: [[u8; 4]; 4] = [[0, 8, 2, 10], [12, 4, 14, 6], [3, 11, 1, 9], [15, 7, 13, 5]];
+
+/// How 8-bit color channels are reduced to RGB565's 5/6/5 bits
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Quantizer {
+    /// Drop the low bits (fast, slightly darkens and bands gradients)
+    #[default]
+    Truncate,
+    /// Round to the nearest level
+    Round,
+    /// Ordered 4x4 Bayer dithering: a fixed pattern, stable between frames
+    Bayer,
+    /// Floyd–Steinberg error diffusion: smoothest gradients and photos
+    FloydSteinberg,
+}
+
+/// Color conversion settings for sending images to the panel
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ColorOptions {
+    pub quantizer: Quantizer,
+    /// Gamma applied before quantizing: values above 1.0 darken midtones,
+    /// below 1.0 lighten them (1.0 leaves colors unchanged)
+    pub gamma: f32,
+    /// Per-channel gain (red, green, blue) to correct the panel's white point
+    pub white_balance: [f32; 3],
+}
+
+impl Default for ColorOptions {
+    fn default() -> Self {
+        ColorOptions {
+            quantizer: Quantizer::default(),
+            gamma: 1.0,
+            white_balance: [1.0; 3],
+        }
+    }
+}
+
+/// Levels per channel in RGB565: 5 bits red, 6 green, 5 blue
+const LEVELS: [u16; 3] = [31, 63, 31];
+
+impl ColorOptions {
+    fn is_identity(&self) -> bool {
+        self.gamma == 1.0 && self.white_balance == [1.0; 3]
+    }
+
+    /// Lookup tables mapping each 8-bit value to its corrected value per channel
+    fn correction_tables(&self) -> [[f32; 256]; 3] {
+        let mut tables = [[0.0; 256]; 3];
+        for (table, gain) in tables.iter_mut().zip(self.white_balance) {
+            for (value, corrected) in table.iter_mut().enumerate() {
+                let linear = (value as f32 / 255.0).powf(self.gamma) * gain;
+                *corrected = (linear * 255.0).clamp(0.0, 255.0);
+            }
+        }
+        tables
+    }
+}
+
+/// Quantize an image to RGB565 values, row by row
+pub fn quantize_rgb565(img: &RgbImage, options: ColorOptions) -> Vec<u16> {
+    let width = img.width() as usize;
+    let tables = (!options.is_identity()).then(|| options.correction_tables());
+    let mut channels: Vec<[f32; 3]> = img
+        .pixels()
+        .map(|pixel| {
+            let mut values = pixel.0.map(f32::from);
+            if let Some(tables) = &tables {
+                for ((value, table), raw) in values.iter_mut().zip(tables).zip(pixel.0) {
+                    *value = table[raw as usize];
+                }
+            }
+            values
+        })
+        .collect();
+
+    let mut out = Vec::with_capacity(channels.len());
+    for index in 0..channels.len() {
+        let (x, y) = (index % width, index / width);
+        let mut levels = [0u16; 3];
+        for channel in 0..3 {
+            let value = channels[index][channel];
+            let max = LEVELS[channel];
+            let level = match options.quantizer {
+                Quantizer::Truncate => {
+                    let bits = if max == 63 { 2 } else { 3 };
+                    (value.round() as u16) >> bits
+                }
+                Quantizer::Round => to_level(value, max),
+                Quantizer::Bayer => {
+                    // Offset by up to half a level step either way
+                    let step = 255.0 / max as f32;
+                    let threshold = (BAYER_4X4[y % 4][x % 4] as f32 + 0.5) / 16.0 - 0.5;
+                    to_level(value + threshold * step, max)
+                }
+                Quantizer::FloydSteinberg => {
+                    let level = to_level(value, max);
+                    let error = value - from_level(level, max);
+                    diffuse(&mut channels, width, x, y, channel, error);
+                    level
+                }
+            };
+            levels[channel] = level;
+        }
+        out.push((levels[0] << 11) | (levels[1] << 5) | levels[2]);
+    }
+    out
+}
+
+/// Apply gamma and white balance without reducing the color depth, for
+/// panels that take 24-bit color
+pub fn correct_colors(img: &RgbImage, options: ColorOptions) -> RgbImage {
+    if options.is_identity() {
+        return img.clone();
+    }
+    let tables = options.correction_tables();
+    let mut out = img.clone();
+    for pixel in out.pixels_mut() {
+        for (value, table) in pixel.0.iter_mut().zip(&tables) {
+            *value = table[*value as usize].round() as u8;
+        }
+    }
+    out
+}
+
+/// Nearest level (0..=max) to an 8-bit value
+fn to_level(value: f32, max: u16) -> u16 {
+    (value.clamp(0.0, 255.0) * max as f32 / 255.0).round() as u16
+}
+
+/// The 8-bit value a level displays as
+fn from_level(level: u16, max: u16) -> f32 {
+    level as f32 * 255.0 / max as f32
+}
+
+/// Spread a pixel's quantization error over its unvisited neighbours
+fn diffuse(
+    channels: &mut [[f32; 3]],
+    width: usize,
+    x: usize,
+    y: usize,
+    channel: usize,
+    error: f32,
+) {
+    let height = channels.len() / width;
+    let neighbours: [(isize, usize, f32); 4] = [
+        (1, 0, 7.0 / 16.0),
+        (-1, 1, 3.0 / 16.0),
+        (0, 1, 5.0 / 16.0),
+        (1, 1, 1.0 / 16.0),
+    ];
+    for (dx, dy, weight) in neighbours {
+        let nx = x as isize + dx;
+        let ny = y + dy;
+        if nx < 0 || nx as usize >= width || ny >= height {
+            continue;
+        }
+        channels[ny * width + nx as usize][channel] += error * weight;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::Rgb;
+
+    fn options(quantizer: Quantizer) -> ColorOptions {
+        ColorOptions {
+            quantizer,
+            ..ColorOptions::default()
+        }
+    }
+
+    fn red_level(value: u16) -> u16 {
+        value >> 11
+    }
+
+    #[test]
+    fn test_truncate_matches_bit_shift() {
+        let img = RgbImage::from_pixel(1, 1, Rgb([200, 100, 50]));
+        let value = quantize_rgb565(&img, ColorOptions::default())[0];
+        assert_eq!(value, ((200 >> 3) << 11) | ((100 >> 2) << 5) | (50 >> 3));
+    }
+
+    #[test]
+    fn test_round_picks_nearest_level() {
+        // Level 1 is 8.2, so 6 is closer to it than to 0
+        let img = RgbImage::from_pixel(1, 1, Rgb([6, 0, 0]));
+        assert_eq!(
+            red_level(quantize_rgb565(&img, options(Quantizer::Truncate))[0]),
+            0
+        );
+        assert_eq!(
+            red_level(quantize_rgb565(&img, options(Quantizer::Round))[0]),
+            1
+        );
+    }
+
+    #[test]
+    fn test_floyd_steinberg_diffuses_error_right() {
+        // Red 4 is just under half a level (8.2), so the first pixel rounds
+        // down to 0 and passes 7/16 of its error to the right
+        let img = RgbImage::from_pixel(2, 1, Rgb([4, 0, 0]));
+        let out = quantize_rgb565(&img, options(Quantizer::FloydSteinberg));
+        assert_eq!(red_level(out[0]), 0);
+        // 4 + 4 * 7/16 = 5.75 rounds to level 1 (8.2)
+        assert_eq!(red_level(out[1]), 1);
+    }
+
+    #[test]
+    fn test_floyd_steinberg_preserves_average() {
+        // A flat color between two levels averages out to the original value
+        let img = RgbImage::from_pixel(32, 32, Rgb([100, 100, 100]));
+        let out = quantize_rgb565(&img, options(Quantizer::FloydSteinberg));
+        let mean = out
+            .iter()
+            .map(|&value| from_level(red_level(value), 31))
+            .sum::<f32>()
+            / out.len() as f32;
+        assert!((mean - 100.0).abs() < 1.0, "mean {}", mean);
+        assert!(
+            out.iter().any(|&v| v != out[0]),
+            "expected a dither pattern"
+        );
+    }
+
+    #[test]
+    fn test_bayer_mixes_neighbouring_levels() {
+        let img = RgbImage::from_pixel(4, 4, Rgb([100, 0, 0]));
+        let out = quantize_rgb565(&img, options(Quantizer::Bayer));
+        let mut levels: Vec<u16> = out.iter().map(|&value| red_level(value)).collect();
+        levels.sort();
+        levels.dedup();
+        assert_eq!(levels, vec![12, 13]);
+    }
+
+    #[test]
+    fn test_gamma_darkens_midtones() {
+        let img = RgbImage::from_pixel(1, 1, Rgb([128, 128, 128]));
+        let plain = quantize_rgb565(&img, options(Quantizer::Round))[0];
+        let corrected = quantize_rgb565(
+            &img,
+            ColorOptions {
+                gamma: 2.2,
+                ..options(Quantizer::Round)
+            },
+        )[0];
+        assert!(red_level(corrected) < red_level(plain));
+        // Black and white are unaffected
+        let img = RgbImage::from_pixel(1, 1, Rgb([255, 255, 255]));
+        let white = quantize_rgb565(
+            &img,
+            ColorOptions {
+                gamma: 2.2,
+                ..ColorOptions::default()
+            },
+        );
+        assert_eq!(white[0], 0xFFFF);
+    }
+
+    #[test]
+    fn test_correct_colors_keeps_full_depth() {
+        let img = RgbImage::from_pixel(1, 1, Rgb([200, 100, 50]));
+        assert_eq!(correct_colors(&img, ColorOptions::default()), img);
+        let balanced = correct_colors(
+            &img,
+            ColorOptions {
+                white_balance: [1.0, 1.0, 0.5],
+                ..ColorOptions::default()
+            },
+        );
+        assert_eq!(balanced.get_pixel(0, 0).0, [200, 100, 25]);
+    }
+}
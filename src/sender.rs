@@ -0,0 +1,425 @@
+use crate::display::Display;
+use crate::image::{image_to_panel_bytes, Orientation};
+use crate::panel::{PanelSpec, Rect};
+use crate::protocol::ProtocolError;
+use crate::quantize::ColorOptions;
+use image::RgbImage;
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum SenderError {
+    #[error(transparent)]
+    Protocol(#[from] ProtocolError),
+    #[error("Frame sender has stopped")]
+    Stopped,
+    #[error("A region can only be drawn after a full frame")]
+    NoFrame,
+}
+
+/// Counters for a [`FrameSender`]
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct SenderMetrics {
+    /// Frames and regions handed to the sender
+    pub submitted: u64,
+    /// Updates replaced or merged into a newer one before they were sent
+    pub dropped: u64,
+    /// Updates written to the display
+    pub sent: u64,
+    /// Pixel data written, in bytes
+    pub bytes_sent: u64,
+    /// Time spent writing to the display
+    pub busy: Duration,
+    /// Time since the sender started
+    pub elapsed: Duration,
+}
+
+impl SenderMetrics {
+    /// Fraction of the time the serial link was busy (0.0 to 1.0)
+    pub fn utilization(&self) -> f32 {
+        if self.elapsed.is_zero() {
+            return 0.0;
+        }
+        (self.busy.as_secs_f32() / self.elapsed.as_secs_f32()).min(1.0)
+    }
+
+    /// Pixel data written per second
+    pub fn throughput(&self) -> f32 {
+        if self.elapsed.is_zero() {
+            return 0.0;
+        }
+        self.bytes_sent as f32 / self.elapsed.as_secs_f32()
+    }
+}
+
+#[derive(Default)]
+struct State {
+    /// The picture the display should show, in the panel's layout
+    target: Option<(Vec<u8>, Orientation)>,
+    /// `target` has changed since the worker last took it
+    frame_pending: bool,
+    brightness: Option<u8>,
+    /// The worker is writing to the display
+    busy: bool,
+    closed: bool,
+    stopped: bool,
+    /// Why the worker stopped, until reported
+    error: Option<ProtocolError>,
+    metrics: SenderMetrics,
+}
+
+struct Shared {
+    state: Mutex<State>,
+    changed: Condvar,
+}
+
+impl Shared {
+    fn lock(&self) -> MutexGuard<'_, State> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// Sends frames to a display from a background thread.
+///
+/// Submitting never waits for the serial link. Only the newest frame is
+/// kept: a frame that is replaced before the thread gets to it is dropped,
+/// and regions drawn in the meantime are merged into it. Once a frame has
+/// been shown, later frames in the same orientation are sent as the
+/// rectangle that changed.
+pub struct FrameSender {
+    shared: Arc<Shared>,
+    worker: Option<JoinHandle<Display>>,
+    panel: PanelSpec,
+    started: Instant,
+}
+
+impl FrameSender {
+    /// Start a sender thread that owns `display`
+    pub fn spawn(display: Display) -> Self {
+        let shared = Arc::new(Shared {
+            state: Mutex::new(State::default()),
+            changed: Condvar::new(),
+        });
+        let panel = display.panel();
+        let worker = {
+            let shared = Arc::clone(&shared);
+            thread::spawn(move || run(display, &shared))
+        };
+        FrameSender {
+            shared,
+            worker: Some(worker),
+            panel,
+            started: Instant::now(),
+        }
+    }
+
+    pub fn panel(&self) -> PanelSpec {
+        self.panel
+    }
+
+    /// Queue a full frame already converted for the panel, replacing any
+    /// frame not yet sent
+    pub fn submit_frame(&self, data: Vec<u8>, orientation: Orientation) -> Result<(), SenderError> {
+        let expected = self.panel.frame_bytes();
+        if data.len() != expected {
+            return Err(ProtocolError::PayloadLength {
+                rect: Rect::full(self.panel),
+                expected,
+                actual: data.len(),
+            }
+            .into());
+        }
+        self.update(|state| {
+            state.target = Some((data, orientation));
+            Ok(())
+        })
+    }
+
+    /// Convert and queue an image drawn in `orientation`
+    pub fn submit_image(
+        &self,
+        img: &RgbImage,
+        orientation: Orientation,
+        colors: ColorOptions,
+    ) -> Result<(), SenderError> {
+        let data = image_to_panel_bytes(img, orientation, colors, self.panel);
+        self.submit_frame(data, orientation)
+    }
+
+    /// Queue pixel data for a window of the panel, drawn over the last
+    /// submitted frame
+    pub fn submit_region(&self, rect: Rect, data: &[u8]) -> Result<(), SenderError> {
+        let expected =
+            (rect.width * rect.height) as usize * self.panel.pixel_format.bytes_per_pixel();
+        if !rect.fits(self.panel) {
+            return Err(ProtocolError::WindowOutOfBounds {
+                rect,
+                width: self.panel.width,
+                height: self.panel.height,
+            }
+            .into());
+        }
+        if data.len() != expected {
+            return Err(ProtocolError::PayloadLength {
+                rect,
+                expected,
+                actual: data.len(),
+            }
+            .into());
+        }
+        let panel = self.panel;
+        self.update(|state| {
+            let (frame, _) = state.target.as_mut().ok_or(SenderError::NoFrame)?;
+            panel.paste(frame, rect, data);
+            Ok(())
+        })
+    }
+
+    /// Set backlight brightness once the current update is sent
+    pub fn set_brightness(&self, level: u8) -> Result<(), SenderError> {
+        let mut state = self.shared.lock();
+        check_running(&mut state)?;
+        state.brightness = Some(level);
+        self.shared.changed.notify_all();
+        Ok(())
+    }
+
+    /// Wait until everything submitted so far has been sent
+    pub fn flush(&self) -> Result<(), SenderError> {
+        let mut state = self.shared.lock();
+        while !state.stopped && (state.frame_pending || state.brightness.is_some() || state.busy) {
+            state = self
+                .shared
+                .changed
+                .wait(state)
+                .unwrap_or_else(|e| e.into_inner());
+        }
+        check_running(&mut state)
+    }
+
+    pub fn metrics(&self) -> SenderMetrics {
+        SenderMetrics {
+            elapsed: self.started.elapsed(),
+            ..self.shared.lock().metrics
+        }
+    }
+
+    /// Send whatever is queued, stop the thread and hand back the display
+    pub fn finish(mut self) -> Result<Display, SenderError> {
+        let flushed = self.flush();
+        let display = self.stop().ok_or(SenderError::Stopped)?;
+        flushed.map(|()| display)
+    }
+
+    fn update(
+        &self,
+        apply: impl FnOnce(&mut State) -> Result<(), SenderError>,
+    ) -> Result<(), SenderError> {
+        let mut state = self.shared.lock();
+        check_running(&mut state)?;
+        apply(&mut state)?;
+        state.metrics.submitted += 1;
+        if state.frame_pending {
+            state.metrics.dropped += 1;
+        }
+        state.frame_pending = true;
+        self.shared.changed.notify_all();
+        Ok(())
+    }
+
+    fn stop(&mut self) -> Option<Display> {
+        self.shared.lock().closed = true;
+        self.shared.changed.notify_all();
+        self.worker.take()?.join().ok()
+    }
+}
+
+impl Drop for FrameSender {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+/// Report the error that stopped the worker (once), or that it stopped
+fn check_running(state: &mut State) -> Result<(), SenderError> {
+    if !state.stopped {
+        return Ok(());
+    }
+    Err(state.error.take().map_or(SenderError::Stopped, Into::into))
+}
+
+fn run(mut display: Display, shared: &Shared) -> Display {
+    let mut shown: Option<(Vec<u8>, Orientation)> = None;
+    loop {
+        let (frame, brightness) = {
+            let mut state = shared.lock();
+            while !state.frame_pending && state.brightness.is_none() && !state.closed {
+                state = shared
+                    .changed
+                    .wait(state)
+                    .unwrap_or_else(|e| e.into_inner());
+            }
+            if !state.frame_pending && state.brightness.is_none() {
+                state.stopped = true;
+                shared.changed.notify_all();
+                return display;
+            }
+            state.busy = true;
+            let frame = if state.frame_pending {
+                state.frame_pending = false;
+                state.target.clone()
+            } else {
+                None
+            };
+            (frame, state.brightness.take())
+        };
+
+        let start = Instant::now();
+        let result = frame
+            .map_or(Ok(None), |frame| {
+                send_update(&mut display, &mut shown, frame)
+            })
+            .and_then(|bytes| {
+                if let Some(level) = brightness {
+                    display.set_brightness(level)?;
+                }
+                Ok(bytes)
+            });
+
+        let mut state = shared.lock();
+        state.busy = false;
+        state.metrics.busy += start.elapsed();
+        match result {
+            Ok(Some(bytes)) => {
+                state.metrics.sent += 1;
+                state.metrics.bytes_sent += bytes as u64;
+            }
+            Ok(None) => {}
+            Err(e) => {
+                state.error = Some(e);
+                state.stopped = true;
+                shared.changed.notify_all();
+                return display;
+            }
+        }
+        shared.changed.notify_all();
+    }
+}
+
+/// Show `frame`, sending only the changed rectangle when possible. Returns
+/// the pixel bytes written, or `None` if nothing changed.
+fn send_update(
+    display: &mut Display,
+    shown: &mut Option<(Vec<u8>, Orientation)>,
+    frame: (Vec<u8>, Orientation),
+) -> Result<Option<usize>, ProtocolError> {
+    let panel = display.panel();
+    let (data, orientation) = &frame;
+    let previous = shown
+        .as_ref()
+        .filter(|(_, shown_orientation)| shown_orientation == orientation)
+        .filter(|_| display.capabilities().partial_updates);
+
+    let bytes = match previous {
+        Some((old, _)) => match panel.dirty_rect(old, data) {
+            None => return Ok(None),
+            Some(rect) => {
+                let region = panel.crop(data, rect);
+                display.blit(rect, &region)?;
+                region.len()
+            }
+        },
+        None => {
+            // Forget what was shown in case the frame is cut short
+            *shown = None;
+            display.send_frame(data, *orientation)?;
+            data.len()
+        }
+    };
+    *shown = Some(frame);
+    Ok(Some(bytes))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::driver::tests::Recorder;
+    use crate::driver::WeActFsV1;
+    use crate::protocol::create_bitmap_header_window;
+
+    fn sender() -> (FrameSender, Recorder) {
+        let port = Recorder::default();
+        let display = Display::new(Box::new(port.clone()), &WeActFsV1).unwrap();
+        (FrameSender::spawn(display), port)
+    }
+
+    #[test]
+    fn test_only_latest_frame_is_kept() {
+        let (sender, port) = sender();
+        let frames: Vec<_> = (1..=4)
+            .map(|n| vec![n; sender.panel().frame_bytes()])
+            .collect();
+        // Stall the link so the worker takes at most the first frame
+        // before all four are submitted
+        port.hold();
+        for frame in &frames {
+            sender
+                .submit_frame(frame.clone(), Orientation::Landscape)
+                .unwrap();
+        }
+        port.release();
+        sender.flush().unwrap();
+
+        let metrics = sender.metrics();
+        assert_eq!(metrics.submitted, 4);
+        assert_eq!(metrics.sent + metrics.dropped, 4);
+        assert!(metrics.sent <= 2, "{:?}", metrics);
+        assert!(port.written().ends_with(&frames[3]));
+    }
+
+    #[test]
+    fn test_sends_changed_region() {
+        let (sender, port) = sender();
+        let panel = sender.panel();
+        let frame = vec![0; panel.frame_bytes()];
+        sender
+            .submit_frame(frame.clone(), Orientation::Landscape)
+            .unwrap();
+        sender.flush().unwrap();
+        let before = port.written().len();
+
+        // Unchanged frames aren't sent at all
+        sender
+            .submit_frame(frame.clone(), Orientation::Landscape)
+            .unwrap();
+        sender.flush().unwrap();
+        assert_eq!(port.written().len(), before);
+
+        let rect = Rect::new(10, 20, 2, 3);
+        sender.submit_region(rect, &[0xEE; 12]).unwrap();
+        sender.flush().unwrap();
+        let written = port.written();
+        assert_eq!(
+            &written[before..before + 10],
+            &create_bitmap_header_window(rect).unwrap()
+        );
+        assert_eq!(&written[before + 10..], &[0xEE; 12]);
+        assert_eq!(sender.metrics().sent, 2);
+        assert_eq!(
+            sender.metrics().bytes_sent,
+            (panel.frame_bytes() + 12) as u64
+        );
+    }
+
+    #[test]
+    fn test_region_needs_frame() {
+        let (sender, _) = sender();
+        assert!(matches!(
+            sender.submit_region(Rect::new(0, 0, 1, 1), &[0, 0]),
+            Err(SenderError::NoFrame)
+        ));
+        assert!(sender.finish().is_ok());
+    }
+}
@@ -1,105 +1,619 @@
-use crate::image::{calculate_max_chars_per_line, calculate_max_lines, DISPLAY_WIDTH};
-use crate::measure_text_with_font_size;
+use crate::font::Font;
+use crate::hyphenation::Hyphenator;
+use crate::icons::{icon_for, icon_reference};
+use crate::image::{calculate_max_lines_oriented, Orientation, MAX_FONT_SIZE, MIN_FONT_SIZE};
+use crate::layout::{LayoutOptions, LineLayout};
+use crate::theme::parse_color;
+use image::Rgb;
+use unicode_linebreak::BreakOpportunity;
+
+/// Style of a run of text. `None` fields use the theme color / base font size.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Style {
+    pub color: Option<Rgb<u8>>,
+    pub bold: bool,
+    pub size: Option<f32>,
+}
+
+/// A run of text drawn with a single style
+#[derive(Debug, Clone, PartialEq)]
+pub struct Span {
+    pub text: String,
+    pub style: Style,
+}
+
+/// A style change undone by `{/}`
+enum Saved {
+    Color(Option<Rgb<u8>>),
+    Size(Option<f32>),
+}
+
+/// Parse inline markup into styled spans:
+///
+/// - `{red}` / `{#ff8800}` - text color (any name accepted by `--fg`)
+/// - `{size=20}` - font size in pixels
+/// - `{/}` - undo the most recent color or size tag
+/// - `**bold**` - toggle bold
+/// - `:wifi:` - a built-in color icon (see [`crate::icons`]); emoji that
+///   have an icon, like `📧`, are drawn as the icon too
+/// - `\{`, `\*`, `\:`, `\\` - literal characters
+///
+/// Unrecognized `{...}` tags and `:name:` references are kept as text.
+pub fn parse_markup(text: &str) -> Vec<Span> {
+    let mut spans = Vec::new();
+    let mut style = Style::default();
+    let mut saved: Vec<Saved> = Vec::new();
+    let mut rest = text;
+
+    while let Some(c) = rest.chars().next() {
+        if c == '\\' {
+            if let Some(escaped) = rest[1..].chars().next().filter(|e| "\\{*:".contains(*e)) {
+                push_char(&mut spans, escaped, style);
+                rest = &rest[1 + escaped.len_utf8()..];
+                continue;
+            }
+        }
+        if rest.starts_with("**") {
+            style.bold = !style.bold;
+            rest = &rest[2..];
+            continue;
+        }
+        if c == '{' {
+            if let Some(end) = rest.find('}') {
+                if apply_tag(&rest[1..end], &mut style, &mut saved) {
+                    rest = &rest[end + 1..];
+                    continue;
+                }
+            }
+        }
+        if let Some((icon, len)) = icon_reference(rest) {
+            push_char(&mut spans, icon.char(), style);
+            rest = &rest[len..];
+            continue;
+        }
+        rest = &rest[c.len_utf8()..];
+        // Emoji with a built-in icon are drawn as that icon
+        match icon_for(c) {
+            Some(icon) => {
+                push_char(&mut spans, icon.char(), style);
+                rest = rest.strip_prefix(EMOJI_PRESENTATION).unwrap_or(rest);
+            }
+            None => push_char(&mut spans, c, style),
+        }
+    }
+
+    spans
+}
+
+/// Apply a `{tag}` to the current style. Returns false for unknown tags.
+fn apply_tag(tag: &str, style: &mut Style, saved: &mut Vec<Saved>) -> bool {
+    if tag == "/" {
+        match saved.pop() {
+            Some(Saved::Color(color)) => style.color = color,
+            Some(Saved::Size(size)) => style.size = size,
+            None => {}
+        }
+        return true;
+    }
+    if let Some(size) = tag.strip_prefix("size=") {
+        return match size.parse::<f32>() {
+            Ok(size) if size > 0.0 => {
+                saved.push(Saved::Size(style.size.replace(size)));
+                true
+            }
+            _ => false,
+        };
+    }
+    match parse_color(tag) {
+        Ok(color) => {
+            saved.push(Saved::Color(style.color.replace(color)));
+            true
+        }
+        Err(_) => false,
+    }
+}
+
+fn push_char(spans: &mut Vec<Span>, c: char, style: Style) {
+    match spans.last_mut() {
+        Some(last) if last.style == style => last.text.push(c),
+        _ => spans.push(Span {
+            text: c.to_string(),
+            style,
+        }),
+    }
+}
+
+fn push_str(spans: &mut Vec<Span>, text: &str, style: Style) {
+    text.chars().for_each(|c| push_char(spans, c, style));
+}
+
+/// Escape text (e.g. command output) so it is displayed literally
+pub fn escape_markup(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for (i, c) in text.char_indices() {
+        if matches!(c, '\\' | '{' | '*') || icon_reference(&text[i..]).is_some() {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+/// Serialize spans back to markup. Every span opens and closes its own
+/// tags, so the result can be split at span boundaries.
+pub fn to_markup(spans: &[Span]) -> String {
+    let mut markup = String::new();
+    for span in spans {
+        let style = span.style;
+        if style.bold {
+            markup.push_str("**");
+        }
+        if let Some(Rgb([r, g, b])) = style.color {
+            markup.push_str(&format!("{{#{:02x}{:02x}{:02x}}}", r, g, b));
+        }
+        if let Some(size) = style.size {
+            markup.push_str(&format!("{{size={}}}", size));
+        }
+        markup.push_str(&escape_markup(&span.text));
+        let opened = style.color.is_some() as usize + style.size.is_some() as usize;
+        markup.push_str(&"{/}".repeat(opened));
+        if style.bold {
+            markup.push_str("**");
+        }
+    }
+    markup
+}
+
+/// Split spans at newlines into lines of spans
+pub(crate) fn split_lines(spans: &[Span]) -> Vec<Vec<Span>> {
+    let mut lines = vec![Vec::new()];
+    for span in spans {
+        for (i, part) in span.text.split('\n').enumerate() {
+            if i > 0 {
+                lines.push(Vec::new());
+            }
+            if !part.is_empty() {
+                push_str(lines.last_mut().unwrap(), part, span.style);
+            }
+        }
+    }
+    lines
+}
 
 /// Split text into pages that fit on the display.
-/// Uses word-aware splitting (never breaks mid-word).
+/// Uses Unicode line breaking; only words wider than the display are split.
 pub fn split_into_pages(text: &str, font_size: f32) -> Vec<String> {
-    let max_lines = calculate_max_lines(font_size);
+    split_into_pages_oriented(
+        &Font::default(),
+        text,
+        font_size,
+        Orientation::default(),
+        LayoutOptions::default(),
+    )
+}
+
+/// Split markup text into pages for a specific font, orientation and layout
+/// (margins and line spacing). Styles carry over to wrapped lines and later pages.
+pub fn split_into_pages_oriented(
+    font: &Font,
+    text: &str,
+    font_size: f32,
+    orientation: Orientation,
+    options: LayoutOptions,
+) -> Vec<String> {
+    let max_lines = calculate_max_lines_oriented(font, font_size, orientation, options);
 
     if max_lines == 0 {
         return vec![];
     }
 
-    let lines = wrap_text(text, font_size);
+    let max_width = options.text_width(orientation.width());
+    let lines = wrap_text(font, text, font_size, max_width, options.overflow);
 
     if lines.is_empty() {
         return vec![];
     }
 
-    lines
-        .chunks(max_lines)
-        .map(|chunk| chunk.join("\n"))
-        .collect()
+    // Lines with larger spans are taller, so fill pages by height. `page_height`
+    // is the offset of the next line's top from the top of the page.
+    let max_height = options.text_height(orientation.height()) as f32;
+    let mut pages: Vec<Vec<String>> = Vec::new();
+    let mut page_height = 0.0;
+    for line in &lines {
+        let height = LineLayout::styled(font, line, font_size).line_height;
+        match pages.last_mut() {
+            Some(page) if page_height + height <= max_height => {
+                page.push(to_markup(line));
+            }
+            _ => {
+                pages.push(vec![to_markup(line)]);
+                page_height = 0.0;
+            }
+        }
+        page_height += height * options.line_spacing;
+    }
+
+    pages.into_iter().map(|page| page.join("\n")).collect()
+}
+
+/// Font size and pages chosen by [`plan_layout`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct LayoutPlan {
+    pub font_size: f32,
+    /// Wrapped markup for each page, as from [`split_into_pages_oriented`]
+    pub pages: Vec<String>,
 }
 
-/// Wrap text into lines that fit within the display width.
-/// Respects word boundaries and existing newlines.
-fn wrap_text(text: &str, font_size: f32) -> Vec<String> {
+impl LayoutPlan {
+    /// The first page, or `text` itself if nothing could be laid out
+    pub fn first_page<'a>(&'a self, text: &'a str) -> &'a str {
+        self.pages.first().map_or(text, String::as_str)
+    }
+}
+
+/// Auto-fit that wraps: find the largest font size at which the text,
+/// word-wrapped, fits on at most `max_pages` pages without splitting any
+/// word. Falls back to the minimum size (and as many pages as needed)
+/// when nothing fits.
+pub fn plan_layout(
+    font: &Font,
+    text: &str,
+    orientation: Orientation,
+    options: LayoutOptions,
+    max_pages: usize,
+) -> LayoutPlan {
+    let plan_at = |font_size| LayoutPlan {
+        font_size,
+        pages: split_into_pages_oriented(font, text, font_size, orientation, options),
+    };
     if text.is_empty() {
-        return Vec::new();
+        return plan_at(MIN_FONT_SIZE);
     }
 
-    let mut result = Vec::new();
-    let max_chars = calculate_max_chars_per_line(font_size);
+    let max_pages = max_pages.max(1);
+    let max_width = options.text_width(orientation.width());
+    let fits = |plan: &LayoutPlan| {
+        !plan.pages.is_empty()
+            && plan.pages.len() <= max_pages
+            && words_fit(font, text, plan.font_size, max_width)
+    };
 
-    for paragraph in text.split('\n') {
-        if paragraph.is_empty() {
-            result.push(String::new());
-            continue;
-        }
+    let mut best = None;
+    let mut low = MIN_FONT_SIZE;
+    let mut high = MAX_FONT_SIZE;
 
-        let words: Vec<&str> = paragraph.split_whitespace().collect();
-        if words.is_empty() {
-            result.push(String::new());
-            continue;
+    while high - low > 0.5 {
+        let mid = (low + high) / 2.0;
+        let plan = plan_at(mid);
+
+        if fits(&plan) {
+            low = mid;
+            best = Some(plan);
+        } else {
+            high = mid;
         }
+    }
 
-        let mut current_line = String::new();
+    best.unwrap_or_else(|| plan_at(MIN_FONT_SIZE))
+}
 
-        for word in words {
-            if current_line.is_empty() {
-                // First word on line - check if it fits
-                if fits_in_width(word, font_size) {
-                    current_line = word.to_string();
-                } else {
-                    // Word too long, truncate it
-                    current_line = truncate_to_fit(word, font_size, max_chars);
-                    result.push(current_line);
-                    current_line = String::new();
-                }
+/// Check that no unbreakable run of the text is wider than `max_width`,
+/// so wrapping never has to fall back to the overflow policy
+fn words_fit(font: &Font, text: &str, font_size: f32, max_width: u32) -> bool {
+    let fitter = LineFitter {
+        font,
+        font_size,
+        max_width,
+    };
+    split_lines(&parse_markup(text)).iter().all(|paragraph| {
+        let chars: Vec<StyledChar> = paragraph
+            .iter()
+            .flat_map(|span| span.text.chars().map(move |c| (c, span.style)))
+            .collect();
+        break_segments(&chars)
+            .iter()
+            .all(|(segment, _)| fitter.fits_chars(segment))
+    })
+}
+
+/// Soft hyphen: an invisible break opportunity, shown as `-` when a line breaks there
+const SOFT_HYPHEN: char = '\u{AD}';
+
+/// Right-to-left mark: invisible, sets the direction of a line
+const RLM: char = '\u{200F}';
+
+/// Variation selector asking for the emoji (color) form of the character before it
+const EMOJI_PRESENTATION: char = '\u{FE0F}';
+
+/// Smallest size `Overflow::ShrinkToFit` will shrink a word to
+const MIN_SHRINK_SIZE: f32 = 6.0;
+
+/// What `wrap_text` does with a word (or other unbreakable run, like a
+/// branch name) that is wider than the display
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Overflow {
+    /// Break between any two characters
+    BreakAnywhere,
+    /// Break at hyphenation points (adding `-`), after `_` and `.`, and at
+    /// camelCase humps, falling back to breaking anywhere
+    #[default]
+    Hyphenate,
+    /// Cut the word short and end it with `…`
+    Ellipsize,
+    /// Draw the word smaller so it fits on one line
+    ShrinkToFit,
+}
+
+type StyledChar = (char, Style);
+
+/// Measures candidate lines while wrapping
+struct LineFitter<'a> {
+    font: &'a Font,
+    font_size: f32,
+    max_width: u32,
+}
+
+impl LineFitter<'_> {
+    fn fits(&self, line: &[Span]) -> bool {
+        fits_in_width(self.font, line, self.font_size, self.max_width)
+    }
+
+    fn fits_chars(&self, chars: &[StyledChar]) -> bool {
+        self.fits(&finish_line(chars))
+    }
+}
+
+/// Wrap markup text into lines of spans that fit within `max_width` pixels.
+/// Lines break only at UAX #14 break opportunities: after spaces, between
+/// CJK characters, after slashes in URLs and at soft hyphens. Runs that are
+/// still too wide are handled according to `overflow`.
+fn wrap_text(
+    font: &Font,
+    text: &str,
+    font_size: f32,
+    max_width: u32,
+    overflow: Overflow,
+) -> Vec<Vec<Span>> {
+    if text.is_empty() {
+        return Vec::new();
+    }
+
+    let mut result = Vec::new();
+    let fitter = LineFitter {
+        font,
+        font_size,
+        max_width,
+    };
+
+    for paragraph in split_lines(&parse_markup(text)) {
+        let chars: Vec<StyledChar> = paragraph
+            .iter()
+            .flat_map(|span| span.text.chars().map(move |c| (c, span.style)))
+            .collect();
+        let first_line = result.len();
+        let mut current_line: Vec<StyledChar> = Vec::new();
+
+        for (segment, mandatory) in break_segments(&chars) {
+            let mut test_line = current_line.clone();
+            test_line.extend_from_slice(segment);
+
+            if fitter.fits_chars(&test_line) {
+                current_line = test_line;
             } else {
-                // Try adding word to current line
-                let test_line = format!("{} {}", current_line, word);
-                if fits_in_width(&test_line, font_size) {
-                    current_line = test_line;
+                // Start new line
+                if !current_line.is_empty() {
+                    result.push(finish_line(&current_line));
+                }
+                if fitter.fits_chars(segment) {
+                    current_line = segment.to_vec();
                 } else {
-                    // Start new line
-                    result.push(current_line);
-                    if fits_in_width(word, font_size) {
-                        current_line = word.to_string();
-                    } else {
-                        current_line = truncate_to_fit(word, font_size, max_chars);
-                        result.push(current_line);
-                        current_line = String::new();
-                    }
+                    // Segment too long even on its own line
+                    let (lines, rest) = split_overflowing(&fitter, segment, overflow);
+                    result.extend(lines);
+                    current_line = rest;
                 }
             }
+
+            if mandatory && !current_line.is_empty() {
+                result.push(finish_line(&current_line));
+                current_line = Vec::new();
+            }
         }
 
-        if !current_line.is_empty() {
-            result.push(current_line);
+        if !current_line.is_empty() || result.len() == first_line {
+            result.push(finish_line(&current_line));
+        }
+        if paragraph_is_rtl(&chars) {
+            result[first_line..].iter_mut().for_each(mark_rtl);
         }
     }
 
     result
 }
 
-/// Check if text fits within display width
-fn fits_in_width(text: &str, font_size: f32) -> bool {
-    let (width, _) = measure_text_with_font_size(text, font_size);
-    width <= DISPLAY_WIDTH
+fn paragraph_is_rtl(chars: &[StyledChar]) -> bool {
+    let text: String = chars.iter().map(|(c, _)| c).collect();
+    unicode_bidi::get_base_direction(text.as_str()) == unicode_bidi::Direction::Rtl
+}
+
+/// Start a wrapped line of a right-to-left paragraph with a right-to-left
+/// mark, unless its own first letter already says so, so it keeps the
+/// paragraph's direction (e.g. a line that starts with a Latin word)
+fn mark_rtl(line: &mut Vec<Span>) {
+    let text: String = line.iter().map(|span| span.text.as_str()).collect();
+    if unicode_bidi::get_base_direction(text.as_str()) == unicode_bidi::Direction::Rtl {
+        return;
+    }
+    match line.first_mut() {
+        Some(span) => span.text.insert(0, RLM),
+        None => line.push(Span {
+            text: RLM.to_string(),
+            style: Style::default(),
+        }),
+    }
+}
+
+/// Apply the overflow policy to a segment wider than a line. Returns the
+/// finished lines and the remainder, which fits and can be continued.
+fn split_overflowing(
+    fitter: &LineFitter,
+    segment: &[StyledChar],
+    overflow: Overflow,
+) -> (Vec<Vec<Span>>, Vec<StyledChar>) {
+    match overflow {
+        Overflow::Ellipsize => (vec![ellipsize(fitter, segment)], Vec::new()),
+        Overflow::ShrinkToFit => (vec![shrink_to_fit(fitter, segment)], Vec::new()),
+        Overflow::BreakAnywhere | Overflow::Hyphenate => {
+            let mut lines = Vec::new();
+            let mut rest = segment;
+            while !fitter.fits_chars(rest) {
+                let points = match overflow {
+                    Overflow::Hyphenate => break_points(rest),
+                    _ => Vec::new(),
+                };
+                let hyphenated = points.iter().rev().find_map(|&(at, hyphen)| {
+                    let mut line = finish_line(&rest[..at]);
+                    if hyphen {
+                        let style = rest[at - 1].1;
+                        push_char(&mut line, '-', style);
+                    }
+                    fitter.fits(&line).then_some((line, at))
+                });
+                let (line, at) = hyphenated.unwrap_or_else(|| {
+                    // Always take at least one character so wrapping progresses
+                    let at = (1..rest.len())
+                        .rev()
+                        .find(|&at| fitter.fits_chars(&rest[..at]))
+                        .unwrap_or(1);
+                    (finish_line(&rest[..at]), at)
+                });
+                lines.push(line);
+                rest = &rest[at..];
+            }
+            (lines, rest.to_vec())
+        }
+    }
 }
 
-/// Truncate word to fit within display width
-fn truncate_to_fit(word: &str, font_size: f32, max_chars: usize) -> String {
-    let mut result = word.to_string();
-    let limit = max_chars.min(word.len());
+/// Positions inside `chars` where a long word can be split, and whether a
+/// hyphen is shown there: dictionary hyphenation points, and (without a
+/// hyphen) after `_` or `.` and before the capital of a camelCase hump
+fn break_points(chars: &[StyledChar]) -> Vec<(usize, bool)> {
+    let mut points = Vec::new();
+
+    let mut start = 0;
+    while start < chars.len() {
+        let len = chars[start..]
+            .iter()
+            .take_while(|(c, _)| c.is_alphabetic())
+            .count();
+        if len > 0 {
+            let word: String = chars[start..start + len].iter().map(|(c, _)| *c).collect();
+            let hyphens = Hyphenator::english().hyphenate(&word);
+            points.extend(hyphens.into_iter().map(|at| (start + at, true)));
+        }
+        start += len.max(1);
+    }
 
-    while !result.is_empty() && !fits_in_width(&result, font_size) {
-        result = result.chars().take(limit.saturating_sub(1)).collect();
+    for at in 1..chars.len() {
+        let (prev, c) = (chars[at - 1].0, chars[at].0);
+        if matches!(prev, '_' | '.') || (prev.is_lowercase() && c.is_uppercase()) {
+            points.push((at, false));
+        }
     }
 
-    result
+    points.sort_unstable();
+    points.dedup_by_key(|(at, _)| *at);
+    points
+}
+
+/// Cut `chars` short so that it fits with a trailing `…`
+fn ellipsize(fitter: &LineFitter, chars: &[StyledChar]) -> Vec<Span> {
+    (0..chars.len())
+        .rev()
+        .map(|at| {
+            let mut line = finish_line(&chars[..at]);
+            let style = chars[at.saturating_sub(1)].1;
+            push_char(&mut line, '…', style);
+            line
+        })
+        .find(|line| fitter.fits(line))
+        .unwrap_or_default()
+}
+
+/// Shrink `chars` in 5% steps until it fits, down to `MIN_SHRINK_SIZE`,
+/// then ellipsize whatever is still too wide
+fn shrink_to_fit(fitter: &LineFitter, chars: &[StyledChar]) -> Vec<Span> {
+    let resize = |factor: f32| -> Vec<StyledChar> {
+        chars
+            .iter()
+            .map(|&(c, style)| {
+                let size = style.size.unwrap_or(fitter.font_size) * factor;
+                let size = ((size * 2.0).round() / 2.0).max(MIN_SHRINK_SIZE);
+                (
+                    c,
+                    Style {
+                        size: Some(size),
+                        ..style
+                    },
+                )
+            })
+            .collect()
+    };
+
+    let mut factor = 1.0;
+    let mut resized = chars.to_vec();
+    while fitter.font_size * factor > MIN_SHRINK_SIZE {
+        factor -= 0.05;
+        resized = resize(factor);
+        if fitter.fits_chars(&resized) {
+            return finish_line(&resized);
+        }
+    }
+    ellipsize(fitter, &resized)
+}
+
+/// Split a paragraph into unbreakable segments, each ending at a break
+/// opportunity (trailing spaces stay with the segment before the break).
+/// The flag marks forced breaks inside the paragraph, e.g. U+2028.
+fn break_segments(chars: &[StyledChar]) -> Vec<(&[StyledChar], bool)> {
+    let plain: String = chars.iter().map(|(c, _)| *c).collect();
+    let offsets: Vec<usize> = plain.char_indices().map(|(i, _)| i).collect();
+
+    let mut segments = Vec::new();
+    let mut start = 0;
+    for (byte, opportunity) in unicode_linebreak::linebreaks(&plain) {
+        let end = offsets.partition_point(|&offset| offset < byte);
+        let mandatory = opportunity == BreakOpportunity::Mandatory && end < chars.len();
+        segments.push((&chars[start..end], mandatory));
+        start = end;
+    }
+    segments
+}
+
+/// Convert the characters of a wrapped line to spans, dropping trailing
+/// whitespace and showing a soft hyphen at the end of the line as `-`
+fn finish_line(chars: &[StyledChar]) -> Vec<Span> {
+    let end = chars
+        .iter()
+        .rposition(|(c, _)| !c.is_whitespace())
+        .map_or(0, |i| i + 1);
+    let mut spans = Vec::new();
+    for (i, &(c, style)) in chars[..end].iter().enumerate() {
+        let c = if c == SOFT_HYPHEN && i == end - 1 {
+            '-'
+        } else {
+            c
+        };
+        push_char(&mut spans, c, style);
+    }
+    spans
+}
+
+/// Check if a line of spans fits within `max_width` pixels
+fn fits_in_width(font: &Font, line: &[Span], font_size: f32, max_width: u32) -> bool {
+    let width = LineLayout::styled(font, line, font_size).ink_width();
+    width.ceil() as u32 <= max_width
 }
 
 #[cfg(test)]
@@ -165,20 +679,418 @@ mod tests {
         assert!(combined.contains("Line three"));
     }
 
+    #[test]
+    fn test_portrait_wraps_narrower() {
+        let text = "The quick brown fox jumps over the lazy dog";
+        let font = Font::embedded();
+        let options = LayoutOptions::default();
+        let landscape =
+            split_into_pages_oriented(&font, text, 14.0, Orientation::Landscape, options);
+        let portrait = split_into_pages_oriented(&font, text, 14.0, Orientation::Portrait, options);
+        let line_count = |pages: &[String]| pages.iter().map(|p| p.lines().count()).sum::<usize>();
+        assert!(line_count(&portrait) > line_count(&landscape));
+    }
+
     #[test]
     fn test_wrap_text_basic() {
-        let lines = wrap_text("Hello World", 14.0);
+        let lines = wrap_text(
+            &Font::embedded(),
+            "Hello World",
+            14.0,
+            160,
+            Overflow::default(),
+        );
         assert!(!lines.is_empty());
     }
 
     #[test]
     fn test_fits_in_width_short_text() {
-        assert!(fits_in_width("Hi", 14.0));
+        assert!(fits_in_width(
+            &Font::embedded(),
+            &parse_markup("Hi"),
+            14.0,
+            160
+        ));
     }
 
     #[test]
     fn test_fits_in_width_long_text() {
         let long = "This is a very long line that definitely won't fit on a 160 pixel wide display";
-        assert!(!fits_in_width(long, 14.0));
+        assert!(!fits_in_width(
+            &Font::embedded(),
+            &parse_markup(long),
+            14.0,
+            160
+        ));
+    }
+
+    #[test]
+    fn test_margins_and_spacing_affect_pages() {
+        let text = "one two three four five six seven eight nine ten eleven twelve";
+        let font = Font::embedded();
+        let orientation = Orientation::Landscape;
+        let line_count = |options| {
+            split_into_pages_oriented(&font, text, 14.0, orientation, options)
+                .iter()
+                .map(|p| p.lines().count())
+                .sum::<usize>()
+        };
+        let page_count =
+            |options| split_into_pages_oriented(&font, text, 14.0, orientation, options).len();
+
+        let default = LayoutOptions::default();
+        let wide_margins = LayoutOptions {
+            margin_x: 40,
+            ..default
+        };
+        let spaced = LayoutOptions {
+            line_spacing: 3.0,
+            ..default
+        };
+        assert!(line_count(wide_margins) > line_count(default));
+        assert!(page_count(spaced) > page_count(default));
+    }
+
+    fn plain_lines(text: &str, max_width: u32) -> Vec<String> {
+        wrap_text(
+            &Font::embedded(),
+            text,
+            14.0,
+            max_width,
+            Overflow::default(),
+        )
+        .iter()
+        .map(|line| line.iter().map(|span| span.text.as_str()).collect())
+        .collect()
+    }
+
+    #[test]
+    fn test_wrapped_rtl_lines_keep_direction() {
+        let lines = plain_lines("שלום עולם Hello World", 60);
+        assert!(lines.len() > 1);
+        assert!(lines[0].starts_with('ש'));
+        // A continuation line that starts with a Latin word stays right-to-left
+        let latin = lines.iter().find(|line| line.contains("Hello")).unwrap();
+        assert!(latin.starts_with(RLM));
+        assert!(LineLayout::new(&Font::embedded(), latin, 14.0).rtl);
+    }
+
+    #[test]
+    fn test_cjk_wraps_between_characters() {
+        // Break opportunities don't depend on glyphs, so this holds even
+        // when CJK renders as tofu without the `japanese` feature
+        let lines = plain_lines("日本語のテキストを折り返します", 80);
+        assert!(lines.len() > 1, "{:?}", lines);
+        assert_eq!(lines.concat(), "日本語のテキストを折り返します");
+    }
+
+    #[test]
+    fn test_cjk_punctuation_not_at_line_start() {
+        let lines = plain_lines("今日は晴れです。明日は雨です。", 60);
+        for line in &lines {
+            assert!(!line.starts_with('。'), "{:?}", lines);
+        }
+    }
+
+    #[test]
+    fn test_url_breaks_at_slashes() {
+        let url = "https://example.com/some/long/path/to/resource";
+        let lines = plain_lines(url, 120);
+        assert!(lines.len() > 1, "{:?}", lines);
+        assert_eq!(lines.concat(), url, "URL should be split, not truncated");
+        for line in &lines[..lines.len() - 1] {
+            assert!(line.ends_with('/'), "{:?}", lines);
+        }
+    }
+
+    #[test]
+    fn test_soft_hyphen_breaks_with_visible_hyphen() {
+        let lines = plain_lines(
+            "in\u{AD}com\u{AD}pre\u{AD}hen\u{AD}si\u{AD}bil\u{AD}i\u{AD}ty",
+            80,
+        );
+        assert!(lines.len() > 1, "{:?}", lines);
+        for line in &lines[..lines.len() - 1] {
+            assert!(line.ends_with('-'), "{:?}", lines);
+        }
+        let joined: String = lines.iter().map(|l| l.trim_end_matches('-')).collect();
+        assert_eq!(joined.replace('\u{AD}', ""), "incomprehensibility");
+    }
+
+    #[test]
+    fn test_soft_hyphen_invisible_mid_line() {
+        let font = Font::embedded();
+        let with = LineLayout::new(&font, "co\u{AD}op", 14.0);
+        let without = LineLayout::new(&font, "coop", 14.0);
+        assert_eq!(with.advance, without.advance);
+    }
+
+    #[test]
+    fn test_blank_and_whitespace_paragraphs_kept() {
+        assert_eq!(plain_lines("a\n\n   \nb", 160), ["a", "", "", "b"]);
+    }
+
+    #[cfg(feature = "japanese")]
+    #[test]
+    fn test_japanese_wrap_fills_lines() {
+        let font = Font::embedded();
+        let text = "吾輩は猫である。名前はまだ無い。どこで生れたかとんと見当がつかぬ。";
+        let lines = wrap_text(&font, text, 14.0, 160, Overflow::default());
+        assert!(lines.len() > 1);
+        for line in &lines[..lines.len() - 1] {
+            let width = LineLayout::styled(&font, line, 14.0).ink_width();
+            // Each full line should use most of the width, not one "word" per line
+            assert!(width > 160.0 - 2.0 * 14.0, "line too short: {:?}", line);
+        }
+    }
+
+    #[cfg(feature = "japanese")]
+    #[test]
+    fn test_japanese_pages_keep_all_text() {
+        let font = Font::embedded();
+        let text = "東京都の天気は晴れ、最高気温は二十五度の予報です。";
+        let pages = split_into_pages_oriented(
+            &font,
+            text,
+            20.0,
+            Orientation::Portrait,
+            LayoutOptions::default(),
+        );
+        let joined: String = pages.iter().flat_map(|p| p.lines()).collect();
+        assert_eq!(joined, text);
+    }
+
+    #[cfg(feature = "japanese")]
+    #[test]
+    fn test_mixed_japanese_latin_wraps() {
+        let lines = plain_lines("ビルド build 成功 success しました", 80);
+        assert!(lines.len() > 1);
+        assert_eq!(
+            lines.concat().replace(' ', ""),
+            "ビルドbuild成功successしました"
+        );
+    }
+
+    fn overflow_lines(text: &str, max_width: u32, overflow: Overflow) -> Vec<Vec<Span>> {
+        wrap_text(&Font::embedded(), text, 14.0, max_width, overflow)
+    }
+
+    fn line_text(line: &[Span]) -> String {
+        line.iter().map(|span| span.text.as_str()).collect()
+    }
+
+    #[test]
+    fn test_break_anywhere_keeps_all_characters() {
+        let word = "abcdefghijklmnopqrstuvwxyz";
+        let lines = overflow_lines(word, 60, Overflow::BreakAnywhere);
+        assert!(lines.len() > 1);
+        assert_eq!(lines.iter().map(|l| line_text(l)).collect::<String>(), word);
+    }
+
+    #[test]
+    fn test_hyphenate_adds_hyphens() {
+        let lines = overflow_lines("internationalization", 80, Overflow::Hyphenate);
+        assert!(lines.len() > 1);
+        let texts: Vec<String> = lines.iter().map(|l| line_text(l)).collect();
+        assert!(texts[0].ends_with('-'), "{:?}", texts);
+        let joined: String = texts.iter().map(|t| t.trim_end_matches('-')).collect();
+        assert_eq!(joined, "internationalization");
+    }
+
+    #[test]
+    fn test_hyphenate_splits_identifiers_at_separators() {
+        let lines = overflow_lines("feature_login_redirectHandler", 100, Overflow::Hyphenate);
+        let texts: Vec<String> = lines.iter().map(|l| line_text(l)).collect();
+        assert!(texts.len() > 1);
+        assert!(
+            texts[0].ends_with('_') || texts[0].ends_with('-'),
+            "{:?}",
+            texts
+        );
+        assert!(texts.iter().all(|t| !t.is_empty()));
+    }
+
+    #[test]
+    fn test_ellipsize_single_line() {
+        let lines = overflow_lines("supercalifragilistic next", 80, Overflow::Ellipsize);
+        let first = line_text(&lines[0]);
+        assert!(first.ends_with('…'), "{:?}", first);
+        assert_eq!(line_text(&lines[1]), "next");
+    }
+
+    #[test]
+    fn test_shrink_to_fit_uses_smaller_size() {
+        let lines = overflow_lines("refactor_very_long_branch_name", 60, Overflow::ShrinkToFit);
+        let shrunk = lines
+            .iter()
+            .flatten()
+            .any(|span| span.style.size.is_some_and(|size| size < 14.0));
+        assert!(shrunk, "{:?}", lines);
+        for line in &lines {
+            assert!(fits_in_width(&Font::embedded(), line, 14.0, 60));
+        }
+    }
+
+    #[test]
+    fn test_overflow_keeps_styles() {
+        let lines = overflow_lines(
+            "{red}abcdefghijklmnopqrstuvwxyz{/}",
+            60,
+            Overflow::Hyphenate,
+        );
+        for span in lines.iter().flatten() {
+            assert_eq!(span.style.color, Some(Rgb([255, 40, 40])));
+        }
+    }
+
+    #[test]
+    fn test_plan_wraps_long_sentence_at_larger_size() {
+        let font = Font::embedded();
+        let text = "The quick brown fox jumps over the lazy dog";
+        let options = LayoutOptions::default();
+        let unwrapped = crate::image::calculate_auto_fit_size_oriented(
+            &font,
+            text,
+            Orientation::Landscape,
+            options,
+        );
+        let plan = plan_layout(&font, text, Orientation::Landscape, options, 1);
+        assert_eq!(plan.pages.len(), 1);
+        assert!(plan.pages[0].lines().count() > 1);
+        assert!(
+            plan.font_size > unwrapped + 4.0,
+            "{:?} vs {}",
+            plan,
+            unwrapped
+        );
+    }
+
+    #[test]
+    fn test_plan_more_pages_allows_larger_size() {
+        let font = Font::embedded();
+        let text = "Deploy finished for all twelve services in the staging cluster";
+        let options = LayoutOptions::default();
+        let one = plan_layout(&font, text, Orientation::Landscape, options, 1);
+        let three = plan_layout(&font, text, Orientation::Landscape, options, 3);
+        assert!(three.font_size > one.font_size);
+        assert!(three.pages.len() <= 3);
+    }
+
+    #[test]
+    fn test_plan_never_splits_words() {
+        let font = Font::embedded();
+        let text = "Extraordinarily long words everywhere";
+        let plan = plan_layout(
+            &font,
+            text,
+            Orientation::Portrait,
+            LayoutOptions::default(),
+            1,
+        );
+        let words: Vec<&str> = plan
+            .pages
+            .iter()
+            .flat_map(|p| p.split_whitespace())
+            .collect();
+        assert_eq!(words, ["Extraordinarily", "long", "words", "everywhere"]);
+    }
+
+    #[test]
+    fn test_plan_falls_back_to_min_size() {
+        let font = Font::embedded();
+        let text = "word ".repeat(200);
+        let plan = plan_layout(
+            &font,
+            &text,
+            Orientation::Landscape,
+            LayoutOptions::default(),
+            1,
+        );
+        assert_eq!(plan.font_size, MIN_FONT_SIZE);
+        assert!(plan.pages.len() > 1);
+    }
+
+    #[test]
+    fn test_parse_markup_styles() {
+        let spans = parse_markup("build: {red}FAILED{/} in **2s**");
+        let texts: Vec<&str> = spans.iter().map(|s| s.text.as_str()).collect();
+        assert_eq!(texts, ["build: ", "FAILED", " in ", "2s"]);
+        assert_eq!(spans[1].style.color, Some(Rgb([255, 40, 40])));
+        assert_eq!(spans[2].style, Style::default());
+        assert!(spans[3].style.bold);
+    }
+
+    #[test]
+    fn test_parse_markup_nested_tags() {
+        let spans = parse_markup("{size=20}{#00ff00}a{/}b{/}c");
+        assert_eq!(spans[0].style.size, Some(20.0));
+        assert_eq!(spans[0].style.color, Some(Rgb([0, 255, 0])));
+        assert_eq!(spans[1].style.size, Some(20.0));
+        assert_eq!(spans[1].style.color, None);
+        assert_eq!(spans[2].style, Style::default());
+    }
+
+    #[test]
+    fn test_unknown_tags_and_escapes_are_literal() {
+        let spans = parse_markup("{\"a\": 1} \\** \\{red}");
+        assert_eq!(spans.len(), 1);
+        assert_eq!(spans[0].text, "{\"a\": 1} ** {red}");
+    }
+
+    #[test]
+    fn test_escape_markup_round_trip() {
+        let text = "a**b {red} c\\d";
+        let spans = parse_markup(&escape_markup(text));
+        assert_eq!(spans.len(), 1);
+        assert_eq!(spans[0].text, text);
+    }
+
+    #[test]
+    fn test_parse_markup_icons() {
+        let wifi = crate::icons::icon("wifi").unwrap().char();
+        let mail = crate::icons::icon("mail").unwrap().char();
+        let spans = parse_markup(":wifi: 12:34 :nope: \u{1F4E7}\u{FE0F}");
+        assert_eq!(spans[0].text, format!("{wifi} 12:34 :nope: {mail}"));
+        // Escaped references stay literal, and escaping keeps them literal
+        assert_eq!(parse_markup("\\:wifi:")[0].text, ":wifi:");
+        assert_eq!(
+            parse_markup(&escape_markup("now :wifi:"))[0].text,
+            "now :wifi:"
+        );
+        assert_eq!(escape_markup("12:34"), "12:34");
+    }
+
+    #[test]
+    fn test_to_markup_round_trip() {
+        let spans = parse_markup("x {blue}**y**{size=30}z{/}{/}");
+        assert_eq!(parse_markup(&to_markup(&spans)), spans);
+    }
+
+    #[test]
+    fn test_wrap_preserves_spans_across_lines() {
+        let text = "{red}one two three four five six seven eight nine ten{/}";
+        let pages = split_into_pages(text, 14.0);
+        for line in pages.iter().flat_map(|page| page.lines()) {
+            for span in parse_markup(line) {
+                assert_eq!(span.style.color, Some(Rgb([255, 40, 40])), "{:?}", span);
+            }
+        }
+    }
+
+    #[test]
+    fn test_large_spans_paginate_by_height() {
+        let font = Font::embedded();
+        let options = LayoutOptions::default();
+        let small =
+            split_into_pages_oriented(&font, "a\nb\nc\nd", 14.0, Orientation::Landscape, options);
+        let large = split_into_pages_oriented(
+            &font,
+            "{size=40}a\nb\nc\nd{/}",
+            14.0,
+            Orientation::Landscape,
+            options,
+        );
+        assert_eq!(small.len(), 1);
+        assert!(large.len() > 1);
     }
 }
@@ -0,0 +1,109 @@
+use image::Rgb;
+
+/// Foreground/background colors used when rendering text
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Theme {
+    pub foreground: Rgb<u8>,
+    pub background: Rgb<u8>,
+}
+
+pub const WHITE: Rgb<u8> = Rgb([255, 255, 255]);
+pub const BLACK: Rgb<u8> = Rgb([0, 0, 0]);
+pub const YELLOW: Rgb<u8> = Rgb([255, 220, 0]);
+pub const RED: Rgb<u8> = Rgb([255, 40, 40]);
+
+impl Default for Theme {
+    /// White text on black (the original look)
+    fn default() -> Self {
+        Theme {
+            foreground: WHITE,
+            background: BLACK,
+        }
+    }
+}
+
+impl Theme {
+    pub fn new(foreground: Rgb<u8>, background: Rgb<u8>) -> Self {
+        Theme {
+            foreground,
+            background,
+        }
+    }
+
+    /// Same theme with a different text color
+    pub fn with_foreground(self, foreground: Rgb<u8>) -> Self {
+        Theme { foreground, ..self }
+    }
+
+    /// Swap foreground and background
+    pub fn inverted(self) -> Self {
+        Theme {
+            foreground: self.background,
+            background: self.foreground,
+        }
+    }
+}
+
+/// Parse a color name (`red`, `cyan`, ...) or hex value (`#RRGGBB` / `RRGGBB`)
+pub fn parse_color(s: &str) -> Result<Rgb<u8>, String> {
+    let named = match s.to_ascii_lowercase().as_str() {
+        "white" => Some(WHITE),
+        "black" => Some(BLACK),
+        "red" => Some(RED),
+        "green" => Some(Rgb([0, 220, 80])),
+        "blue" => Some(Rgb([40, 120, 255])),
+        "yellow" => Some(YELLOW),
+        "cyan" => Some(Rgb([0, 220, 255])),
+        "magenta" => Some(Rgb([255, 0, 255])),
+        "orange" => Some(Rgb([255, 140, 0])),
+        "gray" | "grey" => Some(Rgb([128, 128, 128])),
+        _ => None,
+    };
+    if let Some(color) = named {
+        return Ok(color);
+    }
+
+    let hex = s.strip_prefix('#').unwrap_or(s);
+    if hex.len() != 6 || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(format!("'{}' is not a color name or #RRGGBB value", s));
+    }
+    let channel = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).unwrap_or(0);
+    Ok(Rgb([channel(0), channel(2), channel(4)]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_theme_is_white_on_black() {
+        let theme = Theme::default();
+        assert_eq!(theme.foreground, WHITE);
+        assert_eq!(theme.background, BLACK);
+    }
+
+    #[test]
+    fn test_inverted_swaps_colors() {
+        let theme = Theme::new(RED, BLACK).inverted();
+        assert_eq!(theme.foreground, BLACK);
+        assert_eq!(theme.background, RED);
+    }
+
+    #[test]
+    fn test_parse_named_color() {
+        assert_eq!(parse_color("red"), Ok(RED));
+        assert_eq!(parse_color("Yellow"), Ok(YELLOW));
+    }
+
+    #[test]
+    fn test_parse_hex_color() {
+        assert_eq!(parse_color("#102030"), Ok(Rgb([0x10, 0x20, 0x30])));
+        assert_eq!(parse_color("ff0000"), Ok(Rgb([255, 0, 0])));
+    }
+
+    #[test]
+    fn test_parse_invalid_color() {
+        assert!(parse_color("notacolor").is_err());
+        assert!(parse_color("#12345").is_err());
+    }
+}